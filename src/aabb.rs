@@ -0,0 +1,123 @@
+use crate::transform::Matrix4;
+use crate::vec3::Vec3;
+use crate::Ray;
+
+/// An axis-aligned box from corner `min` to corner `max`, used to skip whole
+/// groups of objects that a ray misses.
+#[derive(Debug, Copy, Clone)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+/// Flat boxes are thickened to this size so rays still hit them.
+const MIN_THICKNESS: f64 = 1e-4;
+
+impl Aabb {
+    /// The smallest box around all of `points`, which must not be empty.
+    pub fn from_points(points: &[Vec3]) -> Aabb {
+        let (min, max) = points[1..]
+            .iter()
+            .fold((points[0], points[0]), |(min, max), p| {
+                (min.min(*p), max.max(*p))
+            });
+        let pad = |lo: f64, hi: f64| {
+            if hi - lo < MIN_THICKNESS {
+                MIN_THICKNESS / 2.
+            } else {
+                0.
+            }
+        };
+        let pad = Vec3(
+            pad(min.x(), max.x()),
+            pad(min.y(), max.y()),
+            pad(min.z(), max.z()),
+        );
+        Aabb {
+            min: min - pad,
+            max: max + pad,
+        }
+    }
+
+    /// The smallest box holding both `a` and `b`.
+    pub fn surrounding(a: &Aabb, b: &Aabb) -> Aabb {
+        Aabb {
+            min: a.min.min(b.min),
+            max: a.max.max(b.max),
+        }
+    }
+
+    /// The box shared by `a` and `b`, or None if they don't overlap.
+    pub fn intersection(a: &Aabb, b: &Aabb) -> Option<Aabb> {
+        let (min, max) = (a.min.max(b.min), a.max.min(b.max));
+        if min.x() > max.x() || min.y() > max.y() || min.z() > max.z() {
+            None
+        } else {
+            Some(Aabb { min, max })
+        }
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) / 2.
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let d = self.max - self.min;
+        2. * (d.x() * d.y() + d.y() * d.z() + d.z() * d.x())
+    }
+
+    pub fn corners(&self) -> [Vec3; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Vec3(a.x(), a.y(), a.z()),
+            Vec3(b.x(), a.y(), a.z()),
+            Vec3(a.x(), b.y(), a.z()),
+            Vec3(b.x(), b.y(), a.z()),
+            Vec3(a.x(), a.y(), b.z()),
+            Vec3(b.x(), a.y(), b.z()),
+            Vec3(a.x(), b.y(), b.z()),
+            Vec3(b.x(), b.y(), b.z()),
+        ]
+    }
+
+    /// The box around this one after it is moved by `matrix`.
+    pub fn transformed(&self, matrix: &Matrix4) -> Aabb {
+        let corners = self.corners();
+        Aabb::from_points(
+            &corners
+                .iter()
+                .map(|c| matrix.transform_point(c))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Whether the ray passes through the box between `t_min` and `t_max`.
+    pub fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.clip(r, t_min, t_max).is_some()
+    }
+
+    /// The part of [t_min, t_max] the ray spends inside the box, found by
+    /// clipping it against each pair of parallel faces in turn.
+    pub fn clip(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let (o, d) = (r.origin(), r.direction());
+        let slabs = [
+            (o.x(), d.x(), self.min.x(), self.max.x()),
+            (o.y(), d.y(), self.min.y(), self.max.y()),
+            (o.z(), d.z(), self.min.z(), self.max.z()),
+        ];
+        let (mut t0, mut t1) = (t_min, t_max);
+        for &(o, d, lo, hi) in &slabs {
+            let inverse = 1. / d;
+            let (mut near, mut far) = ((lo - o) * inverse, (hi - o) * inverse);
+            if inverse < 0. {
+                std::mem::swap(&mut near, &mut far);
+            }
+            t0 = t0.max(near);
+            t1 = t1.min(far);
+            if t1 <= t0 {
+                return None;
+            }
+        }
+        Some((t0, t1))
+    }
+}
@@ -0,0 +1,422 @@
+use std::f64::consts::PI;
+
+use crate::environment::Environment;
+use crate::integrator::Integrator;
+use crate::onb::Onb;
+use crate::sampler::{cosine_direction, Sampler};
+use crate::vec3::Vec3;
+use crate::{HitRecord, Hittable, Ray};
+
+/// Most vertices a path can have past the camera: its bounces and the one
+/// on the light. Every vertex of a camera path is joined to every vertex of
+/// a light path, so the work grows with the square of this.
+const MAX_VERTICES: usize = 16;
+
+/// Bidirectional path tracing, after Veach's thesis: paths are followed out
+/// from the lights as well as back from the camera, and each vertex of one
+/// joined to each vertex of the other. A complete path could have been made
+/// by many of these joins, and multiple importance sampling weights each by
+/// how likely it was to be made that way, so light a camera path alone
+/// rarely finds, like a room lit by a lamp facing the ceiling or a caustic
+/// focused through glass seen in a mirror or off a wall, comes through far
+/// less noisily.
+///
+/// Light paths aren't joined to the camera itself, so caustics seen
+/// directly still come only from camera paths finding the light. Lights
+/// give off light from their front faces only, and nested transparent
+/// objects are each taken to border on air.
+#[derive(Copy, Clone)]
+pub struct Bidirectional<'a> {
+    pub world: &'a dyn Hittable,
+    /// The emitters light paths start from, also in `world`.
+    pub lights: &'a dyn Hittable,
+    /// The light arriving from far away, seen by rays that miss everything.
+    pub environment: &'a dyn Environment,
+}
+
+/// A point where a camera or light path meets a surface.
+struct Vertex<'a> {
+    hit: HitRecord<'a>,
+    /// The ray the path reached the vertex along.
+    incoming: Ray,
+    /// For camera paths, what the light found at the vertex is multiplied
+    /// by on its way back to the camera; for light paths, the light the
+    /// path carries to it. Both divided by the density of the path so far.
+    beta: Vec3,
+    /// Whether the surface only scatters in directions it chooses itself,
+    /// so the vertex can't be joined to another path.
+    delta: bool,
+    /// Density, per unit area, with which the path picked the vertex, and
+    /// with which a path going the other way would.
+    pdf_fwd: f64,
+    pdf_rev: f64,
+    /// For a point on a light, the density, per unit area, with which
+    /// sampling the lights from the next vertex picks it, as joining it on
+    /// its own to a camera path does.
+    pdf_next_event: f64,
+}
+
+impl Vertex<'_> {
+    /// Density, per unit area at `to`, with which a path reaching the
+    /// vertex from `from` scatters on to `to`.
+    fn pdf(&self, from: &Vec3, to: &Vertex) -> f64 {
+        let r = Ray {
+            a: *from,
+            b: self.hit.p - *from,
+            time: self.incoming.time(),
+            wavelengths: self.incoming.wavelengths,
+        };
+        let direction = to.hit.p - self.hit.p;
+        match self.hit.material.pdf(&r, &self.hit) {
+            Some(pdf) => pdf.value(&direction) * to.cosine(&direction) / direction.squared_length(),
+            None => 0.,
+        }
+    }
+
+    /// Density, per unit area at `to`, with which the vertex, as a point on
+    /// a light, sends light paths to `to`.
+    fn pdf_emission(&self, to: &Vertex) -> f64 {
+        let direction = to.hit.p - self.hit.p;
+        let cosine = (direction.unit().dot(self.hit.normal) / PI).max(0.);
+        cosine * to.cosine(&direction) / direction.squared_length()
+    }
+
+    fn cosine(&self, direction: &Vec3) -> f64 {
+        direction.unit().dot(self.hit.normal).abs()
+    }
+
+    /// How much of the light reaching the vertex from `direction` it sends
+    /// back along its path.
+    fn eval(&self, direction: &Vec3) -> Vec3 {
+        self.hit.material.eval(&self.incoming, &self.hit, direction)
+    }
+}
+
+impl<'a> Bidirectional<'a> {
+    /// Follows a path on from `r`, adding the vertices where it meets
+    /// surfaces to `path` until it has `MAX_VERTICES` or stops. `beta` is
+    /// its throughput so far and `pdf` the density, per unit solid angle,
+    /// of the direction of `r`, zero if a specular surface chose it. If the
+    /// path leaves the scene, returns the ray it left along with its
+    /// throughput then.
+    fn walk(
+        &self,
+        mut r: Ray,
+        mut beta: Vec3,
+        mut pdf: f64,
+        sampler: &mut dyn Sampler,
+        path: &mut Vec<Vertex<'a>>,
+    ) -> Option<(Ray, Vec3)> {
+        while path.len() < MAX_VERTICES {
+            let hit = match self.world.hit(&r, 0.0001, f64::MAX) {
+                Some(hit) => hit,
+                None => return Some((r, beta)),
+            };
+            if !hit.front_face && !hit.material.two_sided() {
+                return None;
+            }
+            let mut vertex = Vertex {
+                hit,
+                incoming: r,
+                beta,
+                delta: false,
+                pdf_fwd: 0.,
+                pdf_rev: 0.,
+                pdf_next_event: 0.,
+            };
+            vertex.pdf_fwd =
+                pdf * vertex.cosine(r.direction()) / (vertex.hit.p - r.a).squared_length();
+            let scatter = vertex.hit.material.scatter(&r, &vertex.hit, sampler);
+            path.push(vertex);
+            let scatter = match scatter {
+                Some(scatter)
+                    if path[path.len() - 1]
+                        .hit
+                        .consistent(scatter.scattered.direction()) =>
+                {
+                    scatter
+                }
+                _ => return None,
+            };
+            let direction = *scatter.scattered.direction();
+            let n = path.len();
+            path[n - 1].delta = scatter.pdf.is_none();
+            pdf = scatter.pdf.as_ref().map_or(0., |pdf| pdf.value(&direction));
+            // how likely a path coming back the other way would have been
+            // to reach the vertex before
+            if n >= 2 {
+                let (before, current) = path.split_at_mut(n - 1);
+                let current = &current[0];
+                before[n - 2].pdf_rev = current.pdf(&(current.hit.p + direction), &before[n - 2]);
+            }
+            beta = beta * scatter.weight();
+            r = scatter.scattered;
+        }
+        None
+    }
+
+    /// A path starting from a point picked on one of the lights, at the
+    /// time and wavelengths of camera ray `camera`.
+    fn light_path(&self, camera: &Ray, sampler: &mut dyn Sampler) -> Vec<Vertex<'a>> {
+        let mut path = Vec::with_capacity(MAX_VERTICES);
+        let (time, wavelengths) = (camera.time(), camera.wavelengths);
+        let (mut hit, density) = match self.lights.sample_surface(sampler) {
+            Some(sample) => sample,
+            None => return path,
+        };
+        hit.wavelengths = wavelengths;
+        let emitted = hit.emitted() / density;
+        let direction = Onb::from_w(&hit.normal).local(&cosine_direction(sampler.get_2d()));
+        let r = Ray {
+            a: hit.p,
+            b: direction,
+            time,
+            wavelengths,
+        };
+        path.push(Vertex {
+            hit,
+            incoming: Ray {
+                a: hit.p + hit.normal,
+                b: -hit.normal,
+                time,
+                wavelengths,
+            },
+            beta: emitted,
+            delta: false,
+            pdf_fwd: density,
+            pdf_rev: 0.,
+            pdf_next_event: 0.,
+        });
+        // cosine weighted directions leave the light times pi
+        let pdf = direction.dot(hit.normal) / PI;
+        self.walk(r, PI * emitted, pdf, sampler, &mut path);
+        if path.len() >= 2 {
+            path[0].pdf_next_event = self.pdf_light(&path[0], &path[1].hit.p);
+        }
+        path
+    }
+
+    /// Density, per unit area, with which sampling the lights from `from`
+    /// picks `light`.
+    fn pdf_light(&self, light: &Vertex, from: &Vec3) -> f64 {
+        let direction = light.hit.p - *from;
+        self.lights.pdf_value(from, &direction) * light.cosine(&direction)
+            / direction.squared_length()
+    }
+
+    /// Density, per unit area, with which starting a light path picks
+    /// `light`, seen from `from`.
+    fn pdf_origin(&self, light: &Vertex, from: &Vec3) -> f64 {
+        self.lights.surface_pdf(from, &(light.hit.p - *from))
+    }
+
+    /// Whether nothing stands between points `a` and `b`.
+    fn visible(&self, a: &Vec3, b: &Vec3, time: f64) -> bool {
+        let r = Ray {
+            a: *a,
+            b: *b - *a,
+            time,
+            wavelengths: None,
+        };
+        self.world.hit(&r, 0.0001, 0.9999).is_none()
+    }
+
+    /// The light the first `s` vertices of `light` carry to the first `t`
+    /// of `camera`, joined between the last of each, weighted by how much
+    /// more likely this join was to make the path than the others.
+    fn connect(
+        &self,
+        camera: &[Vertex],
+        light: &[Vertex],
+        s: usize,
+        t: usize,
+        sampler: &mut dyn Sampler,
+    ) -> Vec3 {
+        let black = Vec3(0., 0., 0.);
+        let pt = &camera[t - 1];
+        let (time, wavelengths) = (pt.incoming.time(), pt.incoming.wavelengths);
+        match s {
+            // the camera path found a light itself
+            0 => {
+                if !pt.hit.front_face {
+                    return black;
+                }
+                let emitted = pt.hit.emitted();
+                if emitted.squared_length() == 0. {
+                    return black;
+                }
+                pt.beta * emitted * self.weight(camera, light, None, s, t)
+            }
+            // a point picked on a light as seen from the camera path
+            1 => {
+                if pt.delta {
+                    return black;
+                }
+                let p = pt.hit.p;
+                let direction = self.lights.random(&p, sampler);
+                let density = self.lights.pdf_value(&p, &direction);
+                if density <= 0. {
+                    return black;
+                }
+                let shadow_ray = Ray {
+                    a: p,
+                    b: direction,
+                    time,
+                    wavelengths,
+                };
+                let hit = match self.world.hit(&shadow_ray, 0.0001, f64::MAX) {
+                    Some(hit) if hit.front_face => hit,
+                    _ => return black,
+                };
+                let emitted = hit.emitted();
+                let contribution = pt.beta * pt.eval(&direction) * emitted / density;
+                if contribution.squared_length() == 0. {
+                    return black;
+                }
+                let mut sampled = Vertex {
+                    hit,
+                    incoming: shadow_ray,
+                    beta: emitted / density,
+                    delta: false,
+                    pdf_fwd: 0.,
+                    pdf_rev: 0.,
+                    pdf_next_event: 0.,
+                };
+                // weighed like the start of a light path, beside how it
+                // was picked here
+                sampled.pdf_fwd = self.pdf_origin(&sampled, &p);
+                sampled.pdf_next_event = self.pdf_light(&sampled, &p);
+                contribution * self.weight(camera, light, Some(&sampled), s, t)
+            }
+            // the two paths joined between their ends
+            _ => {
+                let qs = &light[s - 1];
+                if pt.delta || qs.delta {
+                    return black;
+                }
+                let d = pt.hit.p - qs.hit.p;
+                let contribution =
+                    qs.beta * qs.eval(&d) * pt.eval(&-d) * pt.beta / d.squared_length();
+                if contribution.squared_length() == 0. || !self.visible(&qs.hit.p, &pt.hit.p, time)
+                {
+                    return black;
+                }
+                contribution * self.weight(camera, light, None, s, t)
+            }
+        }
+    }
+
+    /// The share of the path made by joining the first `s` vertices of
+    /// `light`, or `sampled` in place of its first, to the first `t` of
+    /// `camera` that this join gets, by the power heuristic over the
+    /// densities every other join would have made it with.
+    fn weight(
+        &self,
+        camera: &[Vertex],
+        light: &[Vertex],
+        sampled: Option<&Vertex>,
+        s: usize,
+        t: usize,
+    ) -> f64 {
+        if s + t == 1 {
+            return 1.;
+        }
+        let pt = &camera[t - 1];
+        let qs = match s {
+            0 => None,
+            1 => sampled,
+            _ => Some(&light[s - 1]),
+        };
+        // the densities at the ends of the two paths and their neighbours
+        // change with how the paths are joined
+        let pt_rev = match qs {
+            Some(qs) if s == 1 => qs.pdf_emission(pt),
+            Some(qs) => qs.pdf(&qs.incoming.a, pt),
+            None => self.pdf_light(pt, &pt.incoming.a),
+        };
+        if s == 0 && pt_rev == 0. {
+            // not one of the lights, so only found this way
+            return 1.;
+        }
+        let pt_minus_rev = match (qs, t) {
+            (_, 1) => 0.,
+            (Some(qs), _) => pt.pdf(&qs.hit.p, &camera[t - 2]),
+            (None, _) => pt.pdf_emission(&camera[t - 2]),
+        };
+        let qs_rev = qs.map_or(0., |qs| pt.pdf(&pt.incoming.a, qs));
+        let qs_minus_rev = match qs {
+            Some(qs) if s >= 2 => qs.pdf(&pt.hit.p, &light[s - 2]),
+            _ => 0.,
+        };
+        // the vertex on the light is picked by sampling the lights from the
+        // next one when it is joined on its own, and from all the lights
+        // when it starts a longer light path
+        let (next_event, origin) = match qs {
+            None => (pt_rev, self.pdf_origin(pt, &pt.incoming.a)),
+            Some(qs) => {
+                let start = if s == 1 { qs } else { &light[0] };
+                (start.pdf_next_event, start.pdf_fwd)
+            }
+        };
+
+        // densities of specular choices are left out, cancelling along the
+        // chain of ratios
+        let remap = |pdf: f64| if pdf == 0. { 1. } else { pdf };
+        let mut sum = 0.;
+        // moving the join back along the camera path, one vertex at a time
+        let mut ratio = 1.;
+        for k in (1..t).rev() {
+            let rev = match t - 1 - k {
+                0 => pt_rev,
+                1 => pt_minus_rev,
+                _ => camera[k].pdf_rev,
+            };
+            ratio *= (remap(rev) / remap(camera[k].pdf_fwd)).powi(2);
+            if s + t - k == 2 {
+                ratio *= (remap(origin) / remap(next_event)).powi(2);
+            }
+            let delta = k != t - 1 && camera[k].delta;
+            if !delta && !camera[k - 1].delta {
+                sum += ratio;
+            }
+        }
+        // and back along the light path
+        let mut ratio = 1.;
+        for i in (0..s).rev() {
+            let (rev, fwd, delta) = match s - 1 - i {
+                0 => (qs_rev, qs.map_or(0., |qs| qs.pdf_fwd), false),
+                1 => (qs_minus_rev, light[i].pdf_fwd, light[i].delta),
+                _ => (light[i].pdf_rev, light[i].pdf_fwd, light[i].delta),
+            };
+            let fwd = if i == 0 { next_event } else { fwd };
+            ratio *= (remap(rev) / remap(fwd)).powi(2);
+            if i == 1 {
+                ratio *= (remap(next_event) / remap(origin)).powi(2);
+            }
+            let delta_before = i > 0 && light[i - 1].delta;
+            if !delta && !delta_before {
+                sum += ratio;
+            }
+        }
+        1. / (1. + sum)
+    }
+}
+
+impl Integrator for Bidirectional<'_> {
+    fn radiance(&self, r: Ray, sampler: &mut dyn Sampler) -> Vec3 {
+        let mut camera = Vec::with_capacity(MAX_VERTICES);
+        let mut radiance = match self.walk(r, Vec3(1., 1., 1.), 0., sampler, &mut camera) {
+            Some((escaped, beta)) => beta * self.environment.along(&escaped),
+            None => Vec3(0., 0., 0.),
+        };
+        let light = self.light_path(&r, sampler);
+        for t in 1..=camera.len() {
+            // picking a point on a light from the camera path doesn't need
+            // the light path
+            for s in 0..=light.len().max(1).min(MAX_VERTICES - t) {
+                radiance = radiance + self.connect(&camera, &light, s, t, sampler);
+            }
+        }
+        radiance
+    }
+}
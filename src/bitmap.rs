@@ -0,0 +1,147 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use image::codecs::hdr::HdrDecoder;
+
+use crate::vec3::Vec3;
+
+/// A grid of RGB values, stored row by row from the top of the image.
+pub struct Bitmap {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Vec3>,
+}
+
+impl Bitmap {
+    /// Load a PNG or JPEG, scaling each channel to [0, 1] without any color
+    /// space conversion, or a Radiance HDR or OpenEXR file, keeping its
+    /// values as they are. Alpha is dropped and grayscale is copied to all
+    /// channels.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Bitmap, image::ImageError> {
+        let path = path.as_ref();
+        let hdr = path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("hdr"));
+        if hdr {
+            return Bitmap::load_hdr(path);
+        }
+        let img = image::open(path)?.to_rgb32f();
+        let (width, height) = img.dimensions();
+        let pixels = img
+            .pixels()
+            .map(|p| Vec3(f64::from(p[0]), f64::from(p[1]), f64::from(p[2])))
+            .collect();
+        Ok(Bitmap {
+            width: width as usize,
+            height: height as usize,
+            pixels,
+        })
+    }
+
+    /// Radiance HDR files, which `image::open` would tone map down to eight
+    /// bits a channel.
+    fn load_hdr(path: &Path) -> Result<Bitmap, image::ImageError> {
+        let file = BufReader::new(File::open(path)?);
+        let decoder = HdrDecoder::new(file)?;
+        let meta = decoder.metadata();
+        let pixels = decoder
+            .read_image_hdr()?
+            .into_iter()
+            .map(|p| Vec3(f64::from(p[0]), f64::from(p[1]), f64::from(p[2])))
+            .collect();
+        Ok(Bitmap {
+            width: meta.width as usize,
+            height: meta.height as usize,
+            pixels,
+        })
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Vec3 {
+        self.pixels[y * self.width + x]
+    }
+
+    /// The pixel nearest to texture coordinates (u, v), with v = 0 at the
+    /// bottom of the image; coordinates outside [0, 1] wrap around.
+    pub fn sample(&self, u: f64, v: f64) -> Vec3 {
+        let u = u - u.floor();
+        let v = v - v.floor();
+        let x = ((u * self.width as f64) as usize).min(self.width - 1);
+        let y = (((1. - v) * self.height as f64) as usize).min(self.height - 1);
+        self.pixel(x, y)
+    }
+
+    /// Bilinear interpolation between the four pixels around (u, v), wrapping
+    /// like `sample`.
+    pub fn bilinear(&self, u: f64, v: f64) -> Vec3 {
+        // pixel centers sit at half-integer positions
+        let x = (u - u.floor()) * self.width as f64 - 0.5;
+        let y = (1. - (v - v.floor())) * self.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let wrap = |i: f64, n: usize| i.rem_euclid(n as f64) as usize;
+        let (x0, x1) = (wrap(x0, self.width), wrap(x0 + 1., self.width));
+        let (y0, y1) = (wrap(y0, self.height), wrap(y0 + 1., self.height));
+        let top = (1. - fx) * self.pixel(x0, y0) + fx * self.pixel(x1, y0);
+        let bottom = (1. - fx) * self.pixel(x0, y1) + fx * self.pixel(x1, y1);
+        (1. - fy) * top + fy * bottom
+    }
+
+    /// Half the size in each direction, each pixel averaging a 2x2 block.
+    fn downsample(&self) -> Bitmap {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let (x0, y0) = (2 * x, 2 * y);
+                let x1 = (x0 + 1).min(self.width - 1);
+                let y1 = (y0 + 1).min(self.height - 1);
+                let sum = self.pixel(x0, y0)
+                    + self.pixel(x1, y0)
+                    + self.pixel(x0, y1)
+                    + self.pixel(x1, y1);
+                pixels.push(sum / 4.);
+            }
+        }
+        Bitmap {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+/// A mip pyramid: the full image followed by successively halved copies, down
+/// to a single pixel, for looking up the average over a region cheaply.
+pub struct MipMap {
+    pub levels: Vec<Bitmap>,
+}
+
+impl MipMap {
+    pub fn new(image: Bitmap) -> MipMap {
+        let mut levels = vec![image];
+        loop {
+            let last = &levels[levels.len() - 1];
+            if last.width == 1 && last.height == 1 {
+                break;
+            }
+            let next = last.downsample();
+            levels.push(next);
+        }
+        MipMap { levels }
+    }
+
+    /// Trilinear lookup of the region around (u, v) about `width` across in
+    /// texture coordinates: bilinear within the two levels whose pixels are
+    /// nearest that size, blended between them.
+    pub fn sample(&self, u: f64, v: f64, width: f64) -> Vec3 {
+        let base = &self.levels[0];
+        let texels = width * base.width.max(base.height) as f64;
+        let level = texels.max(1.).log2().min((self.levels.len() - 1) as f64);
+        let lower = level.floor() as usize;
+        let upper = (lower + 1).min(self.levels.len() - 1);
+        let t = level - lower as f64;
+        (1. - t) * self.levels[lower].bilinear(u, v) + t * self.levels[upper].bilinear(u, v)
+    }
+}
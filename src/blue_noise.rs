@@ -0,0 +1,387 @@
+use crate::sampler::{RandomSampler, Sampler};
+
+/// Width and height of the mask, which tiles the image.
+pub const SIZE: usize = 64;
+
+/// Spread of the Gaussian by which points push each other away, in pixels.
+const SIGMA: f64 = 1.5;
+
+/// The value of the blue noise mask at pixel (x, y), in [0, 1). Values
+/// are spread evenly, and neighbouring pixels' values differ as much as
+/// they can, so noise shifted by them has no low frequencies for the eye
+/// to pick out.
+pub fn value(x: u32, y: u32) -> f64 {
+    let rank = MASK[(y as usize % SIZE) * SIZE + x as usize % SIZE];
+    (rank as f64 + 0.5) / (SIZE * SIZE) as f64
+}
+
+/// A mask made by Ulichney's void-and-cluster method: the pixels ranked by
+/// the order they go into a pattern that is kept as even as possible,
+/// always taking the middle of the largest gap. `MASK` is what this
+/// returns, written out, so renders needn't build it; print it to
+/// regenerate `MASK` after changing `SIZE` or `SIGMA`.
+pub fn void_and_cluster() -> Vec<u16> {
+    let n = SIZE * SIZE;
+    let mut pattern = Pattern::new();
+    let mut random = RandomSampler::new(0, 0);
+    while pattern.count < n / 10 {
+        let i = (random.get_1d() * n as f64) as usize;
+        if !pattern.on[i] {
+            pattern.set(i, true);
+        }
+    }
+    // even out the starting points, moving the most crowded one into the
+    // largest gap until it would go straight back
+    loop {
+        let cluster = pattern.tightest_cluster();
+        pattern.set(cluster, false);
+        let void = pattern.largest_void();
+        pattern.set(void, true);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut rank = vec![0; n];
+    // the starting points rank by taking them out most crowded first
+    let mut taken = pattern.clone();
+    for r in (0..pattern.count).rev() {
+        let cluster = taken.tightest_cluster();
+        taken.set(cluster, false);
+        rank[cluster] = r as u16;
+    }
+    // and the rest by filling in the largest gaps
+    for r in pattern.count..n {
+        let void = pattern.largest_void();
+        pattern.set(void, true);
+        rank[void] = r as u16;
+    }
+    rank
+}
+
+/// Pixels of the mask chosen so far, and how crowded each pixel is by
+/// them, wrapping around the edges.
+#[derive(Clone)]
+struct Pattern {
+    on: Vec<bool>,
+    count: usize,
+    energy: Vec<f64>,
+    /// How much a point crowds a pixel, by their offset.
+    kernel: Vec<f64>,
+}
+
+impl Pattern {
+    fn new() -> Pattern {
+        let wrapped = |d: usize| d.min(SIZE - d) as f64;
+        let kernel = (0..SIZE * SIZE)
+            .map(|i| {
+                let (dx, dy) = (wrapped(i % SIZE), wrapped(i / SIZE));
+                (-(dx * dx + dy * dy) / (2. * SIGMA * SIGMA)).exp()
+            })
+            .collect();
+        Pattern {
+            on: vec![false; SIZE * SIZE],
+            count: 0,
+            energy: vec![0.; SIZE * SIZE],
+            kernel,
+        }
+    }
+
+    fn set(&mut self, i: usize, on: bool) {
+        self.on[i] = on;
+        let sign = if on { 1. } else { -1. };
+        if on {
+            self.count += 1;
+        } else {
+            self.count -= 1;
+        }
+        let (x, y) = (i % SIZE, i / SIZE);
+        for (j, energy) in self.energy.iter_mut().enumerate() {
+            let dx = (j % SIZE + SIZE - x) % SIZE;
+            let dy = (j / SIZE + SIZE - y) % SIZE;
+            *energy += sign * self.kernel[dy * SIZE + dx];
+        }
+    }
+
+    /// The most crowded point of the pattern.
+    fn tightest_cluster(&self) -> usize {
+        self.most(true, |a, b| a > b)
+    }
+
+    /// The least crowded pixel not in the pattern.
+    fn largest_void(&self) -> usize {
+        self.most(false, |a, b| a < b)
+    }
+
+    fn most(&self, on: bool, better: impl Fn(f64, f64) -> bool) -> usize {
+        let mut best = None;
+        for (i, &energy) in self.energy.iter().enumerate() {
+            if self.on[i] == on && best.is_none_or(|b: usize| better(energy, self.energy[b])) {
+                best = Some(i);
+            }
+        }
+        best.unwrap()
+    }
+}
+
+/// The mask, row by row: the rank of each pixel from 0 to `SIZE * SIZE - 1`,
+/// as made by `void_and_cluster`.
+#[rustfmt::skip]
+const MASK: [u16; SIZE * SIZE] = [
+     939, 4012,  279, 2735, 3793,  552, 1213, 3656, 2206, 1114, 3610, 2305, 1539,  146, 3146, 2341,
+    3420, 1907, 1170, 4019, 3047,  468, 3363, 2016, 1143, 3783, 2078, 3344, 4011,  132, 2422, 3782,
+    1178, 2648, 1435, 2369, 1864,  198, 3452, 1549, 2269,   61, 1073, 3855, 3113,  831, 1593, 2694,
+      84, 3801, 2812, 2236, 3499, 1548, 4050,    7, 1619, 2948, 3555,  263, 3822, 2088,   98, 3277,
+     514, 2468, 3229, 1311, 3408, 1943, 3127,  222, 2681, 3237,  479, 1260, 2804, 4094,  658, 1341,
+     268, 2864, 1504,  200, 2467, 1657,  837, 2616,  393, 1573,  780, 2531, 1802, 3082, 1412,  450,
+    1759, 3588,  345, 3233,  756, 3084, 2034,  500,  906, 3289, 2535, 2112, 2751,  236, 3686, 1107,
+    3331,  874, 1724, 1221,  589, 1991, 1091, 3256, 2134,  907, 1206, 2414, 1568, 2928, 2507, 1382,
+    3569, 1171, 1789,  711, 2308,  347, 1560, 3986,  804, 1677, 3871, 2087,  903, 1825, 3281, 2188,
+    3724,  738, 3191, 2068, 3451, 1357, 2974, 3975, 2244, 3434, 2869,  217, 1173,  681, 2752, 3359,
+    2290,  655, 2053, 4062, 2714, 1025, 3796, 2924, 1807, 3598, 1406,  389, 1688, 3471, 2227,  541,
+    2001, 2951,  266, 3868, 2593, 3673, 2858,  508, 2572, 3937, 1883, 3313,  412,  768, 3912, 1941,
+    2217, 2942,   29, 2677, 3886,  971, 2872, 2067, 1288, 2559,   12, 3048, 3572,  361, 2661, 1068,
+    1762, 2562, 3906, 1014,  548, 3640,  176, 1836,  603, 1337, 3692, 2300, 3307, 3917, 1966,  986,
+    3738, 3011, 1087, 1681,  112, 1499, 2478, 1245,  178, 2664,  725, 3933, 1006, 2912, 1309, 2526,
+    4088, 1463, 2368, 3089,  955,  157, 1840, 1351, 3485,  218,  656, 2780, 3659, 1079, 3140,  251,
+     782, 3739, 3350, 2045, 1364, 3525, 2385,  580, 3182, 3492, 1872,  700, 2469, 1564, 3424,  111,
+    3041, 1293,  325, 1728, 2833, 2324, 1122, 2730, 3227,  979, 1906,  480, 1453, 2610,  401, 1653,
+      50, 1397, 2598, 3411, 2224, 3657,  640, 3243, 4018, 2059, 3155, 2435, 1974,   26, 3267, 1747,
+     334, 3576,  616, 2064, 1673, 3231, 2452,  751, 3050, 1679, 2307, 1329, 2095, 1750, 2693, 1488,
+    2384, 1057, 1601,  540, 3083,  145, 1765, 3809,  320, 1024, 2314, 1410, 3772, 1000, 2072, 3990,
+     610, 2423, 3306, 2139, 3746,  735, 1664, 3815, 2419,   13, 4059, 3056,  887, 2159, 3620, 2846,
+    2367, 3948,  477,  871, 2862,  379, 1721, 2291,  947, 1565,  466, 1228, 3548,  633, 3807,  828,
+    2721, 1083, 3382, 1327, 3967,  350, 3586, 2146, 3867, 1018, 2914, 4063,   47, 3396,  525, 3951,
+    1845,  316, 2494, 4078,  878, 2639, 1150, 2966, 1544, 2767, 4038,  224, 2941,  481, 2818, 1384,
+    1913, 3591,  879, 1464,   74, 3026, 3352,  368, 1418, 2050, 2580, 1639, 3450,  161, 1246, 3221,
+     635, 2005, 3116, 1787, 3901, 1209, 3491, 2997,  289, 2726, 3698, 1788, 3006, 2669, 2042, 1273,
+    3204, 2178,   96, 2564,  792, 2840, 1176, 1541,  123, 2534,  512, 3173,  899, 2512, 1230, 3014,
+    3590, 3298, 2877, 1950, 1467, 3463, 2089,  667, 3533, 2029,  855, 3304, 1749, 3541, 2397,  838,
+    3117,  233, 2774, 4069, 2521, 1208, 1967,  908, 2842, 3558, 1149,  678, 2916, 1827, 3877,  911,
+    1554, 3512, 1137,  181, 2141, 2591,  802, 1931, 3819, 1112, 3308,  166,  924, 1522, 2356,  244,
+    3949, 1834, 2895, 3676, 1501, 2258,  538, 3269, 1826, 3707, 1280, 1984, 1625, 3805, 2147,  188,
+    1550,  714, 1226,  419, 3147,  248, 3931, 2433,   70, 1240, 2658,  587, 2158, 1177,   45, 3865,
+    1594, 2304, 1161, 1853,  484, 3528, 2281, 3968,  554, 3154,  253, 3773, 2373,  492, 2645, 2186,
+     275, 2786, 2429, 3644, 1512, 3283,    0, 1409, 2407,  562, 2091, 2514, 4039,  489, 3662, 3022,
+     600, 1372,  959,  464, 1885, 3123, 3768, 2603,  885, 3416, 2267,  293, 3487,  617, 2785, 1008,
+    3978, 2722, 2093, 3717, 2353, 1803,  839, 3246, 1613, 3736, 3102, 1498, 3944, 2557, 3326, 1990,
+     588, 3719, 3338,  757, 2932, 1596,  172, 2632, 1515, 1863, 2225, 1348,  938, 3649, 1470, 3023,
+    4032, 1820,  452,  926, 2969,  604, 4080, 2851, 3508, 1622, 3092, 1332, 1922, 3343, 1127, 1729,
+    2590, 3592, 3064, 2381, 4058,   63, 1058, 2071,  258, 2827,  739, 3005, 2393, 1450, 3189, 1909,
+    2400,  124, 3410,  981,  565, 2946, 1217, 2737, 1958,  507, 2264,  169,  892, 1700,  385, 2934,
+    1346, 2626,  301, 2117, 3893,  946, 3131, 1236, 3472,  765, 3935, 2715, 3330, 1916,   33, 1168,
+     777, 3368, 1360, 3836, 1737, 2233, 1172, 2026,  916,  228, 3891,  662, 2897,   66, 2705,  746,
+    2218,  162, 1633, 3433,  811, 1413, 2970, 1627, 3913, 1350, 1742, 3989, 1067,   85, 3757,  497,
+    3035, 1300, 1690, 2537, 4022, 1527, 3582,  326, 3878, 1063, 2600, 3228, 3677, 2784, 1086, 3571,
+    2251,  982, 3088, 1416, 2566, 1940, 3762,  363, 2376, 2926,  106, 1606,  440, 3098, 2252, 3514,
+    2592, 2044, 3106, 2472,  267, 2756, 3674,  448, 3296, 2695, 2205, 1041, 3560, 2320, 1510, 3157,
+    3730, 1072, 2058,  376, 2683, 2185, 3578,  597, 3156, 2497,  429, 2007, 3531, 2653, 2163,  940,
+    1817, 3834,  375, 3223, 2023,   97, 2453, 2122,  769, 3356, 1769, 1340, 2047,  669, 3141, 1862,
+     249, 4028, 1746, 3442,   30,  654, 2755, 1702,  994, 1957, 3556, 1189, 2536,  823, 3756,  560,
+    1674,  186,  679, 1194, 3244,  859, 1536, 2545, 1771, 1297, 3682, 1856,  470, 1254, 3966, 1884,
+     503, 2856, 3925, 1272, 3320, 1751,  216, 2306, 1132, 3655,  912, 3217, 1567,  710, 1381, 3631,
+    2604,  787, 2898, 1186,  697, 3370, 1085, 3043, 1459, 2819,  231, 4082,  457, 2366, 1279, 3710,
+     824, 2719,  537, 1136, 2379, 3535, 1344, 3262, 3890,  486, 3152, 2165, 4074, 1744, 2803, 1368,
+    2972, 3982, 2241, 3597, 1932, 3950,   94, 3439,  696, 3032,  130, 2518, 3358, 2838,  250,  963,
+    3373, 1440, 2508,  665, 2939,  980, 3770, 2792, 1860,   36, 2740, 2199,  274, 2859, 3380,  184,
+    3259, 1962, 2322, 1503, 3804, 2749, 1785, 3957,  557, 3498, 2309,  932, 2713, 3388,   59, 2529,
+    1523, 3329, 2226, 3731, 3019,  922, 2098,  245, 2449, 1286, 2666,  668,  179, 1103, 2116,  315,
+    3351, 1066, 2690, 1437,  459, 2907, 2209, 1118, 1993, 4026,  984, 1543,  772, 2073, 3616, 2636,
+    2246,   23, 1822, 3486,  297, 2399, 1321,  712, 3959, 1514, 3441, 1257, 4095, 1783, 2279, 1152,
+     561, 3956,   15, 3124,  353, 2202,  888,  259, 2511, 1659, 1234, 3664, 1847, 1471, 3905, 2086,
+    2991,  173, 1920, 1375,  378, 1811, 3653, 2956,  844, 1833, 3687, 1545, 3400, 3074, 3816, 2499,
+     800, 1791,   68, 3099,  948, 1602, 2627, 3585,  314, 2357, 2800, 3778, 3144, 1675,  571, 1289,
+    3105, 3827,  825, 2106, 4037, 1623, 3417, 2992, 2143,  501, 2457,  687, 3095,  410, 3766, 1620,
+    2426, 1039, 3529, 1727, 2547, 3600, 1402, 3729, 3185, 2081,   89, 2987,  698, 3199,  975,  518,
+    1184, 3979,  736, 2834, 3866, 2532,  637, 1495, 3993,   51, 2870, 2342,  904, 1937,  531, 1562,
+    3523, 2315, 3765, 2033, 3312, 3842,  618, 1354, 3201, 1716,  556, 1270,  171, 2325, 4087, 1970,
+     422, 1585, 2845, 1219, 3118,  511, 1961,  149, 1113, 3257, 3803, 1973, 1065, 2565,  822, 2950,
+    3398, 1426, 2692,  784, 1196,  596, 1996, 2892, 1048,  737, 3988, 2388,  381, 2210, 2814, 1828,
+    3517, 2298, 3164, 1056, 1624,  180, 3324, 2731, 2162, 3381, 1130,  441, 3960, 2643, 1291, 3003,
+     286, 2753, 1274,  724, 2480,  191, 1895, 2875,  849, 3691, 2156, 3369, 2579, 1043, 2899, 3316,
+    1002, 2554, 3575,  212, 2461,  891, 2678, 3613, 2375, 1718,  309, 2874, 1506, 3536, 2060,  209,
+    1838,  420, 2131, 4066, 3033, 3362, 2415,  159, 1758, 3403, 2754, 1557, 3601, 1248, 3840,  260,
+    2622, 1569,  318, 2038, 3444, 2312, 1261,  983,  394, 1910, 1445, 3052, 1713,  148, 3712, 2201,
+    1060, 4044,  443, 1714, 3681, 1165, 2335, 3955,   18, 2679, 1570,  332, 3900, 1516,  766,   99,
+    3706, 2169,  644, 1908, 3371, 1559, 3857, 1278,  657, 2747,  953, 3680,  117, 3175, 1220, 3869,
+     988, 3280, 2890,  136, 1905,  436, 1542, 3894, 2608, 1343,  294,  992, 1953, 3252,  803, 1411,
+    3347,  623, 3747, 2776,  795, 4085, 1797, 3170, 3820, 2634, 3619,  689, 3345, 2003,  631, 3437,
+    1665, 3174, 2138, 2975, 2642,  397, 3364, 1419, 2041, 1095, 2968,  676, 1944, 3506, 2716, 1873,
+    1249, 3186, 1489, 3973, 1117, 2930,  328, 2181, 3135, 3991, 1393, 2155, 2431, 1703,  590, 2629,
+    3580,  652, 1582, 2444, 1314, 3703,  843, 3133,  522, 2214, 3810, 3066, 2510,   38, 2292, 2949,
+    1997, 1038, 2405, 1392,   11, 2962,  455, 2412,  830,  125, 2208, 1071, 2473, 2909,  976, 2701,
+      27,  807, 1378, 3546,  863, 1581, 3016,  566, 3532, 2434, 3764, 3171, 1252, 2355,  292, 3872,
+    2482,  446, 2802,   81, 2354,  717, 1841, 3476,    4, 1948,  449, 3430,  793, 4035, 2984, 2276,
+    1283, 1994, 3914,  930, 3460, 2773, 2254, 1162, 1930, 3549,  835, 1784,  585, 4064, 1647, 3589,
+     215, 3963, 3136, 1821, 3608, 2180, 1584, 3500, 1295, 2884, 1661, 3753,  262, 1480, 3926, 2328,
+    1900, 3789, 2445,  223, 1935, 4009, 2229,  999, 1766,  232,  860, 2160,  465, 3291, 1075, 3020,
+    1720,  848, 3790, 2082, 3219, 3668, 2617,  965, 1608, 2462, 3010, 1089, 2734,  357, 1490,   43,
+    3128, 2613,  220, 2952,  574, 1694,   83, 4033, 2865,  229, 2655, 1242, 3413, 2797, 1080,  731,
+    2525, 1322,  469,  875, 2672, 1154,  666, 3061, 1964, 4017,  583, 3115, 1888, 3483,  462, 1253,
+    3334, 2823,  624, 3230, 1227, 2743,  291, 3315, 2867, 3903, 1394, 2832, 1829, 4042, 1534,  602,
+    2193, 3502, 1420, 1037, 1672,  382, 1371, 3057, 3862,  702, 3630, 1432, 1903, 3311, 2092, 3798,
+    1034, 1669, 3611, 1193, 2061, 2589, 3288, 1444,  709, 1663, 3167, 2062,  366, 1472, 2211, 3215,
+    1709, 2879, 2271, 3821, 3263,  296, 3921, 2563,  235,  973, 2659, 1323, 2394,  727, 3021, 1754,
+     337, 1045, 1605, 3851, 2331,  707, 3742, 1473, 2021,  613, 2506, 3552,   48,  918, 2700, 3693,
+     165, 2553, 3053,  287, 2691, 4075, 2277,  498, 1241, 2783, 2184,  120, 3721,  966, 2649,  442,
+    2187,  627, 2343, 3145, 3945,  333, 1036, 3679, 2287, 3454,  987, 3829, 2437, 3614,  175, 3858,
+     543, 3510,  147, 1507, 1898, 2440, 1404, 1760, 3609, 2261, 3414,   44, 3846, 1023, 2177, 4070,
+    2485, 3515, 2100,  110, 3097, 1725, 1123, 2599,  133, 3254, 1145, 1638, 3149, 2420, 2010, 3340,
+    1166, 1779,  690, 3372, 1868,  882, 3300, 2020, 3574,  295, 1706, 3120, 2396,  686, 1722, 3428,
+    4057, 2750,  284, 1400,  818, 1772, 3017, 1981,  396, 2568,   65, 1533,  611, 2902,  937, 1843,
+    2618, 1099, 2065, 2965,  577, 3666,  817, 3187, 1119,  495, 1609, 2048, 3238, 2763, 1528,  143,
+     857, 2904, 1361, 2680,  524, 3421, 2175, 3629,  806, 4003, 2302,  342, 3767,  693, 1324,  395,
+    2850, 3916, 2392, 3697, 1259, 2873,  103, 1637, 2489, 1032, 4023,  559, 1307, 3835, 2881, 1369,
+     897, 3248, 1917, 3776, 2488, 3432,  567, 2766, 1197, 3927, 3070, 1881, 3333, 1281, 2153, 3077,
+    1398, 4047,  850, 3469, 1250, 2807,   71, 2075, 2682, 3888, 2982,  670, 1243,  388, 3675, 3301,
+    1912, 3785,  674, 1839, 4029,  956,  323, 3040, 1604, 2741, 1979,  952, 2918, 1861, 3985, 2278,
+     813, 1479,   24, 2074,  553, 2338, 3909,  722, 3438, 3034, 1951, 2709, 3355,  407, 2024,  155,
+    3669, 1607, 1120, 2852,    3, 2235, 1424, 3715, 1685,  763, 2168, 1030, 4006, 2578,  444, 3696,
+      35, 2383, 3181,  288, 2200, 3947, 1683, 3504,  374, 1429, 2454, 3557, 1792, 2621,  927, 2346,
+     460, 3134, 1207, 2297, 2835, 1448, 2486, 1911, 1182,  437, 3121, 1455, 2530,  113, 3299, 1618,
+    3096, 3583, 1028, 2983, 1611, 3178, 1109, 2736, 1454,  371,  854, 1553, 2257, 1078, 3139, 2552,
+    2334,  432, 2166,  671, 3565, 1081, 3122,  190, 2465, 3495,  416, 2829,  204, 1580,  842, 3393,
+    1676,  672, 1854, 2689, 1491,  949, 2372, 3085,  774, 1983, 1005,  137, 3929, 2077, 3055, 1317,
+    1691, 2595,   21, 3527,  428, 3168, 3898,   77, 3327, 3814,  708, 3690, 3418, 1160,  646, 2476,
+     247, 1925, 2630, 3994,  346, 3547, 1902,  177, 2125, 3639, 2436, 3870,   60, 3496, 1736,  720,
+    1268, 3394, 3954, 3039, 1678, 2036, 4091,  870, 1889, 3002, 1457, 2364, 3727, 1985, 2757, 2280,
+    2931, 3806, 1164, 3626,  533, 3297,  197, 1199, 4068, 2900, 3395, 1561, 3172,  759,  201, 3992,
+    3341, 1012, 3837, 1615, 2063, 1101,  634, 2196, 2561, 1306, 2076,  261, 2240, 1773, 2825, 3700,
+    1275, 3431,  705, 2262, 1349,  799, 2479, 3839, 2959, 1233, 3232,  683, 2787, 1403, 3997, 2921,
+      92, 2614, 1446,  931,  252, 2505,  542, 2796, 1214, 3896,  607, 3192, 1163, 3465,  555, 1326,
+     960,  312, 2439, 3015, 2004, 3818, 2570, 1551, 2220,  255, 2606,  558, 2311, 1167, 2837, 1938,
+     622, 2148, 2889,  786, 3391, 2612, 1701, 3562,  886, 2847, 1631, 3000,  895, 3922,  370, 2140,
+     905, 2923,  300, 1804, 3354, 2816, 1576,  535,  967, 1819,  317, 1635, 2142,  942,  458, 1980,
+    3831,  400, 1859, 2775, 3812, 3260, 1469, 3448, 2299,   54, 2121,  883, 1808,  153, 3018, 3934,
+    1921, 3274, 1443,  107,  869, 1734, 2860,  647, 3683, 1865, 1310, 3850, 1763, 3701, 2471, 1485,
+    3643,  359, 2443, 1388,  164, 3760, 2998, 1417,  193, 4053,  528, 3545, 2446, 1377, 3103, 1697,
+    4079, 1475, 2533, 3852, 1084,  234, 3720, 3210, 2288, 4056, 2560, 3456, 3027, 3744, 2466, 3234,
+    1155, 2295, 3194,  572, 2120, 1131, 1831,  398, 3734, 1667, 3397, 2651, 4048, 2428, 1537, 2157,
+     435, 2697, 3876, 2247, 3526, 1266,  405, 3226, 1044, 3481,  833, 2993,   32, 3309,  426,  925,
+    3063, 1774, 4089, 3193, 1936,  969,  391, 1995, 3348, 2337, 1824, 1203,   91, 3387,  684, 2390,
+     135, 3188, 1998,  515, 3059, 2362, 1969, 1379,   19, 2855,  767, 1192,  174, 1852,  615, 1589,
+    2961,  876, 3699, 1335, 3520,   87, 2971, 2576,  760, 1104, 2913, 1395,  453,  783, 3542, 1069,
+    3685,  770, 1699,  510, 2981, 2483, 3974, 1955, 2360,  299, 2746, 2167, 1462, 1062, 2054, 3493,
+    2668,  207, 1157,  595, 2771, 2255, 3924, 2538, 1082,  749, 3216, 2660, 3861, 1976, 2788, 1151,
+    3445,  773, 1218, 3688, 1666,  889, 2738,  626, 3645, 2051, 1496, 3596, 2216,  991, 2718, 3638,
+    2018,  451, 1648, 2728, 2361,  798, 3970, 2012, 3196, 2351,  214, 3711, 2055, 3294, 2809,    6,
+    3094, 2509, 1147, 3328, 2032,  970,   49, 1359, 3110, 1652, 3939,  606, 3162, 2555, 3923,  570,
+    1355, 2316, 3375, 1626, 3618, 1318,  536, 2960, 1526, 3797,  321, 2154,  801, 1578,  406, 3728,
+    1735, 2624, 2248, 2849,  122, 3247, 3984, 1153, 1726, 3282,  340, 2493, 3104, 4008, 1396,  131,
+    3449, 2569, 4051,  203, 1076, 3342, 1612, 1247,  502, 3885, 1801,  977, 2573, 1201, 1740, 2318,
+    1380, 1986, 4092,  241, 1505, 3779, 2732, 3427,  546, 2542, 1195, 1929, 3633,  254, 1629, 2888,
+    1954, 3838,  761, 2498,  142, 3129, 1810, 3458,   10, 2760, 1712, 3045, 1100, 3339, 2313, 2945,
+     568, 3953,  307, 3494, 1308, 2171,  415, 2403, 3009,  958, 3897,  680, 1680,  409, 3278, 2344,
+    1487,  593, 1890, 3158, 2079, 2811,  308, 3605, 2696, 1509, 3406, 3067,  304, 3641,  632, 3241,
+     386, 3464,  653, 2863, 2296,  734, 1739, 2114,  934, 3581,  118, 2933,  884, 2382, 1169, 3346,
+      67, 1001, 3044, 1874, 3971, 1125,  808, 2107, 2427, 1277, 3490,  474, 2522, 4010,   55, 1313,
+    2096, 1031, 1603,  819, 1934, 3774, 1521, 3534,  182, 2708, 2136, 1215, 2905, 1975,  748, 1108,
+    3856, 2986,  923, 1399, 3811,  638, 1814, 2332,  917,   42, 2203,  726, 1949, 1433, 3932, 2641,
+    1007, 1610, 2541, 1271, 3213, 3632,  349, 3012, 4041, 1546, 2256, 3321, 1761, 4005,  629, 2238,
+    3733, 2623, 1493,  306, 2228, 2647, 3573,  404, 4034,  943, 1972, 3780, 1415,  691, 1893, 3108,
+    3579, 2764, 3276, 2481, 2980,  605, 2665,  847, 1915, 1421, 3482,   76, 3732, 2524, 3538, 2770,
+      14, 2273, 3559,  272, 2398, 3466, 1191, 3163, 4073, 2937, 1276, 3848, 2798, 2268,  116, 1798,
+    3567, 2124, 3740,   82, 1923, 1061, 2602, 1285,  256, 2782,  744, 1352,  408, 2638, 3208, 1316,
+    1793,  551, 3604, 3319,  628, 1366, 2917, 1693, 3205,  625, 2878,  154, 2237, 3272, 2637,  896,
+     227, 1795,  487, 3902,   73, 1204, 3166, 2232, 4086,  506, 3202, 2289, 1520,  974,  365, 1777,
+    2037, 1142, 2688, 1658,  812, 2903,  140, 2019,  586, 1644, 2484,  431, 3477, 1090, 3137,  685,
+    2805,  330,  894, 3028, 3930,  530, 1695, 3268, 2347, 1869, 3435, 3802, 2871, 1017, 2056,  271,
+    2995, 2370, 1138, 2843, 1971, 3884,   95, 1052, 2584, 1508, 2340, 3399, 1705, 1134, 3704, 1531,
+    3962, 2259, 1074, 1478, 2352, 3723, 1741,  280, 2848, 1020, 1711,  718, 3060, 3969, 1347, 3209,
+    3436,  430, 3987, 3107, 1927, 3899, 1474, 2650, 3642, 1059, 3235, 1871,  832, 1588, 2425, 4016,
+    1365, 3336, 2386, 1547, 2710, 2102, 3501,  778, 3875, 1096,  505, 2118,    1, 1715, 3832, 3412,
+     846, 4060,  360, 1654,  880, 2447, 3337, 2070, 3749,  243, 3624,  996,  534, 3037,  344, 2450,
+     608, 3349, 2724, 3519, 1978,  933, 3325, 1367, 3615, 2421, 3849, 2673,  239, 2108, 2459,  779,
+    1597, 2520,  661, 1282,  341, 2502,  985, 3385,  221, 2182, 3781,  281, 2957, 3648,  230, 2046,
+    1027, 1837, 3824,  649, 1223,  246, 2927, 1383,  101, 2686, 3073, 1425, 3670, 2402,  612, 1555,
+    2725, 1338, 2080, 3758, 3076,  475, 1799,  677, 2988, 1235, 1897, 2698, 4083, 2174, 1805, 2857,
+    1231, 2030,  167,  723, 3086,  425, 2550,  695, 2028,  100, 1255, 1947, 3577,  581, 2841, 3794,
+    1222, 3004, 2223, 3627, 3258, 2133,  516, 1835, 3030,  721, 1373, 2540, 2110, 1264, 2644, 3290,
+     509, 2915,  150, 2207, 3153, 4020, 2418, 1965, 3595, 1634, 2310,  872, 3225, 1141, 2894,  205,
+    2249, 3275,   69, 2640, 1224, 3513, 1452, 4004, 2492,  392, 3255,  754, 1358,   28, 3650,  866,
+    3817, 3119, 1643, 4045, 1312, 2830, 3845, 1645, 3453, 3054,  836, 3365, 1574, 1077, 1823,  129,
+    3665,  893, 1756,   56, 1513, 2765, 3524, 1238, 2607, 4021, 1698, 3179,  563, 3880,  796, 1628,
+    3718, 2491, 1401, 3637, 1013, 1719,  414,  961, 3207,  564, 4081,  270, 2605, 1952, 3475, 3911,
+     978, 1818, 3636,  781, 2345,  402, 2822,  995, 2198, 1525, 3800, 2085, 3126, 2474, 3317, 1492,
+     257, 2582, 1019, 2374, 1812,    5, 2172, 1093,  496, 2729, 2191,  338, 2908, 4036, 3177, 2150,
+     298, 3310, 2727, 4061, 1049,  688, 3828,  372, 2239,  950,   16, 3473, 1133, 1904, 3114,   79,
+    2144,  909, 3384, 1896, 2772,  719, 3750, 2882, 2515, 1212, 1870, 3361, 1502,  423,  762, 1655,
+    2588,  517, 2964, 1517, 3952, 1989, 3266,   46, 3594,  829, 2620,  269, 1710, 1050,  519, 2266,
+    1899, 3446,  384, 2938, 3530,  864, 3200, 2517, 4013, 1524, 3725, 1294, 2333,  433,  841, 2571,
+    1867, 1263,  539, 2380, 1987, 3080, 1781, 1442, 2876, 3603, 1855, 2790,  322, 2387, 2703, 4077,
+    1305, 2976,  573,  310, 2350, 3302, 1458, 2090,  219, 3788, 2245,  827, 2810, 3617, 2365, 3068,
+    1292, 3443, 2039, 1047,  194, 1660, 2513, 1301, 3036, 1753, 3405,  641, 3882, 2667, 3593, 2880,
+     740, 3863, 1571,  614, 2022, 3787, 1430,  362, 1886,  936,  192, 3303, 1775, 2720, 1500, 3895,
+    2275, 3570, 1646, 2944,  285, 3429, 2528,  158, 3250,  599, 1319, 2212, 3771, 1529, 1010,  403,
+    1752, 3497, 2567, 1538, 3943,   22, 1054, 3561,  663, 1577, 3046,   58, 3942, 1187, 2008,  152,
+    4040,  354, 2455, 3745, 2717, 3459,  715, 3708,  482, 2145, 1140, 2989, 1387, 1946,  127, 1303,
+    3271, 1088, 2417, 3160, 1188,  278, 2684, 3480, 2358, 3079, 2601,  621, 3823, 1011, 3419,  648,
+     348, 3138,  928, 3799, 1389,  775, 1159, 3964, 2009, 2464, 3915,  821, 2947,  575, 3654, 3159,
+    2272,  742, 3775, 1148, 2135, 3101, 2596, 1858, 2745, 3468,  997, 2487, 1790,  526, 3212,  944,
+    2779, 1438,  758, 3087,  493, 1124, 2301, 1851, 2836, 4052,  189, 2284, 3457,  865, 3981, 2176,
+    1668, 2733,   90, 3998, 2204, 2911, 1731,  582, 1179, 3658, 1575, 2101, 2979,  105, 1999, 2854,
+    1200, 2549,   88, 2066, 2663, 3587, 2263,  529, 1591, 1042, 3075,  109, 1780, 3389, 2043, 1251,
+    2769,  104, 1939, 2853,  794, 1682,  467, 4015, 1299,  367, 2035, 3253, 1345, 2675, 3705, 2194,
+    1748, 3357, 2285, 1880, 1556, 3190, 3841,  126,  951, 1465, 2699, 1651,  439, 2558, 3081,  642,
+     352, 3521, 1879,  716, 1476, 3353,  910, 3938, 2215,   57,  826, 3377, 1144, 2470, 1408, 3689,
+    1815, 3965, 1540, 3249,  447, 1755, 3109, 2808, 3671,  335, 3503, 2170, 1298, 2587,  213,  862,
+    3907, 1482, 3180,  411, 3667, 3287, 2260,  861, 2990, 2408, 3892,  682, 3507,  195, 1483,  609,
+    3844,   31, 1021, 3977,  242, 2129, 1315, 2953, 2438, 3602,  651, 3112, 3761, 1094, 1796, 3647,
+    2323, 3001, 1053, 2597, 3612,  163, 2015, 3132, 1405, 2824, 3874, 1894,  490, 4067, 3176,  753,
+    2789,  532, 2231, 1070, 3887, 1302,   39,  915, 1901, 2574, 1477, 2839,  957, 3983, 3058, 1671,
+    2410, 3422, 1046, 2490, 1436,  170, 1183, 3423, 1776,   93, 1617, 1115, 2886, 1924, 2448, 3078,
+    1262, 2891, 2503, 3489, 2793,  853, 3447,  591, 1816, 3251, 1185, 1959, 2416,  226, 1460, 2795,
+     898, 1374, 3843,  476, 2294, 1156, 2656,  399, 3566, 1770,  313, 2319, 2778, 1535,  210, 2348,
+    1121, 3383, 2954,  790, 2500, 3407, 2161, 4002, 1225, 3245,  694, 3716,  387, 2327,  664, 3625,
+     282, 2069,  549, 4049, 2179, 2799, 3864, 2539,  620, 3741, 3100, 2274,  421, 4014,  785, 3550,
+     383, 2002,  701, 1376,  438, 2401, 1656, 3999,  356, 2183,   53, 3940,  797, 3379, 2130, 4027,
+      25, 1977, 3222, 1579, 2883, 4093, 1708,  659, 2389,  954, 3184, 1267, 3606,  868, 2040, 3651,
+    1704,  134, 3791, 1846,  273, 1511, 2868,  569, 2395,  225, 2109, 1794, 3292, 1431, 1919, 2685,
+    1328, 2866, 1738, 3065,  834, 1857,  327, 1484, 2097, 2739,  820, 3622, 1530, 2657, 1040, 2242,
+    1566, 3735, 3161, 1757, 3777, 3273, 1004, 2671, 1386, 3694, 2910, 1616, 2652, 1258, 3024,  598,
+    2458, 3474,  789,  238, 2049,  858, 3426, 3093, 1237, 3751, 2631,  619, 1723, 3335, 2896,  454,
+    2619, 2128, 1336, 2706, 3183,  732, 3713, 1733, 3455, 2759, 3904, 1105, 2901,   20, 3402,  989,
+    3859,  752, 3551,   80, 1244, 3702, 2963, 1051, 3279,  237, 1353, 2011, 3378,   62, 1849, 3322,
+    2744,  196, 2432, 1106, 2219,  139, 1968, 3069,  771, 2477, 1026,  578, 3518,  319, 1866, 1022,
+    3769, 1696, 2687, 3049, 3754, 1422,  114, 2794, 1945,  283, 2111, 3918,   41, 2430, 1198, 4000,
+    3206,  902,  584, 3568, 2283, 1135, 2057,  128, 1330,  776, 1590,  478, 2496, 4065, 2197,  445,
+    3197, 2349, 1447, 2594, 3236, 2126,  699, 3537, 1764, 4072, 2516,  520, 1181, 2920, 3920,  513,
+    1320,  945, 4084,  521, 2985, 1532, 3910,  302, 3544, 1692, 3242, 2000, 2336, 3889, 2748, 3270,
+    1363,  424, 2230, 1210,  380, 2548, 2195, 3879, 1558,  791, 3305, 1451, 3008, 1956,  728, 1519,
+    2303, 3830, 3013, 1595,  355, 4090, 3261, 2543, 3051, 3621, 2265, 3150,  851, 1284, 1717, 2611,
+    1190,  240, 2017, 3961,  369, 1587, 2501,  417, 2270,  929, 2994, 3748, 2221,  743, 2413, 1687,
+    2967, 3409, 1926, 2712, 3646,  814, 2581, 1304, 2151,  456, 4007,  108, 1407,  805, 1592,  144,
+    2378, 3143,  913, 3584, 3240, 1832,  990,  523, 3511, 2475, 2828, 1015,  483, 3661, 3386,  276,
+    1882, 1211,    9, 1992, 2654,  881, 1650,  494, 1029, 1892,  331, 3786, 1963, 3488,  639, 3628,
+    2978, 1636, 3415,  643, 1102, 2925, 3919, 1296, 3195, 1572,  138, 1800, 3285, 1449, 3678,  156,
+    2132,  692, 1481,    2, 1175, 2099, 3484,  660, 2893, 2441, 1111, 2999, 2625, 3376, 2084, 3695,
+     729, 4046, 2014, 1600,  576, 3976, 2676, 3130, 1205,  185, 1806, 4030, 2190, 2635, 1092, 2815,
+     547, 3478, 2451, 3755, 1325, 2936, 3470, 2371, 3936, 2844, 1423, 2575,  160, 2887, 2104,  311,
+    3881,  919, 2813, 2234, 3599, 1850,    8, 2723,  750, 3860, 2633, 1097,  329, 2670,  998, 3142,
+    3553, 2556, 3795, 2317, 3314, 1662,  277, 3169, 1486, 3737, 1768,  703, 3825,  364, 1016, 2929,
+    1767, 2662,  208, 2906, 2359, 1287,   34, 1732, 3808, 2329, 3366,  741, 1385,  119, 1689, 3946,
+    2935, 1563,  935, 3295,  650,  206, 2027, 1390,   52,  745, 3440, 1098, 1642, 3318, 1055, 1461,
+    2409, 1877,  141, 1339, 3151,  852, 2363, 3367, 1933,  485, 2192, 3516, 2025, 4031,  491, 1876,
+    1216,  413,  962, 3025,  544, 2781, 4024, 1942,  914,  168, 3218, 2282, 1928, 1331, 2527, 3522,
+     504, 1139, 1428, 3784,  856, 3539, 2973, 2115,  890, 1441,  373, 2885, 3763, 3220, 2411,  845,
+    2123,  303, 2711, 1782, 2253, 3853,  816, 3634, 3198, 2213, 1813, 4025,  550, 2286, 3709, 2762,
+     713, 3224, 4043, 2615,  472, 1686, 3813, 1035, 1439, 3660, 2831,  788, 1598, 3062, 2250, 2826,
+    1583, 3928, 2052, 1745, 3623,  867, 1269, 2330, 2742, 3563, 1232,  488, 2958, 3332,   17, 1494,
+    2105, 3111, 3404, 2189,  427, 1552, 2504,  630, 3479, 3091, 2544, 1875, 1146, 2013,  358, 3672,
+    1290, 3165, 3972,  473, 1434, 3072, 2702, 1743, 1180, 2806,  265, 3090, 2628,  873,   37, 1778,
+    3505,  418, 1128, 1988, 3714, 2861,  290, 2119, 3007,  211, 1239, 3293,   40, 1158,  733, 3425,
+     183, 3214, 2704,  264, 1414, 2551,   86, 3792,  579, 1684, 2586, 4071, 1599,  815, 3722, 2406,
+    3908,  921,  121, 2801, 1891, 3203, 1129, 3958,  202, 1640,  993, 3509,  592, 2791, 1497, 3360,
+     151, 1918, 1126, 2404, 3374, 1033,  324, 2391,  527, 3847,  972, 2083, 1362, 3264, 3941, 1265,
+    2164, 3071, 1586, 2424,  706, 1468, 3284,  636, 4055, 2519, 1842, 2293, 3854, 2585, 3635, 1342,
+    2463,  675, 1110, 3743, 2113, 3401, 1786, 3125, 2094, 3392,  941,  187, 2149, 2777, 1174, 1809,
+     377, 2609, 1649, 3995,  704, 3663,  343, 1844, 2817, 2243, 3883,   78, 2321, 4076,  964, 2577,
+    3759,  645, 2996, 3607,   72, 2031, 4054, 1466, 3390, 2546, 1621, 3752,  434, 1914, 2919,  339,
+    2583,  900, 3652,  102, 3467, 2646, 1202, 2339, 1614,  901, 3554,  594, 2943, 1641,  390, 1887,
+    3996, 2173, 1670, 2977,  461, 2821,  730, 1116,  351, 1427, 3031, 2460, 3540,  336, 3239,  673,
+    3042, 3564, 1064, 2456, 1356, 2103, 2707, 1256, 3323,  755, 1391, 3211, 1707, 3029,  471, 2222,
+    2820, 1518, 2137,  877, 1630, 2523, 2955,  764, 1878,  115, 2940,  747, 3461, 2495, 1009, 1632,
+    3833,  545, 2758, 2127,  968, 1830, 3826,   64, 3148, 2768,  305, 1456, 1003, 2152, 3265,  840,
+    2922,   75, 3462,  920, 3873, 1333, 2442, 4001, 2761, 3684, 1848,  601, 1334, 1982, 3980, 2326,
+    1370, 1960,  499, 3286,  199, 3038,  809, 2377, 3726,  463, 2006, 2674,  810, 1229, 3543, 1730,
+];
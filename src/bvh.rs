@@ -0,0 +1,690 @@
+use crate::aabb::Aabb;
+use crate::vec3::Vec3;
+use crate::{HitRecord, Hittable, Ray};
+
+/// A bounding volume hierarchy: a tree of boxes over a set of objects, so a
+/// ray only tests the objects in boxes it passes through rather than every
+/// one.
+///
+/// Each node has up to four children whose boxes are stored side by side,
+/// coordinate by coordinate, so a ray is tested against all four in one
+/// pass the compiler can turn into SIMD instructions. The nodes are kept in
+/// one array, parents before children, and traversed with an explicit
+/// stack. The objects stay in the order they were given, so they can be
+/// swapped for moved versions of themselves and the boxes refit around them.
+///
+/// The tree is usually over a list of objects, but can be over anything
+/// whose parts can be told apart by index, like the faces of a mesh, so
+/// they needn't each be made an object of their own.
+pub struct Bvh<P: Primitives = Vec<Box<dyn Hittable>>> {
+    primitives: P,
+    nodes: Vec<WideNode>,
+}
+
+/// A set of parts a `Bvh` can be built over, known by their indices.
+pub trait Primitives: Send + Sync {
+    /// How many parts there are.
+    fn count(&self) -> usize;
+
+    fn hit_primitive(&self, index: usize, r: &Ray, t_min: f64, t_max: f64)
+        -> Option<HitRecord<'_>>;
+
+    /// A box part `index` lies entirely within, or None if it is unbounded.
+    fn primitive_box(&self, index: usize) -> Option<Aabb>;
+
+    /// A box around the part of part `index` inside `clip`; see
+    /// `Hittable::clipped_box`.
+    fn clipped_primitive_box(&self, index: usize, clip: &Aabb) -> Option<Aabb>;
+}
+
+impl Primitives for Vec<Box<dyn Hittable>> {
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    fn hit_primitive(
+        &self,
+        index: usize,
+        r: &Ray,
+        t_min: f64,
+        t_max: f64,
+    ) -> Option<HitRecord<'_>> {
+        self[index].hit(r, t_min, t_max)
+    }
+
+    fn primitive_box(&self, index: usize) -> Option<Aabb> {
+        self[index].bounding_box()
+    }
+
+    fn clipped_primitive_box(&self, index: usize, clip: &Aabb) -> Option<Aabb> {
+        self[index].clipped_box(clip)
+    }
+}
+
+/// Children per node.
+const WIDTH: usize = 4;
+
+/// Entries a traversal stack holds before it spills over onto the heap,
+/// enough for trees thirty nodes deep.
+const STACK_SIZE: usize = 96;
+
+struct WideNode {
+    /// Box corners by axis, then child.
+    min: [[f64; WIDTH]; 3],
+    max: [[f64; WIDTH]; 3],
+    children: [Child; WIDTH],
+}
+
+#[derive(Copy, Clone)]
+enum Child {
+    Empty,
+    /// An index into the nodes.
+    Node(usize),
+    /// An index into the objects.
+    Leaf(usize),
+}
+
+/// A node of the binary tree the wide one is collapsed from.
+struct BinaryNode {
+    bbox: Aabb,
+    contents: Contents,
+}
+
+enum Contents {
+    /// An index into the objects.
+    Leaf(usize),
+    /// The second child's index; the first is the next node.
+    Interior(usize),
+}
+
+/// Nodes still to visit, in a fixed array so traversal allocates nothing,
+/// spilling over into a growable one only for the deepest trees.
+struct Stack<T> {
+    items: [T; STACK_SIZE],
+    len: usize,
+    overflow: Vec<T>,
+}
+
+impl<T: Copy + Default> Stack<T> {
+    fn new(first: T) -> Stack<T> {
+        let mut items = [T::default(); STACK_SIZE];
+        items[0] = first;
+        Stack {
+            items,
+            len: 1,
+            overflow: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, item: T) {
+        if self.len < STACK_SIZE {
+            self.items[self.len] = item;
+            self.len += 1;
+        } else {
+            self.overflow.push(item);
+        }
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        if let Some(item) = self.overflow.pop() {
+            return Some(item);
+        }
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        Some(self.items[self.len])
+    }
+}
+
+/// The lanes for which `keep` holds, farthest by `key` first so the nearest
+/// ends up on top of the stack, and how many there are.
+fn farthest_first(keep: impl Fn(usize) -> bool, key: &[f64; WIDTH]) -> ([usize; WIDTH], usize) {
+    let mut order = [0; WIDTH];
+    let mut count = 0;
+    for lane in 0..WIDTH {
+        if keep(lane) {
+            order[count] = lane;
+            count += 1;
+        }
+    }
+    order[..count].sort_unstable_by(|&a, &b| key[b].partial_cmp(&key[a]).unwrap());
+    (order, count)
+}
+
+impl WideNode {
+    fn set_box(&mut self, lane: usize, bbox: &Aabb) {
+        let (lo, hi) = (bbox.min, bbox.max);
+        for (i, (l, h)) in [(lo.x(), hi.x()), (lo.y(), hi.y()), (lo.z(), hi.z())]
+            .iter()
+            .enumerate()
+        {
+            self.min[i][lane] = *l;
+            self.max[i][lane] = *h;
+        }
+    }
+
+    fn child_box(&self, lane: usize) -> Aabb {
+        Aabb {
+            min: Vec3(self.min[0][lane], self.min[1][lane], self.min[2][lane]),
+            max: Vec3(self.max[0][lane], self.max[1][lane], self.max[2][lane]),
+        }
+    }
+
+    /// The box around all the children.
+    fn bbox(&self) -> Aabb {
+        (1..WIDTH)
+            .filter(|&lane| !matches!(self.children[lane], Child::Empty))
+            .fold(self.child_box(0), |sum, lane| {
+                Aabb::surrounding(&sum, &self.child_box(lane))
+            })
+    }
+
+    /// Where a ray with origin `o` and reciprocal direction `inverse` enters
+    /// each child's box within [t_min, t_max], or infinity if it misses.
+    fn entries(&self, o: [f64; 3], inverse: [f64; 3], t_min: f64, t_max: f64) -> [f64; WIDTH] {
+        let mut t0 = [t_min; WIDTH];
+        let mut t1 = [t_max; WIDTH];
+        for i in 0..3 {
+            for lane in 0..WIDTH {
+                let near = (self.min[i][lane] - o[i]) * inverse[i];
+                let far = (self.max[i][lane] - o[i]) * inverse[i];
+                t0[lane] = t0[lane].max(near.min(far));
+                t1[lane] = t1[lane].min(near.max(far));
+            }
+        }
+        let mut entries = [f64::INFINITY; WIDTH];
+        for lane in 0..WIDTH {
+            if t0[lane] <= t1[lane] {
+                entries[lane] = t0[lane];
+            }
+        }
+        entries
+    }
+}
+
+impl<P: Primitives> Bvh<P> {
+    /// Build a tree over `objects`, splitting each node's objects where the
+    /// surface area heuristic predicts the cheapest traversal.
+    ///
+    /// Panics if `objects` is empty or any of them is unbounded.
+    pub fn new(objects: P) -> Bvh<P> {
+        Bvh::build(objects, false)
+    }
+
+    /// Like `new`, but also weighing splits that cut space rather than the
+    /// list of objects, putting objects that straddle the cut on both sides,
+    /// each clipped to its side. Slower to build, and objects may be listed
+    /// more than once, but much quicker to trace when big objects cross
+    /// many small ones, like a long ground triangle under a detailed model.
+    /// `refit` keeps such a tree correct but loses the clipping.
+    ///
+    /// Panics if `objects` is empty or any of them is unbounded.
+    pub fn with_spatial_splits(objects: P) -> Bvh<P> {
+        Bvh::build(objects, true)
+    }
+
+    fn build(objects: P, spatial: bool) -> Bvh<P> {
+        assert!(objects.count() > 0, "a BVH needs at least one object");
+        let mut members: Vec<(Aabb, usize)> = (0..objects.count())
+            .map(|i| (bounds(&objects, i), i))
+            .collect();
+        let mut binary = Vec::new();
+        let mut clipper = Clipper {
+            objects: &objects,
+            budget: objects.count(),
+        };
+        build(
+            &mut binary,
+            &mut members,
+            if spatial { Some(&mut clipper) } else { None },
+        );
+        let mut bvh = Bvh {
+            primitives: objects,
+            nodes: Vec::new(),
+        };
+        bvh.collapse(&binary, 0);
+        bvh
+    }
+
+    /// Recompute every box from the objects' current bounds, keeping the
+    /// tree's shape. Much cheaper than building a new tree, and as good as
+    /// one while objects only move a little; after bigger changes the boxes
+    /// overlap more and more and rebuilding pays off.
+    ///
+    /// Panics if an object has become unbounded.
+    pub fn refit(&mut self) {
+        // children come after their parents
+        for i in (0..self.nodes.len()).rev() {
+            for lane in 0..WIDTH {
+                let bbox = match self.nodes[i].children[lane] {
+                    Child::Empty => continue,
+                    Child::Node(node) => self.nodes[node].bbox(),
+                    Child::Leaf(object) => bounds(&self.primitives, object),
+                };
+                self.nodes[i].set_box(lane, &bbox);
+            }
+        }
+    }
+
+    /// Add the wide node standing for binary node `index`, and those below
+    /// it, and return its index. Its children are found by repeatedly
+    /// opening up the biggest interior node among them.
+    fn collapse(&mut self, binary: &[BinaryNode], index: usize) -> usize {
+        let mut group = match binary[index].contents {
+            Contents::Leaf(_) => vec![index],
+            Contents::Interior(second) => vec![index + 1, second],
+        };
+        while group.len() < WIDTH {
+            let biggest = group
+                .iter()
+                .enumerate()
+                .filter(|(_, &b)| matches!(binary[b].contents, Contents::Interior(_)))
+                .max_by(|(_, &a), (_, &b)| {
+                    let area = |n: usize| binary[n].bbox.surface_area();
+                    area(a).partial_cmp(&area(b)).unwrap()
+                })
+                .map(|(position, _)| position);
+            match biggest {
+                Some(position) => {
+                    let opened = group.swap_remove(position);
+                    if let Contents::Interior(second) = binary[opened].contents {
+                        group.push(opened + 1);
+                        group.push(second);
+                    }
+                }
+                None => break,
+            }
+        }
+
+        let node = self.nodes.len();
+        self.nodes.push(WideNode {
+            min: [[0.; WIDTH]; 3],
+            max: [[0.; WIDTH]; 3],
+            children: [Child::Empty; WIDTH],
+        });
+        for (lane, &b) in group.iter().enumerate() {
+            self.nodes[node].set_box(lane, &binary[b].bbox);
+            self.nodes[node].children[lane] = match binary[b].contents {
+                Contents::Leaf(object) => Child::Leaf(object),
+                Contents::Interior(_) => Child::Node(self.collapse(binary, b)),
+            };
+        }
+        node
+    }
+}
+
+impl Bvh {
+    pub fn objects(&self) -> &[Box<dyn Hittable>] {
+        &self.primitives
+    }
+
+    /// Swap the object at `index`, in the order they were given, for
+    /// another, typically the same one moved a little, returning the old
+    /// one. The boxes are stale until `refit`.
+    pub fn replace(&mut self, index: usize, object: Box<dyn Hittable>) -> Box<dyn Hittable> {
+        std::mem::replace(&mut self.primitives[index], object)
+    }
+}
+
+/// What splitting space needs: the objects, to clip them, and how many more
+/// times objects may be listed twice.
+struct Clipper<'a> {
+    objects: &'a dyn Primitives,
+    budget: usize,
+}
+
+/// Add the binary nodes over `members` to `nodes` and return the index of
+/// the first. Space is split as well as the list of members when given a
+/// clipper.
+fn build(
+    nodes: &mut Vec<BinaryNode>,
+    members: &mut Vec<(Aabb, usize)>,
+    mut clipper: Option<&mut Clipper>,
+) -> usize {
+    let index = nodes.len();
+    let bbox = members[1..]
+        .iter()
+        .fold(members[0].0, |sum, (b, _)| Aabb::surrounding(&sum, b));
+    if members.len() == 1 {
+        nodes.push(BinaryNode {
+            bbox,
+            contents: Contents::Leaf(members[0].1),
+        });
+        return index;
+    }
+
+    let mut upper = match split(members, &bbox, clipper.as_deref_mut()) {
+        Some(split) => split,
+        None => {
+            // every center in the same place: any split is as good
+            let half = members.len() / 2;
+            members.split_off(half)
+        }
+    };
+    nodes.push(BinaryNode {
+        bbox,
+        contents: Contents::Interior(0),
+    });
+    build(nodes, members, clipper.as_deref_mut());
+    let second = build(nodes, &mut upper, clipper);
+    nodes[index].contents = Contents::Interior(second);
+    index
+}
+
+fn bounds(objects: &dyn Primitives, index: usize) -> Aabb {
+    objects
+        .primitive_box(index)
+        .expect("objects in a BVH must be bounded")
+}
+
+/// Number of buckets objects are sorted into along each axis when looking
+/// for a split.
+const BINS: usize = 16;
+
+/// Split off and return the members on the far side of the cheapest split
+/// found, or None if all their centers coincide and space isn't being split.
+///
+/// The surface area heuristic takes the chance of a ray that hits a box
+/// also hitting a box inside it to be the ratio of their surface areas, so
+/// the expected cost of a split is the area of each side's box times its
+/// number of members, summed.
+fn split(
+    members: &mut Vec<(Aabb, usize)>,
+    bbox: &Aabb,
+    clipper: Option<&mut Clipper>,
+) -> Option<Vec<(Aabb, usize)>> {
+    let by_objects = object_split(members);
+    if let Some(clipper) = clipper {
+        if let Some((cost, i, position)) = spatial_split(members, bbox, clipper) {
+            if by_objects.is_none_or(|(c, _, _)| cost < c) {
+                if let Some(upper) = cut(members, i, position, clipper) {
+                    return Some(upper);
+                }
+            }
+        }
+    }
+    let (_, i, bucket_split) = by_objects?;
+    let extent = Aabb::from_points(&centers(members));
+    let (lo, hi) = (axis(extent.min, i), axis(extent.max, i));
+    let (below, above) = members
+        .drain(..)
+        .partition(|(b, _)| bucket(axis(b.center(), i), lo, hi) < bucket_split);
+    *members = below;
+    Some(above)
+}
+
+fn axis(v: Vec3, i: usize) -> f64 {
+    match i {
+        0 => v.x(),
+        1 => v.y(),
+        _ => v.z(),
+    }
+}
+
+fn centers(members: &[(Aabb, usize)]) -> Vec<Vec3> {
+    members.iter().map(|(b, _)| b.center()).collect()
+}
+
+/// The cost, axis and bucket of the best plane between buckets of member
+/// centers, or None if the centers all coincide.
+fn object_split(members: &[(Aabb, usize)]) -> Option<(f64, usize, usize)> {
+    let centers = centers(members);
+    let extent = Aabb::from_points(&centers);
+
+    let mut best: Option<(f64, usize, usize)> = None;
+    for i in 0..3 {
+        let (lo, hi) = (axis(extent.min, i), axis(extent.max, i));
+        if hi - lo <= 0. {
+            continue;
+        }
+        let mut bins: Vec<(usize, Option<Aabb>)> = vec![(0, None); BINS];
+        for ((b, _), c) in members.iter().zip(&centers) {
+            let entry = &mut bins[bucket(axis(*c, i), lo, hi)];
+            entry.0 += 1;
+            entry.1 = Some(entry.1.map_or(*b, |sum| Aabb::surrounding(&sum, b)));
+        }
+        // cost and count of everything up to each bin from below, then above
+        let below = running_costs(bins.iter());
+        let mut above = running_costs(bins.iter().rev());
+        above.reverse();
+        for split in 1..BINS {
+            let ((cost_below, n_below), (cost_above, n_above)) = (below[split - 1], above[split]);
+            if n_below == 0 || n_above == 0 {
+                continue;
+            }
+            let cost = cost_below + cost_above;
+            if best.is_none_or(|(c, _, _)| cost < c) {
+                best = Some((cost, i, split));
+            }
+        }
+    }
+    best
+}
+
+/// The cost, axis and position of the best plane between equal slices of
+/// `bbox`, counting members on both sides of a plane they straddle but only
+/// the parts of them on each side towards its box. None if no plane leaves
+/// fewer members on both sides within the clipper's budget.
+fn spatial_split(
+    members: &[(Aabb, usize)],
+    bbox: &Aabb,
+    clipper: &Clipper,
+) -> Option<(f64, usize, f64)> {
+    let mut best: Option<(f64, usize, f64)> = None;
+    for i in 0..3 {
+        let (lo, hi) = (axis(bbox.min, i), axis(bbox.max, i));
+        if hi - lo <= 0. {
+            continue;
+        }
+        let width = (hi - lo) / BINS as f64;
+        // members starting and ending in each slice, and the box around the
+        // parts of them in it
+        let mut entries: Vec<(usize, Option<Aabb>)> = vec![(0, None); BINS];
+        let mut exits = vec![0; BINS];
+        for (b, object) in members {
+            let first = bucket(axis(b.min, i), lo, hi);
+            let last = bucket(axis(b.max, i), lo, hi);
+            for (k, entry) in entries.iter_mut().enumerate().take(last + 1).skip(first) {
+                let slice = slab(i, lo + k as f64 * width, lo + (k + 1) as f64 * width);
+                let piece = Aabb::intersection(b, &slice)
+                    .and_then(|clip| clipper.objects.clipped_primitive_box(*object, &clip));
+                if let Some(piece) = piece {
+                    entry.1 = Some(entry.1.map_or(piece, |sum| Aabb::surrounding(&sum, &piece)));
+                }
+            }
+            entries[first].0 += 1;
+            exits[last] += 1;
+        }
+        let below = running_costs(entries.iter());
+        let exits: Vec<(usize, Option<Aabb>)> = exits
+            .into_iter()
+            .zip(&entries)
+            .map(|(n, (_, b))| (n, *b))
+            .collect();
+        let mut above = running_costs(exits.iter().rev());
+        above.reverse();
+        for split in 1..BINS {
+            let ((cost_below, n_below), (cost_above, n_above)) = (below[split - 1], above[split]);
+            let n = members.len();
+            if n_below == 0 || n_above == 0 || n_below == n || n_above == n {
+                continue;
+            }
+            if n_below + n_above - n > clipper.budget {
+                continue;
+            }
+            let cost = cost_below + cost_above;
+            if best.is_none_or(|(c, _, _)| cost < c) {
+                best = Some((cost, i, lo + split as f64 * width));
+            }
+        }
+    }
+    best
+}
+
+/// The space between `lo` and `hi` along axis `i`.
+fn slab(i: usize, lo: f64, hi: f64) -> Aabb {
+    let (mut min, mut max) = ([f64::NEG_INFINITY; 3], [f64::INFINITY; 3]);
+    min[i] = lo;
+    max[i] = hi;
+    Aabb {
+        min: Vec3(min[0], min[1], min[2]),
+        max: Vec3(max[0], max[1], max[2]),
+    }
+}
+
+/// Split off and return the members above `position` along axis `i`,
+/// clipping those that straddle it to each side, or leave `members` alone
+/// and return None unless both sides end up with fewer members.
+fn cut(
+    members: &mut Vec<(Aabb, usize)>,
+    i: usize,
+    position: f64,
+    clipper: &mut Clipper,
+) -> Option<Vec<(Aabb, usize)>> {
+    let (mut below, mut above) = (Vec::new(), Vec::new());
+    let mut straddling = 0;
+    for &(b, object) in members.iter() {
+        if axis(b.max, i) <= position {
+            below.push((b, object));
+        } else if axis(b.min, i) >= position {
+            above.push((b, object));
+        } else {
+            let side = |lo, hi| {
+                Aabb::intersection(&b, &slab(i, lo, hi))
+                    .and_then(|clip| clipper.objects.clipped_primitive_box(object, &clip))
+            };
+            let lower = side(f64::NEG_INFINITY, position);
+            let upper = side(position, f64::INFINITY);
+            if lower.is_some() && upper.is_some() {
+                straddling += 1;
+            }
+            below.extend(lower.map(|b| (b, object)));
+            above.extend(upper.map(|b| (b, object)));
+        }
+    }
+    let n = members.len();
+    if below.is_empty() || above.is_empty() || below.len() == n || above.len() == n {
+        return None;
+    }
+    clipper.budget = clipper.budget.saturating_sub(straddling);
+    *members = below;
+    Some(above)
+}
+
+/// Which bucket a center at `x` falls in, of those evenly dividing [lo, hi].
+fn bucket(x: f64, lo: f64, hi: f64) -> usize {
+    (((x - lo) / (hi - lo) * BINS as f64) as usize).min(BINS - 1)
+}
+
+/// For each of a run of buckets, the surface area of the box around it and
+/// all the ones before times their object count, and that count.
+fn running_costs<'a>(bins: impl Iterator<Item = &'a (usize, Option<Aabb>)>) -> Vec<(f64, usize)> {
+    let mut count = 0;
+    let mut bbox: Option<Aabb> = None;
+    bins.map(|(n, b)| {
+        count += n;
+        if let Some(b) = b {
+            bbox = Some(bbox.map_or(*b, |sum| Aabb::surrounding(&sum, b)));
+        }
+        (count as f64 * bbox.map_or(0., |b| b.surface_area()), count)
+    })
+    .collect()
+}
+
+impl<P: Primitives> Hittable for Bvh<P> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let (o, d) = (r.origin(), r.direction());
+        let o = [o.x(), o.y(), o.z()];
+        let inverse = [1. / d.x(), 1. / d.y(), 1. / d.z()];
+        let mut closest: Option<HitRecord> = None;
+        let mut limit = t_max;
+        // nodes still to visit with where the ray enters them, nearest on top
+        let mut stack = Stack::new((0, t_min));
+        while let Some((node, entry)) = stack.pop() {
+            if entry >= limit {
+                continue;
+            }
+            let node = &self.nodes[node];
+            let entries = node.entries(o, inverse, t_min, limit);
+            let (order, count) = farthest_first(|lane| entries[lane].is_finite(), &entries);
+            for &lane in &order[..count] {
+                match node.children[lane] {
+                    Child::Empty => {}
+                    Child::Node(child) => stack.push((child, entries[lane])),
+                    Child::Leaf(object) => {
+                        if let Some(hit_record) =
+                            self.primitives.hit_primitive(object, r, t_min, limit)
+                        {
+                            limit = hit_record.t;
+                            closest = Some(hit_record);
+                        }
+                    }
+                }
+            }
+        }
+        closest
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.nodes[0].bbox())
+    }
+
+    /// Walks the tree once for the whole packet: a node is opened if any
+    /// ray still in the running enters it, and its objects are tested
+    /// against just the rays that reach them.
+    fn hit_packet(&self, rays: &[Ray], t_min: f64, t_max: f64) -> Vec<Option<HitRecord<'_>>> {
+        let origins: Vec<[f64; 3]> = rays
+            .iter()
+            .map(|r| [r.origin().x(), r.origin().y(), r.origin().z()])
+            .collect();
+        let inverses: Vec<[f64; 3]> = rays
+            .iter()
+            .map(|r| {
+                let d = r.direction();
+                [1. / d.x(), 1. / d.y(), 1. / d.z()]
+            })
+            .collect();
+        let mut closest: Vec<Option<HitRecord>> = rays.iter().map(|_| None).collect();
+        let mut limits = vec![t_max; rays.len()];
+        let mut stack = Stack::new(0);
+        // per child, the rays entering it and the nearest entry, the lists
+        // kept from node to node so they are allocated once
+        let mut reached: [Vec<usize>; WIDTH] = Default::default();
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            for rays in &mut reached {
+                rays.clear();
+            }
+            let mut nearest = [f64::INFINITY; WIDTH];
+            for ray in 0..rays.len() {
+                let entries = node.entries(origins[ray], inverses[ray], t_min, limits[ray]);
+                for lane in 0..WIDTH {
+                    if entries[lane].is_finite() {
+                        reached[lane].push(ray);
+                        nearest[lane] = nearest[lane].min(entries[lane]);
+                    }
+                }
+            }
+            let (order, count) = farthest_first(|lane| !reached[lane].is_empty(), &nearest);
+            for &lane in &order[..count] {
+                match node.children[lane] {
+                    Child::Empty => {}
+                    Child::Node(child) => stack.push(child),
+                    Child::Leaf(object) => {
+                        for &ray in &reached[lane] {
+                            let r = &rays[ray];
+                            if let Some(hit_record) =
+                                self.primitives.hit_primitive(object, r, t_min, limits[ray])
+                            {
+                                limits[ray] = hit_record.t;
+                                closest[ray] = Some(hit_record);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        closest
+    }
+}
@@ -0,0 +1,474 @@
+use crate::lens::LensSystem;
+use crate::sampler::{sample_disk, Sampler};
+use crate::vec3::Vec3;
+use crate::Ray;
+
+/// Something that turns points of the image into rays into the scene, so
+/// any kind of camera can be put in a scene and rendered the same way.
+pub trait Camera: Send + Sync {
+    /// A ray through point (u, v) of the image, from the bottom left corner
+    /// at (0, 0) to the top right at (1, 1), made from the next dimensions
+    /// of `sampler`, or None if the camera blocks it, as a lens's rims do
+    /// toward the corners.
+    fn generate_ray(&self, u: f64, v: f64, sampler: &mut dyn Sampler) -> Option<Ray>;
+
+    fn pose(&self) -> &Pose;
+
+    /// The views of the left and right eyes, `ipd` apart across this one,
+    /// whose lines of sight meet `convergence` away, where things appear at
+    /// the depth of the screen.
+    fn stereo_pair(&self, ipd: f64, convergence: f64) -> [Box<dyn Camera>; 2];
+}
+
+/// Where a camera stands, which way it faces and when its shutter is open.
+#[derive(Copy, Clone, Debug)]
+pub struct Pose {
+    pub origin: Vec3,
+    /// Unit vectors along the image's rows, up its columns and back from
+    /// where the camera looks.
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+    /// Rays are cast at times spread between these, so things moving
+    /// meanwhile blur. Both zero unless set.
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+}
+
+impl Pose {
+    /// Standing at `lookfrom` looking toward `lookat`, turned so `vup`
+    /// points up in the image.
+    pub fn look_at(lookfrom: Vec3, lookat: Vec3, vup: Vec3) -> Pose {
+        let w = (lookfrom - lookat).unit();
+        let u = vup.cross(w).unit();
+        Pose {
+            origin: lookfrom,
+            u,
+            v: w.cross(u),
+            w,
+            shutter_open: 0.,
+            shutter_close: 0.,
+        }
+    }
+
+    fn time(&self, sampler: &mut dyn Sampler) -> f64 {
+        self.shutter_open + sampler.get_1d() * (self.shutter_close - self.shutter_open)
+    }
+
+    /// The same pose `offset` to the right.
+    fn shifted(&self, offset: f64) -> Pose {
+        Pose {
+            origin: self.origin + offset * self.u,
+            ..*self
+        }
+    }
+}
+
+/// How much light a photograph lets in, as set on a real camera, for
+/// scenes whose lights are in physical units: radiance in nits, candelas
+/// per square metre.
+#[derive(Copy, Clone, Debug)]
+pub struct Exposure {
+    /// Sensitivity of the film, 100 for ISO 100.
+    pub iso: f64,
+    /// How long the shutter is open, in seconds.
+    pub shutter: f64,
+    /// The focal length over the diameter of the aperture, 8 for f/8.
+    pub f_number: f64,
+}
+
+impl Exposure {
+    /// What to multiply radiance by for a pixel value, from the
+    /// saturation-based sensitivity of ISO 12232: the brightest radiance
+    /// that doesn't clip is 78 / (0.65 iso) times N² / t, the 0.65 being
+    /// how much of the light from the scene a typical lens gets to the film.
+    pub fn scale(&self) -> f64 {
+        self.iso * self.shutter / (120. * self.f_number * self.f_number)
+    }
+
+    /// The exposure value for ISO 100, log2 of N² / t scaled to it, as
+    /// light meters give it: 15 for a sunny day, around 7 indoors.
+    pub fn ev100(&self) -> f64 {
+        (self.f_number * self.f_number / self.shutter * 100. / self.iso).log2()
+    }
+
+    /// The sunny 16 rule: f/16, with the shutter open for one over the ISO
+    /// of a second, exposes a sunlit scene.
+    pub fn sunny_16(iso: f64) -> Exposure {
+        Exposure {
+            iso,
+            shutter: 1. / iso,
+            f_number: 16.,
+        }
+    }
+}
+
+/// Rays spread out from a point, or from a thin lens around it.
+#[derive(Copy, Clone, Debug)]
+pub struct Perspective {
+    pub pose: Pose,
+    pub lower_left_corner: Vec3,
+    pub horizontal: Vec3,
+    pub vertical: Vec3,
+    /// Rays start anywhere on a lens this wide around the origin, so only
+    /// things on the plane in focus are sharp. Zero for a pinhole camera.
+    pub lens_radius: f64,
+    /// Normal of the plane in focus, which passes through the middle of the
+    /// window the image is seen through. Along `w` unless tilted.
+    pub focus_normal: Vec3,
+}
+
+impl Perspective {
+    /// A camera at `lookfrom` looking toward `lookat`, turned so `vup`
+    /// points up in the image, with a vertical field of view of `vfov`
+    /// degrees and images `aspect` times wider than they are high. Things
+    /// `focus_dist` away are in focus, and the rest blurred more the wider
+    /// the `aperture`.
+    pub fn new(
+        lookfrom: Vec3,
+        lookat: Vec3,
+        vup: Vec3,
+        vfov: f64,
+        aspect: f64,
+        aperture: f64,
+        focus_dist: f64,
+    ) -> Perspective {
+        let pose = Pose::look_at(lookfrom, lookat, vup);
+        let (u, v, w) = (pose.u, pose.v, pose.w);
+        let half_height = (vfov.to_radians() / 2.).tan();
+        let half_width = aspect * half_height;
+        Perspective {
+            pose,
+            lower_left_corner: lookfrom - focus_dist * (half_width * u + half_height * v + w),
+            horizontal: 2. * half_width * focus_dist * u,
+            vertical: 2. * half_height * focus_dist * v,
+            lens_radius: aperture / 2.,
+            focus_normal: w,
+        }
+    }
+
+    /// The view moved `x` image widths right and `y` heights up without
+    /// turning the camera, like the shift of a view camera's lens, to frame
+    /// a tall building while its verticals stay parallel.
+    pub fn shift(self, x: f64, y: f64) -> Perspective {
+        Perspective {
+            lower_left_corner: self.lower_left_corner + x * self.horizontal + y * self.vertical,
+            ..self
+        }
+    }
+
+    /// The plane in focus turned `degrees` about the image's horizontal
+    /// axis, its top away from the camera for positive angles, like the
+    /// tilt of a view camera's lens. Tilted one way a receding floor is
+    /// sharp from front to back; tilted the other only a band across the
+    /// image is, making real scenes look like miniatures.
+    pub fn tilt(self, degrees: f64) -> Perspective {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Perspective {
+            focus_normal: cos * self.pose.w + sin * self.pose.v,
+            ..self
+        }
+    }
+
+    /// The six square 90 degree views from `center` making up a cube map,
+    /// named for the axis each looks along. The side views have y up, and
+    /// the views up and down have the edges nearest -z at the bottom and top,
+    /// so they meet the -z view in a cross layout.
+    pub fn cube_map(center: Vec3) -> [(&'static str, Perspective); 6] {
+        let face = |direction: Vec3, up: Vec3| {
+            Perspective::new(center, center + direction, up, 90., 1., 0., 1.)
+        };
+        let y = Vec3(0., 1., 0.);
+        let z = Vec3(0., 0., 1.);
+        [
+            ("px", face(Vec3(1., 0., 0.), y)),
+            ("nx", face(Vec3(-1., 0., 0.), y)),
+            ("py", face(y, z)),
+            ("ny", face(-y, -z)),
+            ("pz", face(z, y)),
+            ("nz", face(-z, y)),
+        ]
+    }
+}
+
+impl Camera for Perspective {
+    fn generate_ray(&self, u: f64, v: f64, sampler: &mut dyn Sampler) -> Option<Ray> {
+        let (x, y) = sample_disk(sampler.get_2d());
+        let origin = self.pose.origin + self.lens_radius * (x * self.pose.u + y * self.pose.v);
+        // every ray through the lens for this point meets where the ray
+        // through its center crosses the plane in focus
+        let pinhole = self.lower_left_corner + u * self.horizontal + v * self.vertical;
+        let center = self.lower_left_corner + (self.horizontal + self.vertical) / 2.;
+        let d = pinhole - self.pose.origin;
+        let n = self.focus_normal;
+        let focus = if d.dot(n) < 0. {
+            self.pose.origin + (center - self.pose.origin).dot(n) / d.dot(n) * d
+        } else {
+            pinhole
+        };
+        Some(Ray {
+            a: origin,
+            b: focus - origin,
+            time: self.pose.time(sampler),
+            wavelengths: None,
+        })
+    }
+
+    fn pose(&self) -> &Pose {
+        &self.pose
+    }
+
+    /// The eyes look through a shared window at the convergence distance
+    /// rather than turning inwards, so verticals stay parallel, and keep in
+    /// focus there.
+    fn stereo_pair(&self, ipd: f64, convergence: f64) -> [Box<dyn Camera>; 2] {
+        let origin = self.pose.origin;
+        let center = self.lower_left_corner + (self.horizontal + self.vertical) / 2.;
+        let scale = convergence / (origin - center).dot(self.pose.w);
+        let window = origin + scale * (center - origin);
+        [-0.5, 0.5].map(|side| -> Box<dyn Camera> {
+            Box::new(Perspective {
+                pose: self.pose.shifted(side * ipd),
+                lower_left_corner: window - scale * (self.horizontal + self.vertical) / 2.,
+                horizontal: scale * self.horizontal,
+                vertical: scale * self.vertical,
+                ..*self
+            })
+        })
+    }
+}
+
+/// Parallel rays from points across the image, so distant things look as
+/// big as near ones.
+#[derive(Copy, Clone, Debug)]
+pub struct Orthographic {
+    pub pose: Pose,
+    pub lower_left_corner: Vec3,
+    pub horizontal: Vec3,
+    pub vertical: Vec3,
+}
+
+impl Orthographic {
+    /// A camera at `lookfrom` looking toward `lookat`, turned so `vup`
+    /// points up in the image, seeing a view `height` units high and
+    /// `aspect` times as wide.
+    pub fn new(lookfrom: Vec3, lookat: Vec3, vup: Vec3, height: f64, aspect: f64) -> Orthographic {
+        let pose = Pose::look_at(lookfrom, lookat, vup);
+        Orthographic {
+            pose,
+            lower_left_corner: lookfrom - height / 2. * (aspect * pose.u + pose.v),
+            horizontal: height * aspect * pose.u,
+            vertical: height * pose.v,
+        }
+    }
+}
+
+impl Camera for Orthographic {
+    fn generate_ray(&self, u: f64, v: f64, sampler: &mut dyn Sampler) -> Option<Ray> {
+        Some(Ray {
+            a: self.lower_left_corner + u * self.horizontal + v * self.vertical,
+            b: -self.pose.w,
+            time: self.pose.time(sampler),
+            wavelengths: None,
+        })
+    }
+
+    fn pose(&self) -> &Pose {
+        &self.pose
+    }
+
+    /// The eyes look straight ahead, side by side.
+    fn stereo_pair(&self, ipd: f64, _convergence: f64) -> [Box<dyn Camera>; 2] {
+        [-0.5, 0.5].map(|side| -> Box<dyn Camera> {
+            Box::new(Orthographic {
+                pose: self.pose.shifted(side * ipd),
+                lower_left_corner: self.lower_left_corner + side * ipd * self.pose.u,
+                ..*self
+            })
+        })
+    }
+}
+
+/// How far from the center of a fisheye image a direction lands, given its
+/// angle to the view direction.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FisheyeMapping {
+    /// In proportion to the angle, as dome projections expect.
+    Equidistant,
+    /// In proportion to the sine of half the angle, so equal areas of the
+    /// image see equal solid angles.
+    Equisolid,
+}
+
+/// A circle as high as the image covering a field of view of `fov` degrees,
+/// possibly more than 180, with the corners seeing further still.
+#[derive(Copy, Clone, Debug)]
+pub struct Fisheye {
+    pub pose: Pose,
+    pub mapping: FisheyeMapping,
+    pub fov: f64,
+    /// How many times wider than high images are.
+    pub aspect: f64,
+}
+
+impl Camera for Fisheye {
+    fn generate_ray(&self, u: f64, v: f64, sampler: &mut dyn Sampler) -> Option<Ray> {
+        // from the center, in units of half the image height
+        let (x, y) = ((2. * u - 1.) * self.aspect, 2. * v - 1.);
+        let radius = (x * x + y * y).sqrt();
+        let half_fov = self.fov.to_radians() / 2.;
+        let theta = match self.mapping {
+            FisheyeMapping::Equidistant => radius * half_fov,
+            FisheyeMapping::Equisolid => 2. * (radius * (half_fov / 2.).sin()).min(1.).asin(),
+        }
+        .min(std::f64::consts::PI);
+        let phi = y.atan2(x);
+        let pose = &self.pose;
+        Some(Ray {
+            a: pose.origin,
+            b: theta.sin() * (phi.cos() * pose.u + phi.sin() * pose.v) - theta.cos() * pose.w,
+            time: pose.time(sampler),
+            wavelengths: None,
+        })
+    }
+
+    fn pose(&self) -> &Pose {
+        &self.pose
+    }
+
+    /// The eyes look straight ahead, side by side.
+    fn stereo_pair(&self, ipd: f64, _convergence: f64) -> [Box<dyn Camera>; 2] {
+        [-0.5, 0.5].map(|side| -> Box<dyn Camera> {
+            Box::new(Fisheye {
+                pose: self.pose.shifted(side * ipd),
+                ..*self
+            })
+        })
+    }
+}
+
+/// All the way around, longitude across the image and latitude up it,
+/// centered on the view direction, for images twice as wide as high.
+#[derive(Copy, Clone, Debug)]
+pub struct Equirectangular {
+    pub pose: Pose,
+    /// For stereo, each ray starts this far to the right of the center,
+    /// across its own direction, and aims at the point `convergence` away
+    /// along it.
+    pub eye_offset: f64,
+    pub convergence: f64,
+}
+
+impl Equirectangular {
+    /// A camera at `center` with the middle of the image toward `lookat`,
+    /// turned so `vup` is up.
+    pub fn new(center: Vec3, lookat: Vec3, vup: Vec3) -> Equirectangular {
+        Equirectangular {
+            pose: Pose::look_at(center, lookat, vup),
+            eye_offset: 0.,
+            convergence: f64::INFINITY,
+        }
+    }
+}
+
+impl Camera for Equirectangular {
+    fn generate_ray(&self, u: f64, v: f64, sampler: &mut dyn Sampler) -> Option<Ray> {
+        let pose = &self.pose;
+        let phi = (u - 0.5) * 2. * std::f64::consts::PI;
+        let theta = (v - 0.5) * std::f64::consts::PI;
+        let across = phi.sin() * pose.u - phi.cos() * pose.w;
+        let direction = theta.cos() * across + theta.sin() * pose.v;
+        let right = phi.cos() * pose.u + phi.sin() * pose.w;
+        let origin = pose.origin + self.eye_offset * right;
+        let b = if self.convergence.is_finite() {
+            pose.origin + self.convergence * direction - origin
+        } else {
+            direction
+        };
+        Some(Ray {
+            a: origin,
+            b,
+            time: pose.time(sampler),
+            wavelengths: None,
+        })
+    }
+
+    fn pose(&self) -> &Pose {
+        &self.pose
+    }
+
+    /// Each eye sees all the way around from either side of the center, as
+    /// VR headsets expect.
+    fn stereo_pair(&self, ipd: f64, convergence: f64) -> [Box<dyn Camera>; 2] {
+        [-0.5, 0.5].map(|side| -> Box<dyn Camera> {
+            Box::new(Equirectangular {
+                eye_offset: side * ipd,
+                convergence,
+                ..*self
+            })
+        })
+    }
+}
+
+/// Rays traced from the film out through a compound lens, the front of
+/// which is at the camera's origin.
+#[derive(Clone, Debug)]
+pub struct Realistic {
+    pub pose: Pose,
+    pub lens: LensSystem,
+    /// How many times wider than high images are.
+    pub aspect: f64,
+}
+
+impl Realistic {
+    /// A camera at `lookfrom` looking toward `lookat` through `lens`, turned
+    /// so `vup` points up in the image, focused `focus_dist` away, for
+    /// images `aspect` times wider than they are high.
+    pub fn new(
+        lookfrom: Vec3,
+        lookat: Vec3,
+        vup: Vec3,
+        mut lens: LensSystem,
+        focus_dist: f64,
+        aspect: f64,
+    ) -> Realistic {
+        lens.focus(focus_dist);
+        Realistic {
+            pose: Pose::look_at(lookfrom, lookat, vup),
+            lens,
+            aspect,
+        }
+    }
+}
+
+impl Camera for Realistic {
+    fn generate_ray(&self, u: f64, v: f64, sampler: &mut dyn Sampler) -> Option<Ray> {
+        // the lens turns the image upside down on the film
+        let (width, height) = self.lens.film_size(self.aspect);
+        let rear = sample_disk(sampler.get_2d());
+        let film = ((0.5 - u) * width, (0.5 - v) * height);
+        let (p, d) = self.lens.trace_from_film(film, rear)?;
+        let pose = &self.pose;
+        let to_world = |v: Vec3| v.x() * pose.u + v.y() * pose.v - v.z() * pose.w;
+        Some(Ray {
+            a: pose.origin + self.lens.scale * to_world(p - Vec3(0., 0., self.lens.front_z())),
+            b: to_world(d),
+            time: pose.time(sampler),
+            wavelengths: None,
+        })
+    }
+
+    fn pose(&self) -> &Pose {
+        &self.pose
+    }
+
+    /// The eyes look straight ahead, side by side.
+    fn stereo_pair(&self, ipd: f64, _convergence: f64) -> [Box<dyn Camera>; 2] {
+        [-0.5, 0.5].map(|side| -> Box<dyn Camera> {
+            Box::new(Realistic {
+                pose: self.pose.shifted(side * ipd),
+                ..self.clone()
+            })
+        })
+    }
+}
@@ -0,0 +1,90 @@
+use crate::aabb::Aabb;
+use crate::{HitRecord, Hittable, Ray};
+
+/// How a `Csg` node combines its two operands.
+#[derive(Debug, Copy, Clone)]
+pub enum CsgOp {
+    /// Inside either.
+    Union,
+    /// Inside both.
+    Intersection,
+    /// Inside `a` but not `b`.
+    Difference,
+}
+
+/// Constructive solid geometry: a solid made by combining two closed
+/// objects, like a sphere with a cylindrical hole drilled through it.
+///
+/// The ray is walked through the operands' surfaces in order, keeping track
+/// of whether it is inside each, until it crosses the boundary of the
+/// combination. Hits keep the material of the operand whose surface they lie
+/// on, with `front_face` saying whether the ray enters the combined solid.
+pub struct Csg {
+    pub a: Box<dyn Hittable>,
+    pub b: Box<dyn Hittable>,
+    pub op: CsgOp,
+}
+
+impl Csg {
+    fn inside(&self, in_a: bool, in_b: bool) -> bool {
+        match self.op {
+            CsgOp::Union => in_a || in_b,
+            CsgOp::Intersection => in_a && in_b,
+            CsgOp::Difference => in_a && !in_b,
+        }
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        // look past t_max, since whether the ray starts out inside an operand
+        // is only known from the way it crosses that operand's next surface
+        let mut hit_a = self.a.hit(r, t_min, f64::MAX);
+        let mut hit_b = self.b.hit(r, t_min, f64::MAX);
+        let mut in_a = hit_a.is_some_and(|h| !h.front_face);
+        let mut in_b = hit_b.is_some_and(|h| !h.front_face);
+        loop {
+            let from_a = match (&hit_a, &hit_b) {
+                (Some(a), Some(b)) => a.t <= b.t,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => return None,
+            };
+            let mut hit_record = if from_a { hit_a } else { hit_b }?;
+            if hit_record.t >= t_max {
+                return None;
+            }
+            let was_inside = self.inside(in_a, in_b);
+            if from_a {
+                in_a = hit_record.front_face;
+            } else {
+                in_b = hit_record.front_face;
+            }
+            let now_inside = self.inside(in_a, in_b);
+            if now_inside != was_inside {
+                hit_record.front_face = now_inside;
+                return Some(hit_record);
+            }
+            if from_a {
+                hit_a = self.a.hit(r, hit_record.t, f64::MAX);
+            } else {
+                hit_b = self.b.hit(r, hit_record.t, f64::MAX);
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let a = self.a.bounding_box();
+        match self.op {
+            CsgOp::Union => Some(Aabb::surrounding(&a?, &self.b.bounding_box()?)),
+            CsgOp::Intersection => match (a, self.b.bounding_box()) {
+                (Some(a), Some(b)) => Some(Aabb {
+                    min: a.min.max(b.min),
+                    max: a.max.min(b.max),
+                }),
+                (a, b) => a.or(b),
+            },
+            CsgOp::Difference => a,
+        }
+    }
+}
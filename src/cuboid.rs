@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::vec3::Vec3;
+use crate::{face_normal, HitRecord, Hittable, Material, Ray, PIXEL_SPREAD};
+
+/// An axis-aligned box between corners `min` and `max`, intersected with the
+/// slab test. Each face is mapped to (u, v) along the two axes it spans.
+pub struct Cuboid {
+    pub min: Vec3,
+    pub max: Vec3,
+    pub material: Arc<dyn Material>,
+}
+
+fn axis(v: &Vec3, i: usize) -> f64 {
+    match i {
+        0 => v.x(),
+        1 => v.y(),
+        _ => v.z(),
+    }
+}
+
+fn unit_axis(i: usize) -> Vec3 {
+    match i {
+        0 => Vec3(1., 0., 0.),
+        1 => Vec3(0., 1., 0.),
+        _ => Vec3(0., 0., 1.),
+    }
+}
+
+impl Cuboid {
+    /// Where the ray enters and leaves the box, each with the axis of the
+    /// face it crosses there.
+    fn slabs(&self, r: &Ray) -> Option<((f64, usize), (f64, usize))> {
+        let mut enter = (f64::MIN, 0);
+        let mut exit = (f64::MAX, 0);
+        for i in 0..3 {
+            let inv_d = 1. / axis(r.direction(), i);
+            let mut t0 = (axis(&self.min, i) - axis(r.origin(), i)) * inv_d;
+            let mut t1 = (axis(&self.max, i) - axis(r.origin(), i)) * inv_d;
+            if inv_d < 0. {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            if t0 > enter.0 {
+                enter = (t0, i);
+            }
+            if t1 < exit.0 {
+                exit = (t1, i);
+            }
+        }
+        if enter.0 < exit.0 {
+            Some((enter, exit))
+        } else {
+            None
+        }
+    }
+
+    fn hit_record(&self, r: &Ray, t: f64, k: usize) -> HitRecord<'_> {
+        let p = r.point_at_parameter(t);
+        let center = 0.5 * (self.min + self.max);
+        let outward = if axis(&p, k) > axis(&center, k) {
+            unit_axis(k)
+        } else {
+            -unit_axis(k)
+        };
+        let (normal, front_face) = face_normal(r, outward);
+        let (i, j) = ((k + 1) % 3, (k + 2) % 3);
+        let size = self.max - self.min;
+        let coordinate = |a| (axis(&p, a) - axis(&self.min, a)) / axis(&size, a);
+        HitRecord {
+            t,
+            p,
+            normal,
+            geometric_normal: normal,
+            front_face,
+            tangent: unit_axis(i),
+            u: coordinate(i),
+            v: coordinate(j),
+            uv_width: t * r.direction().length() * PIXEL_SPREAD / axis(&size, i),
+            exterior_ior: 1.,
+            min_roughness: 0.,
+            wavelengths: r.wavelengths,
+            material: &*self.material,
+        }
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let ((t_enter, enter_axis), (t_exit, exit_axis)) = self.slabs(r)?;
+        // the far side shows through where the near one is cut out
+        [(t_enter, enter_axis), (t_exit, exit_axis)]
+            .iter()
+            .filter(|&&(t, _)| t > t_min && t < t_max)
+            .map(|&(t, k)| self.hit_record(r, t, k))
+            .find(HitRecord::opaque)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::from_points(&[self.min, self.max]))
+    }
+}
@@ -0,0 +1,216 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::onb::Onb;
+use crate::vec3::Vec3;
+use crate::{HitRecord, Hittable, Material, Ray};
+
+/// A straight strand of hair or fur: a thin open cylinder from `p0` to `p1`.
+///
+/// Only hits where a ray enters the fiber are reported, so rays transmitted
+/// through it by a hair material don't immediately hit its far side. Hits
+/// carry the strand direction as their tangent and the true cylinder normal,
+/// from which the material recovers its longitudinal and azimuthal angles.
+pub struct Fiber {
+    pub p0: Vec3,
+    pub p1: Vec3,
+    pub radius: f64,
+    pub material: Arc<dyn Material>,
+}
+
+/// Where a ray enters a thin open tube tapering from `r0` at `p0` to `r1` at
+/// `p1`.
+struct TubeHit {
+    t: f64,
+    normal: Vec3,
+    axis: Vec3,
+    /// Fraction of the way from `p0` to `p1`.
+    along: f64,
+    /// Angle around the axis, in [-pi, pi].
+    angle: f64,
+}
+
+fn hit_tube(
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+    (p0, r0): (Vec3, f64),
+    (p1, r1): (Vec3, f64),
+) -> Option<TubeHit> {
+    let axis = p1 - p0;
+    let length = axis.length();
+    let axis = axis / length;
+    let slope = (r1 - r0) / length;
+    let oc = *r.origin() - p0;
+    let d = *r.direction();
+    let (oc_along, d_along) = (oc.dot(axis), d.dot(axis));
+    let d_perp = d - d_along * axis;
+    let oc_perp = oc - oc_along * axis;
+    let radius_o = r0 + slope * oc_along;
+    let a = d_perp.dot(d_perp) - slope * slope * d_along * d_along;
+    let b = 2. * (oc_perp.dot(d_perp) - slope * radius_o * d_along);
+    let c = oc_perp.dot(oc_perp) - radius_o * radius_o;
+    let discriminant = b * b - 4. * a * c;
+    if a <= 0. || discriminant <= 0. {
+        return None;
+    }
+    // the near root is where the ray enters
+    let t = (-b - discriminant.sqrt()) / (2. * a);
+    if t <= t_min || t >= t_max {
+        return None;
+    }
+    let p = r.point_at_parameter(t);
+    let along = (p - p0).dot(axis);
+    if along < 0. || along > length {
+        return None;
+    }
+    let radial = (p - (p0 + along * axis)).unit();
+    let around = Onb::from_w(&axis);
+    Some(TubeHit {
+        t,
+        // tilted back along the axis by the taper
+        normal: (radial - slope * axis).unit(),
+        axis,
+        along: along / length,
+        angle: radial.dot(around.v).atan2(radial.dot(around.u)),
+    })
+}
+
+/// A box around the tube from `p0` to `p1`, generous by the larger radius.
+fn tube_box((p0, r0): (Vec3, f64), (p1, r1): (Vec3, f64)) -> Aabb {
+    let r = r0.max(r1) * Vec3(1., 1., 1.);
+    Aabb {
+        min: p0.min(p1) - r,
+        max: p0.max(p1) + r,
+    }
+}
+
+impl Hittable for Fiber {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let hit = hit_tube(
+            r,
+            t_min,
+            t_max,
+            (self.p0, self.radius),
+            (self.p1, self.radius),
+        )?;
+        // the near root is always where the ray enters, so the outward normal
+        // already faces it
+        let hit_record = HitRecord {
+            t: hit.t,
+            p: r.point_at_parameter(hit.t),
+            normal: hit.normal,
+            geometric_normal: hit.normal,
+            front_face: true,
+            tangent: hit.axis,
+            u: hit.angle / (2. * PI) + 0.5,
+            v: hit.along,
+            uv_width: hit.t * r.direction().length() * crate::PIXEL_SPREAD
+                / (2. * PI * self.radius),
+            exterior_ior: 1.,
+            min_roughness: 0.,
+            wavelengths: r.wavelengths,
+            material: &*self.material,
+        };
+        Some(hit_record).filter(HitRecord::opaque)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(tube_box((self.p0, self.radius), (self.p1, self.radius)))
+    }
+}
+
+/// A curved strand, like a hair, blade of grass or cable, along a cubic
+/// Bezier curve, its width varying linearly from root to tip.
+///
+/// The curve is flattened into straight tapered segments when built, and each
+/// is intersected like a `Fiber`, only where rays enter it. v runs from 0 at
+/// the root to 1 at the tip.
+pub struct BezierCurve {
+    /// Points along the curve with the radius there.
+    points: Vec<(Vec3, f64)>,
+    pub material: Arc<dyn Material>,
+}
+
+impl BezierCurve {
+    /// A curve through `control[0]` and `control[3]`, pulled toward the two
+    /// middle points, with radius `r0` at the root and `r1` at the tip, built
+    /// from `segments` straight pieces.
+    pub fn new(
+        control: [Vec3; 4],
+        (r0, r1): (f64, f64),
+        segments: usize,
+        material: Arc<dyn Material>,
+    ) -> BezierCurve {
+        let [b0, b1, b2, b3] = control;
+        let points = (0..=segments)
+            .map(|i| {
+                let s = i as f64 / segments as f64;
+                let t = 1. - s;
+                let p = t * t * t * b0 + 3. * t * t * s * b1 + 3. * t * s * s * b2 + s * s * s * b3;
+                (p, (1. - s) * r0 + s * r1)
+            })
+            .collect();
+        BezierCurve { points, material }
+    }
+
+    /// The same from the control points of a uniform cubic B-spline segment,
+    /// which joins smoothly to neighbouring segments sharing three of them.
+    pub fn from_b_spline(
+        control: [Vec3; 4],
+        radii: (f64, f64),
+        segments: usize,
+        material: Arc<dyn Material>,
+    ) -> BezierCurve {
+        let [p0, p1, p2, p3] = control;
+        let bezier = [
+            (p0 + 4. * p1 + p2) / 6.,
+            (4. * p1 + 2. * p2) / 6.,
+            (2. * p1 + 4. * p2) / 6.,
+            (p1 + 4. * p2 + p3) / 6.,
+        ];
+        BezierCurve::new(bezier, radii, segments, material)
+    }
+}
+
+impl Hittable for BezierCurve {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let segments = self.points.len() - 1;
+        let mut closest = None;
+        let mut t_max = t_max;
+        for (i, pair) in self.points.windows(2).enumerate() {
+            if let Some(hit) = hit_tube(r, t_min, t_max, pair[0], pair[1]) {
+                let radius = pair[0].1.max(pair[1].1);
+                let hit_record = HitRecord {
+                    t: hit.t,
+                    p: r.point_at_parameter(hit.t),
+                    normal: hit.normal,
+                    geometric_normal: hit.normal,
+                    front_face: true,
+                    tangent: hit.axis,
+                    u: hit.angle / (2. * PI) + 0.5,
+                    v: (i as f64 + hit.along) / segments as f64,
+                    uv_width: hit.t * r.direction().length() * crate::PIXEL_SPREAD
+                        / (2. * PI * radius),
+                    exterior_ior: 1.,
+                    min_roughness: 0.,
+                    wavelengths: r.wavelengths,
+                    material: &*self.material,
+                };
+                if hit_record.opaque() {
+                    t_max = hit.t;
+                    closest = Some(hit_record);
+                }
+            }
+        }
+        closest
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.points
+            .windows(2)
+            .map(|pair| tube_box(pair[0], pair[1]))
+            .reduce(|a, b| Aabb::surrounding(&a, &b))
+    }
+}
@@ -0,0 +1,52 @@
+use crate::pdf::Pdf;
+use crate::sampler::Sampler;
+use crate::spectrum::Wavelengths;
+use crate::texture::Texture;
+use crate::vec3::Vec3;
+use crate::{HitRecord, Material, Ray, Scatter};
+
+/// Wraps a material, cutting holes in it where the `alpha` texture (read as
+/// a scalar) falls below one: leaves, fences and decals on simple geometry.
+pub struct Cutout {
+    pub alpha: Box<dyn Texture>,
+    pub material: Box<dyn Material>,
+}
+
+impl Material for Cutout {
+    fn scatter(
+        &self,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        self.material.scatter(r, hit_record, sampler)
+    }
+
+    fn pdf(&self, r: &Ray, hit_record: &HitRecord) -> Option<Box<dyn Pdf>> {
+        self.material.pdf(r, hit_record)
+    }
+
+    fn eval(&self, r: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Vec3 {
+        self.material.eval(r, hit_record, direction)
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Vec3) -> Vec3 {
+        self.material.emitted(u, v, p)
+    }
+
+    fn emitted_spectral(&self, u: f64, v: f64, p: &Vec3, wavelengths: &Wavelengths) -> Vec3 {
+        self.material.emitted_spectral(u, v, p, wavelengths)
+    }
+
+    fn alpha(&self, u: f64, v: f64, p: &Vec3) -> f64 {
+        self.alpha.scalar(u, v, p) * self.material.alpha(u, v, p)
+    }
+
+    fn two_sided(&self) -> bool {
+        self.material.two_sided()
+    }
+
+    fn glossy(&self, hit_record: &HitRecord) -> bool {
+        self.material.glossy(hit_record)
+    }
+}
@@ -0,0 +1,195 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::onb::Onb;
+use crate::vec3::Vec3;
+use crate::{face_normal, HitRecord, Hittable, Material, Ray, PIXEL_SPREAD};
+
+/// A cylinder of `radius` around the segment from `base` to `base + axis`,
+/// closed by disks at both ends if `capped`.
+pub struct Cylinder {
+    pub base: Vec3,
+    pub axis: Vec3,
+    pub radius: f64,
+    pub capped: bool,
+    pub material: Arc<dyn Material>,
+}
+
+/// A cone, or a truncated one, around the segment from `base` to
+/// `base + axis`, with radius `base_radius` at the base and `top_radius` at
+/// the top, either of which may be zero. Closed by disks at the ends if
+/// `capped`.
+pub struct Cone {
+    pub base: Vec3,
+    pub axis: Vec3,
+    pub base_radius: f64,
+    pub top_radius: f64,
+    pub capped: bool,
+    pub material: Arc<dyn Material>,
+}
+
+/// The box around two discs centered on `base` and `base + axis`, facing
+/// along the axis, of radius `r0` and `r1`.
+fn frustum_box(base: Vec3, axis: Vec3, r0: f64, r1: f64) -> Aabb {
+    let n = axis.unit();
+    // a disc of unit radius reaches sqrt(1 - n_i^2) along axis i
+    let reach = Vec3(
+        (1. - n.x() * n.x()).max(0.).sqrt(),
+        (1. - n.y() * n.y()).max(0.).sqrt(),
+        (1. - n.z() * n.z()).max(0.).sqrt(),
+    );
+    let top = base + axis;
+    Aabb::surrounding(
+        &Aabb::from_points(&[base - r0 * reach, base + r0 * reach]),
+        &Aabb::from_points(&[top - r1 * reach, top + r1 * reach]),
+    )
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let frustum = Frustum {
+            base: self.base,
+            axis: self.axis,
+            r0: self.radius,
+            r1: self.radius,
+            capped: self.capped,
+        };
+        frustum.hit(r, t_min, t_max, &*self.material)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(frustum_box(self.base, self.axis, self.radius, self.radius))
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let frustum = Frustum {
+            base: self.base,
+            axis: self.axis,
+            r0: self.base_radius,
+            r1: self.top_radius,
+            capped: self.capped,
+        };
+        frustum.hit(r, t_min, t_max, &*self.material)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(frustum_box(
+            self.base,
+            self.axis,
+            self.base_radius,
+            self.top_radius,
+        ))
+    }
+}
+
+/// Which part of the surface a ray crosses.
+#[derive(Copy, Clone)]
+enum Part {
+    Side,
+    Bottom,
+    Top,
+}
+
+/// The shape both primitives share: a side whose radius varies linearly from
+/// `r0` at the base to `r1` at the top.
+struct Frustum {
+    base: Vec3,
+    axis: Vec3,
+    r0: f64,
+    r1: f64,
+    capped: bool,
+}
+
+impl Frustum {
+    fn hit<'a>(
+        &self,
+        r: &Ray,
+        t_min: f64,
+        t_max: f64,
+        material: &'a dyn Material,
+    ) -> Option<HitRecord<'a>> {
+        // work in a frame with the axis along z, from 0 at the base to height
+        let height = self.axis.length();
+        let frame = Onb::from_w(&self.axis);
+        let o = frame.to_local(&(*r.origin() - self.base));
+        let d = frame.to_local(r.direction());
+        let slope = (self.r1 - self.r0) / height;
+        let radius_at = |z: f64| self.r0 + slope * z;
+
+        let mut crossings = Vec::with_capacity(4);
+        let a = d.x() * d.x() + d.y() * d.y() - slope * slope * d.z() * d.z();
+        let b = 2. * (o.x() * d.x() + o.y() * d.y() - slope * radius_at(o.z()) * d.z());
+        let c = o.x() * o.x() + o.y() * o.y() - radius_at(o.z()) * radius_at(o.z());
+        let discriminant = b * b - 4. * a * c;
+        if a.abs() > 1e-12 && discriminant > 0. {
+            for &t in &[
+                (-b - discriminant.sqrt()) / (2. * a),
+                (-b + discriminant.sqrt()) / (2. * a),
+            ] {
+                let z = o.z() + t * d.z();
+                // stay on the right nappe of the double cone
+                if (0. ..=height).contains(&z) && radius_at(z) >= 0. {
+                    crossings.push((t, Part::Side));
+                }
+            }
+        }
+        if self.capped && d.z().abs() > 1e-12 {
+            for &(z, radius, part) in &[(0., self.r0, Part::Bottom), (height, self.r1, Part::Top)] {
+                let t = (z - o.z()) / d.z();
+                let (x, y) = (o.x() + t * d.x(), o.y() + t * d.y());
+                if radius > 0. && x * x + y * y <= radius * radius {
+                    crossings.push((t, part));
+                }
+            }
+        }
+        crossings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        let max_radius = self.r0.max(self.r1);
+        let footprint = r.direction().length() * PIXEL_SPREAD;
+        crossings
+            .into_iter()
+            .filter(|&(t, _)| t > t_min && t < t_max)
+            .map(|(t, part)| {
+                let local = o + t * d;
+                let (outward, tangent, u, v) = match part {
+                    Part::Side => {
+                        let outward = Vec3(local.x(), local.y(), -slope * radius_at(local.z()));
+                        let angle = local.y().atan2(local.x());
+                        let tangent = Vec3(-local.y(), local.x(), 0.);
+                        (
+                            outward,
+                            tangent,
+                            angle / (2. * PI) + 0.5,
+                            local.z() / height,
+                        )
+                    }
+                    Part::Bottom | Part::Top => {
+                        let sign = if let Part::Top = part { 1. } else { -1. };
+                        let u = 0.5 + local.x() / (2. * max_radius);
+                        let v = 0.5 + local.y() / (2. * max_radius);
+                        (Vec3(0., 0., sign), Vec3(1., 0., 0.), u, v)
+                    }
+                };
+                let (normal, front_face) = face_normal(r, frame.local(&outward).unit());
+                HitRecord {
+                    t,
+                    p: r.point_at_parameter(t),
+                    normal,
+                    geometric_normal: normal,
+                    front_face,
+                    tangent: frame.local(&tangent),
+                    u,
+                    v,
+                    uv_width: t * footprint / (2. * PI * max_radius),
+                    exterior_ior: 1.,
+                    min_roughness: 0.,
+                    wavelengths: r.wavelengths,
+                    material,
+                }
+            })
+            .find(HitRecord::opaque)
+    }
+}
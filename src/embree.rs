@@ -0,0 +1,212 @@
+use std::os::raw::{c_char, c_uint, c_void};
+use std::ptr;
+
+use crate::aabb::Aabb;
+use crate::mesh::TriangleMesh;
+use crate::{HitRecord, Hittable, Ray};
+
+/// A triangle mesh traced by Intel Embree rather than the crate's own BVH,
+/// for the biggest meshes, where Embree's hand-tuned traversal pays off.
+/// Embree finds which face a ray hits first, in single precision; the hit
+/// is then filled in from the mesh as `TriangleMesh::hit` would, so
+/// materials, normals and surface coordinates work the same either way.
+///
+/// Needs the `embree` feature and the Embree 3 library to link against.
+pub struct EmbreeMesh {
+    pub mesh: TriangleMesh,
+    device: *mut c_void,
+    scene: *mut c_void,
+}
+
+// Embree scenes may be traced from any number of threads at once once
+// committed, and are never changed afterwards.
+unsafe impl Send for EmbreeMesh {}
+unsafe impl Sync for EmbreeMesh {}
+
+impl EmbreeMesh {
+    /// Hand `mesh` to Embree, which builds its own tree over the faces.
+    ///
+    /// Panics if Embree can't be started.
+    pub fn new(mesh: TriangleMesh) -> EmbreeMesh {
+        unsafe {
+            let device = rtcNewDevice(ptr::null());
+            assert!(!device.is_null(), "couldn't start Embree");
+            let scene = rtcNewScene(device);
+            let geometry = rtcNewGeometry(device, RTC_GEOMETRY_TYPE_TRIANGLE);
+            let vertices = rtcSetNewGeometryBuffer(
+                geometry,
+                RTC_BUFFER_TYPE_VERTEX,
+                0,
+                RTC_FORMAT_FLOAT3,
+                3 * std::mem::size_of::<f32>(),
+                mesh.positions.len(),
+            ) as *mut f32;
+            let vertices = std::slice::from_raw_parts_mut(vertices, 3 * mesh.positions.len());
+            for (chunk, p) in vertices.chunks_mut(3).zip(&mesh.positions) {
+                chunk.copy_from_slice(&[p.x() as f32, p.y() as f32, p.z() as f32]);
+            }
+            let indices = rtcSetNewGeometryBuffer(
+                geometry,
+                RTC_BUFFER_TYPE_INDEX,
+                0,
+                RTC_FORMAT_UINT3,
+                3 * std::mem::size_of::<u32>(),
+                mesh.indices.len(),
+            ) as *mut u32;
+            let indices = std::slice::from_raw_parts_mut(indices, 3 * mesh.indices.len());
+            for (chunk, face) in indices.chunks_mut(3).zip(&mesh.indices) {
+                chunk.copy_from_slice(face);
+            }
+            rtcCommitGeometry(geometry);
+            rtcAttachGeometry(scene, geometry);
+            rtcReleaseGeometry(geometry);
+            rtcCommitScene(scene);
+            EmbreeMesh {
+                mesh,
+                device,
+                scene,
+            }
+        }
+    }
+
+    /// The face `r` hits first between `t_min` and `t_max`, where, and the
+    /// weights of its second and third vertices there.
+    fn intersect(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<(usize, f64, (f64, f64))> {
+        let (o, d) = (r.origin(), r.direction());
+        let mut context = RTCIntersectContext {
+            flags: RTC_INTERSECT_CONTEXT_FLAG_INCOHERENT,
+            filter: ptr::null_mut(),
+            inst_id: [RTC_INVALID_GEOMETRY_ID],
+        };
+        let mut ray_hit = RTCRayHit {
+            ray: RTCRay {
+                org: [o.x() as f32, o.y() as f32, o.z() as f32],
+                tnear: t_min as f32,
+                dir: [d.x() as f32, d.y() as f32, d.z() as f32],
+                time: r.time() as f32,
+                tfar: t_max.min(f32::MAX as f64) as f32,
+                mask: u32::MAX,
+                id: 0,
+                flags: 0,
+            },
+            hit: RTCHit {
+                ng: [0.; 3],
+                u: 0.,
+                v: 0.,
+                prim_id: RTC_INVALID_GEOMETRY_ID,
+                geom_id: RTC_INVALID_GEOMETRY_ID,
+                inst_id: [RTC_INVALID_GEOMETRY_ID],
+            },
+        };
+        unsafe { rtcIntersect1(self.scene, &mut context, &mut ray_hit) };
+        if ray_hit.hit.geom_id == RTC_INVALID_GEOMETRY_ID {
+            return None;
+        }
+        let hit = &ray_hit.hit;
+        Some((
+            hit.prim_id as usize,
+            ray_hit.ray.tfar as f64,
+            (hit.u as f64, hit.v as f64),
+        ))
+    }
+}
+
+impl Drop for EmbreeMesh {
+    fn drop(&mut self) {
+        unsafe {
+            rtcReleaseScene(self.scene);
+            rtcReleaseDevice(self.device);
+        }
+    }
+}
+
+impl Hittable for EmbreeMesh {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mut t_min = t_min;
+        loop {
+            let (face, t, bary) = self.intersect(r, t_min, t_max)?;
+            let hit_record = self.mesh.face_hit_record(r, face, t, bary);
+            // cut out here: carry on to whatever is behind
+            if hit_record.opaque() {
+                return Some(hit_record);
+            }
+            t_min = t + 0.0001;
+        }
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.mesh.bounding_box()
+    }
+}
+
+// The parts of the Embree 3 API used above, from rtcore_device.h,
+// rtcore_scene.h, rtcore_geometry.h, rtcore_buffer.h and rtcore_ray.h.
+
+const RTC_GEOMETRY_TYPE_TRIANGLE: c_uint = 0;
+const RTC_BUFFER_TYPE_INDEX: c_uint = 0;
+const RTC_BUFFER_TYPE_VERTEX: c_uint = 1;
+const RTC_FORMAT_UINT3: c_uint = 0x5003;
+const RTC_FORMAT_FLOAT3: c_uint = 0x9003;
+const RTC_INTERSECT_CONTEXT_FLAG_INCOHERENT: c_uint = 0;
+const RTC_INVALID_GEOMETRY_ID: c_uint = u32::MAX;
+
+#[repr(C)]
+struct RTCIntersectContext {
+    flags: c_uint,
+    filter: *mut c_void,
+    inst_id: [c_uint; 1],
+}
+
+#[repr(C, align(16))]
+struct RTCRay {
+    org: [f32; 3],
+    tnear: f32,
+    dir: [f32; 3],
+    time: f32,
+    tfar: f32,
+    mask: c_uint,
+    id: c_uint,
+    flags: c_uint,
+}
+
+#[repr(C, align(16))]
+struct RTCHit {
+    ng: [f32; 3],
+    u: f32,
+    v: f32,
+    prim_id: c_uint,
+    geom_id: c_uint,
+    inst_id: [c_uint; 1],
+}
+
+#[repr(C, align(16))]
+struct RTCRayHit {
+    ray: RTCRay,
+    hit: RTCHit,
+}
+
+#[link(name = "embree3")]
+extern "C" {
+    fn rtcNewDevice(config: *const c_char) -> *mut c_void;
+    fn rtcReleaseDevice(device: *mut c_void);
+    fn rtcNewScene(device: *mut c_void) -> *mut c_void;
+    fn rtcCommitScene(scene: *mut c_void);
+    fn rtcReleaseScene(scene: *mut c_void);
+    fn rtcNewGeometry(device: *mut c_void, kind: c_uint) -> *mut c_void;
+    fn rtcSetNewGeometryBuffer(
+        geometry: *mut c_void,
+        kind: c_uint,
+        slot: c_uint,
+        format: c_uint,
+        byte_stride: usize,
+        item_count: usize,
+    ) -> *mut c_void;
+    fn rtcCommitGeometry(geometry: *mut c_void);
+    fn rtcAttachGeometry(scene: *mut c_void, geometry: *mut c_void) -> c_uint;
+    fn rtcReleaseGeometry(geometry: *mut c_void);
+    fn rtcIntersect1(
+        scene: *mut c_void,
+        context: *mut RTCIntersectContext,
+        ray_hit: *mut RTCRayHit,
+    );
+}
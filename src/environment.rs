@@ -0,0 +1,252 @@
+use std::f64::consts::PI;
+use std::path::Path;
+
+use crate::bitmap::Bitmap;
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+use crate::Ray;
+
+/// What rays that miss everything see: the light arriving from far away in
+/// each direction, which lights the scene along with its emitters.
+pub trait Environment: Send + Sync {
+    /// Radiance arriving from `direction`.
+    fn radiance(&self, direction: &Vec3) -> Vec3;
+
+    /// A direction drawn with density `pdf`, roughly in proportion to the
+    /// light arriving from it, for environments with bright spots worth
+    /// looking for from each surface; None for those only found by paths
+    /// that miss everything.
+    fn sample(&self, _sampler: &mut dyn Sampler) -> Option<Vec3> {
+        None
+    }
+
+    /// The density, per unit solid angle, with which `sample` draws
+    /// `direction`.
+    fn pdf(&self, _direction: &Vec3) -> f64 {
+        0.
+    }
+
+    /// Radiance arriving along `r`, at its wavelengths if it has any.
+    fn along(&self, r: &Ray) -> Vec3 {
+        let radiance = self.radiance(r.direction());
+        match &r.wavelengths {
+            Some(wavelengths) => wavelengths.from_rgb(radiance),
+            None => radiance,
+        }
+    }
+}
+
+/// The same light from every direction, like an overcast sky, or none at
+/// all for black.
+pub struct Solid {
+    pub color: Vec3,
+}
+
+impl Environment for Solid {
+    fn radiance(&self, _direction: &Vec3) -> Vec3 {
+        self.color
+    }
+}
+
+/// A sky blending from `bottom` straight down to `top` straight up.
+pub struct Gradient {
+    pub bottom: Vec3,
+    pub top: Vec3,
+}
+
+impl Gradient {
+    /// White below the horizon, turning light blue overhead.
+    pub fn sky() -> Gradient {
+        Gradient {
+            bottom: Vec3(1., 1., 1.),
+            top: Vec3(0.5, 0.7, 1.),
+        }
+    }
+}
+
+impl Environment for Gradient {
+    fn radiance(&self, direction: &Vec3) -> Vec3 {
+        let t = 0.5 * (direction.unit().y() + 1.);
+        (1. - t) * self.bottom + t * self.top
+    }
+}
+
+/// Light from every direction given by a function of the direction, which
+/// need not be a unit vector, for skies made up in code.
+pub struct Procedural<F>(pub F);
+
+impl<F: Fn(&Vec3) -> Vec3 + Send + Sync> Environment for Procedural<F> {
+    fn radiance(&self, direction: &Vec3) -> Vec3 {
+        (self.0)(direction)
+    }
+}
+
+/// An opening, like a window, that the environment lights a room through:
+/// a parallelogram with one corner at `corner` and sides along `u` and `v`,
+/// like a `Quad`, that nothing hits. Paths in the room look for the
+/// environment's light through points picked evenly over its portals too,
+/// since most directions drawn from all around would only find the walls.
+#[derive(Copy, Clone, Debug)]
+pub struct Portal {
+    pub corner: Vec3,
+    pub u: Vec3,
+    pub v: Vec3,
+}
+
+impl Portal {
+    /// A direction from `origin` through a point picked evenly over the
+    /// portal with `(a, b)`.
+    pub fn sample(&self, origin: &Vec3, (a, b): (f64, f64)) -> Vec3 {
+        self.corner + a * self.u + b * self.v - *origin
+    }
+
+    /// The density, per unit solid angle seen from `origin`, with which
+    /// `sample` picks `direction`; zero if it misses the portal.
+    pub fn pdf(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let n = self.u.cross(self.v);
+        let denom = n.dot(*direction);
+        if denom.abs() < 1e-12 {
+            return 0.;
+        }
+        let t = n.dot(self.corner - *origin) / denom;
+        if t <= 0. {
+            return 0.;
+        }
+        // coordinates of the crossing along the two sides
+        let w = n / n.dot(n);
+        let planar = *origin + t * *direction - self.corner;
+        let alpha = w.dot(planar.cross(self.v));
+        let beta = w.dot(self.u.cross(planar));
+        if !(0. ..=1.).contains(&alpha) || !(0. ..=1.).contains(&beta) {
+            return 0.;
+        }
+        let distance_squared = t * t * direction.squared_length();
+        let cosine = denom.abs() / (n.length() * direction.length());
+        distance_squared / (cosine * n.length())
+    }
+}
+
+/// A panorama of the light from all around, like a photographed HDR
+/// environment, for lighting scenes as they would be lit there. It is
+/// stored equirectangularly, as `Equirectangular` cameras looking down -z
+/// see it: longitude across, with -z in the middle and +x to its right,
+/// and latitude up.
+///
+/// Directions are drawn pixel by pixel in proportion to the light each
+/// brings, its brightness times the solid angle it covers, so a small sun
+/// in a photographed sky is found by nearly every surface that can see it
+/// rather than by the odd path happening to head its way.
+pub struct EnvironmentMap {
+    image: Bitmap,
+    /// What the image's values are multiplied by for radiance.
+    pub scale: f64,
+    /// The share of the light in each row and those above it, from the top.
+    rows: Vec<f64>,
+    /// The same across each row, from the left.
+    columns: Vec<Vec<f64>>,
+    /// The light in each pixel, over the average.
+    weights: Vec<f64>,
+}
+
+impl EnvironmentMap {
+    pub fn new(image: Bitmap, scale: f64) -> EnvironmentMap {
+        let (width, height) = (image.width, image.height);
+        let mut weights: Vec<f64> = (0..height)
+            .flat_map(|y| {
+                let latitude = PI * (0.5 - (y as f64 + 0.5) / height as f64);
+                let image = &image;
+                (0..width).map(move |x| image.pixel(x, y).luminance().max(0.) * latitude.cos())
+            })
+            .collect();
+        let total: f64 = weights.iter().sum();
+        if total > 0. {
+            let average = total / weights.len() as f64;
+            for w in &mut weights {
+                *w /= average;
+            }
+        }
+        let columns: Vec<Vec<f64>> = weights.chunks(width).map(cumulative).collect();
+        let row_totals: Vec<f64> = columns.iter().map(|c| c[c.len() - 1]).collect();
+        EnvironmentMap {
+            image,
+            scale,
+            rows: cumulative(&row_totals),
+            columns,
+            weights,
+        }
+    }
+
+    /// Load an equirectangular panorama from a Radiance HDR or OpenEXR file,
+    /// or any other image `Bitmap` reads.
+    pub fn load<P: AsRef<Path>>(path: P, scale: f64) -> Result<EnvironmentMap, image::ImageError> {
+        Ok(EnvironmentMap::new(Bitmap::load(path)?, scale))
+    }
+}
+
+impl Environment for EnvironmentMap {
+    fn radiance(&self, direction: &Vec3) -> Vec3 {
+        let d = direction.unit();
+        let u = 0.5 + d.x().atan2(-d.z()) / (2. * PI);
+        let v = 0.5 + d.y().clamp(-1., 1.).asin() / PI;
+        self.scale * self.image.bilinear(u, v)
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<Vec3> {
+        if self.rows[self.rows.len() - 1] <= 0. {
+            return None;
+        }
+        let (a, b) = sampler.get_2d();
+        let (y, down) = pick(&self.rows, a);
+        let (x, across) = pick(&self.columns[y], b);
+        let (width, height) = (self.image.width as f64, self.image.height as f64);
+        let phi = 2. * PI * ((x as f64 + across) / width - 0.5);
+        let latitude = PI * (0.5 - (y as f64 + down) / height);
+        Some(Vec3(
+            latitude.cos() * phi.sin(),
+            latitude.sin(),
+            -latitude.cos() * phi.cos(),
+        ))
+    }
+
+    fn pdf(&self, direction: &Vec3) -> f64 {
+        let d = direction.unit();
+        let latitude = d.y().clamp(-1., 1.).asin();
+        if latitude.cos() <= 0. {
+            return 0.;
+        }
+        let (width, height) = (self.image.width, self.image.height);
+        let u = 0.5 + d.x().atan2(-d.z()) / (2. * PI);
+        let v = 0.5 + latitude / PI;
+        let x = ((u * width as f64) as usize).min(width - 1);
+        let y = (((1. - v) * height as f64) as usize).min(height - 1);
+        // per unit area of the image, which covers 2 pi by pi radians
+        // squeezed together by the cosine of the latitude
+        self.weights[y * width + x] / (2. * PI * PI * latitude.cos())
+    }
+}
+
+/// The running totals of `values`.
+fn cumulative(values: &[f64]) -> Vec<f64> {
+    values
+        .iter()
+        .scan(0., |sum, v| {
+            *sum += v;
+            Some(*sum)
+        })
+        .collect()
+}
+
+/// Which entry of running totals `cdf` the fraction `u` of the last falls
+/// in, and how far through it.
+fn pick(cdf: &[f64], u: f64) -> (usize, f64) {
+    let target = u * cdf[cdf.len() - 1];
+    let i = cdf.partition_point(|&c| c <= target).min(cdf.len() - 1);
+    let start = if i == 0 { 0. } else { cdf[i - 1] };
+    let size = cdf[i] - start;
+    let through = if size > 0. {
+        ((target - start) / size).clamp(0., 1.)
+    } else {
+        0.5
+    };
+    (i, through)
+}
@@ -0,0 +1,143 @@
+use crate::vec3::Vec3;
+
+/// How a sample counts towards the pixels around it, by its distance from
+/// their centres. Each pixel is the weighted average of the samples near
+/// it, so wider filters blur more and smooth edges and noise, and
+/// narrower or sharpening ones keep more detail.
+#[derive(Copy, Clone, Debug)]
+pub enum Filter {
+    /// Every sample in the pixel counts the same and none outside it: the
+    /// sharpest, with jagged edges.
+    Box,
+    /// Falling off in a straight line out to `radius` pixels.
+    Tent { radius: f64 },
+    /// A Gaussian bell of width `sigma` pixels, cut off at three times
+    /// that: smooth, a little soft.
+    Gaussian { sigma: f64 },
+    /// Mitchell and Netravali's cubic over two pixels, whose small negative
+    /// lobes keep edges crisp; `b` blurs more and `c` rings more, and
+    /// b = c = 1/3 is their recommendation.
+    Mitchell { b: f64, c: f64 },
+}
+
+impl Filter {
+    /// How far from a sample, in pixels along each axis, the pixels it
+    /// counts towards lie.
+    pub fn radius(&self) -> f64 {
+        match *self {
+            Filter::Box => 0.5,
+            Filter::Tent { radius } => radius,
+            Filter::Gaussian { sigma } => 3. * sigma,
+            Filter::Mitchell { .. } => 2.,
+        }
+    }
+
+    /// How much a sample `(x, y)` pixels from a pixel's centre counts
+    /// towards it.
+    pub fn weight(&self, x: f64, y: f64) -> f64 {
+        self.weight_1d(x) * self.weight_1d(y)
+    }
+
+    fn weight_1d(&self, x: f64) -> f64 {
+        let x = x.abs();
+        if x > self.radius() {
+            return 0.;
+        }
+        match *self {
+            Filter::Box => 1.,
+            Filter::Tent { radius } => 1. - x / radius,
+            Filter::Gaussian { sigma } => {
+                let gaussian = |x: f64| (-x * x / (2. * sigma * sigma)).exp();
+                // lowered to meet zero at the edge rather than stop short
+                gaussian(x) - gaussian(self.radius())
+            }
+            Filter::Mitchell { b, c } => {
+                let cubic = if x < 1. {
+                    (12. - 9. * b - 6. * c) * x * x * x
+                        + (-18. + 12. * b + 6. * c) * x * x
+                        + (6. - 2. * b)
+                } else {
+                    (-b - 6. * c) * x * x * x
+                        + (6. * b + 30. * c) * x * x
+                        + (-12. * b - 48. * c) * x
+                        + (8. * b + 24. * c)
+                };
+                cubic / 6.
+            }
+        }
+    }
+}
+
+/// The image being rendered: for each pixel, the samples around it added
+/// up weighted by `filter`, their alphas likewise, and their weights.
+pub struct Film {
+    width: usize,
+    height: usize,
+    filter: Filter,
+    pixels: Vec<(Vec3, f64, f64)>,
+}
+
+impl Film {
+    pub fn new(width: usize, height: usize, filter: Filter) -> Film {
+        Film {
+            width,
+            height,
+            filter,
+            pixels: vec![(Vec3(0., 0., 0.), 0., 0.); width * height],
+        }
+    }
+
+    /// Adds a sample of radiance `color` at `(x, y)`, in pixels from the
+    /// bottom left corner of the image, to the pixels around it.
+    pub fn add(&mut self, x: f64, y: f64, color: Vec3) {
+        self.add_alpha(x, y, color, 1.);
+    }
+
+    /// `add` for a sample that is only `alpha` opaque, its color
+    /// premultiplied by it.
+    pub fn add_alpha(&mut self, x: f64, y: f64, color: Vec3, alpha: f64) {
+        let radius = self.filter.radius();
+        let (x0, x1) = ((x - 0.5 - radius).ceil(), (x - 0.5 + radius).floor());
+        let (y0, y1) = ((y - 0.5 - radius).ceil(), (y - 0.5 + radius).floor());
+        let (x0, y0) = (x0.max(0.) as usize, y0.max(0.) as usize);
+        if x1 < 0. || y1 < 0. {
+            return;
+        }
+        let x1 = (x1 as usize).min(self.width - 1);
+        let y1 = (y1 as usize).min(self.height - 1);
+        for j in y0..=y1 {
+            for i in x0..=x1 {
+                let weight = self.filter.weight(i as f64 + 0.5 - x, j as f64 + 0.5 - y);
+                let pixel = &mut self.pixels[(self.height - 1 - j) * self.width + i];
+                *pixel = (
+                    pixel.0 + weight * color,
+                    pixel.1 + weight * alpha,
+                    pixel.2 + weight,
+                );
+            }
+        }
+    }
+
+    /// The radiance of each pixel, top row first: the weighted average of
+    /// the samples around it.
+    pub fn pixels(&self) -> Vec<Vec3> {
+        self.pixels
+            .iter()
+            .map(|&(sum, _, weight)| {
+                if weight > 0. {
+                    sum / weight
+                } else {
+                    Vec3(0., 0., 0.)
+                }
+            })
+            .collect()
+    }
+
+    /// The alpha of each pixel, in the same order as `pixels`.
+    pub fn alphas(&self) -> Vec<f64> {
+        self.pixels
+            .iter()
+            .map(|&(_, sum, weight)| if weight > 0. { sum / weight } else { 0. })
+            .collect()
+    }
+}
@@ -0,0 +1,170 @@
+use crate::aabb::Aabb;
+use crate::sampler::Sampler;
+use crate::texture::Texture;
+use crate::vec3::Vec3;
+use crate::Ray;
+
+/// Fog, haze, smoke or cloud, which dims light passing through it with
+/// distance and scatters some of it off in other directions, so distant
+/// objects fade into it and beams of light show up in it. Rays travelling
+/// through it are stopped at a distance drawn in proportion to how likely
+/// the light is to have met the fog there, so thick fog is crossed in many
+/// short steps and thin haze in a few long ones, and light is looked for
+/// from each point it scatters at as from a surface.
+///
+/// Colored coefficients give fog that dims some colors more than others,
+/// like the air that turns distant hills blue. Fog can also be thicker in
+/// some places than others, like a cloud billowing out of a clear sky:
+/// rays are then stepped through it as if it were everywhere as thick as
+/// it gets, and carry on through the steps landing where it is thinner,
+/// delta tracking, so no steps need be fine enough to follow it; the light
+/// reaching a point through it is found by keeping, at each such step, as
+/// much as the fog there lets through, ratio tracking. Both come out right
+/// on average however coarse the steps. See Novák et al. 2018 ("Monte
+/// Carlo Methods for Volumetric Light Transport Simulation").
+pub struct Fog {
+    /// The share of the light absorbed per unit distance, for red, green
+    /// and blue, where the fog is thickest.
+    pub absorption: Vec3,
+    /// The share of the light scattered off in another direction per unit
+    /// distance, where the fog is thickest.
+    pub scattering: Vec3,
+    /// How thick the fog is at each point, as a gray level from 0, clear,
+    /// to 1, thickest; None for evenly thick everywhere. Fog with a density
+    /// needs bounds.
+    pub density: Option<Box<dyn Texture>>,
+    /// Which way the fog scatters light, after Henyey and Greenstein: 0
+    /// evenly in every direction, towards 1 mostly on ahead, like clouds at
+    /// about 0.85, and towards -1 mostly back.
+    pub g: f64,
+    /// Where the fog is, or None for everywhere, which hides the
+    /// environment behind it unless the fog is very thin.
+    pub bounds: Option<Aabb>,
+}
+
+impl Fog {
+    /// Where `r` is in the fog from its start up to `t_max`, if anywhere.
+    fn span(&self, r: &Ray, t_max: f64) -> Option<(f64, f64)> {
+        match &self.bounds {
+            Some(bounds) => bounds.clip(r, 0.0001, t_max),
+            None => {
+                assert!(self.density.is_none(), "fog with a density needs bounds");
+                Some((0.0001, t_max)).filter(|_| t_max > 0.0001)
+            }
+        }
+    }
+
+    /// How thick the fog is at `p`, over its thickest.
+    fn density_at(&self, p: &Vec3) -> f64 {
+        match &self.density {
+            Some(density) => density.scalar(0., 0., p).clamp(0., 1.),
+            None => 1.,
+        }
+    }
+
+    /// The scattering and the absorption and scattering together, per unit
+    /// of `r`'s parameter, at its wavelengths if it has any.
+    fn coefficients(&self, r: &Ray) -> (Vec3, Vec3) {
+        let speed = r.direction().length();
+        let (scattering, extinction) = (self.scattering, self.absorption + self.scattering);
+        match &r.wavelengths {
+            Some(wavelengths) => (
+                speed * wavelengths.interpolate(scattering),
+                speed * wavelengths.interpolate(extinction),
+            ),
+            None => (speed * scattering, speed * extinction),
+        }
+    }
+
+    /// The share of the light that gets along `r` from its start up to
+    /// `t_max` without being absorbed or scattered away; for fog with a
+    /// density, an estimate of it that is right on average.
+    pub fn transmittance(&self, r: &Ray, t_max: f64, sampler: &mut dyn Sampler) -> Vec3 {
+        let (t0, t1) = match self.span(r, t_max) {
+            Some(span) => span,
+            None => return Vec3(1., 1., 1.),
+        };
+        let extinction = self.coefficients(r).1;
+        let majorant = extinction.0.max(extinction.1).max(extinction.2);
+        if self.density.is_none() || majorant <= 0. {
+            return attenuate(extinction, t1 - t0);
+        }
+        // stepping as if the fog were as thick everywhere as its thickest
+        // color, keeping at each step what the fog there lets through
+        let mut transmittance = Vec3(1., 1., 1.);
+        let mut t = t0;
+        loop {
+            t -= (1. - sampler.get_1d()).ln() / majorant;
+            if t >= t1 {
+                break;
+            }
+            let density = self.density_at(&r.point_at_parameter(t));
+            transmittance = transmittance * (Vec3(1., 1., 1.) - density / majorant * extinction);
+        }
+        transmittance
+    }
+
+    /// Where along `r`, before `t_max`, the light meets the fog, or None if
+    /// it gets through, drawn by how far light of color `channel`, 0 to 2
+    /// for red to blue, gets. Along with it come the fog's response, and
+    /// the density with which each color would have drawn it, up to a
+    /// factor the same for all colors: what the light found from there on
+    /// is multiplied by is the one over the other, for whichever way of
+    /// picking the color.
+    pub fn sample(
+        &self,
+        r: &Ray,
+        t_max: f64,
+        channel: usize,
+        sampler: &mut dyn Sampler,
+    ) -> (Option<f64>, Vec3, Vec3) {
+        let mut response = Vec3(1., 1., 1.);
+        let mut chance = Vec3(1., 1., 1.);
+        let (mut t, t1) = match self.span(r, t_max) {
+            Some(span) => span,
+            None => return (None, response, chance),
+        };
+        let (scattering, extinction) = self.coefficients(r);
+        let sigma = [extinction.0, extinction.1, extinction.2][channel.min(2)];
+        loop {
+            let step = if sigma > 0. {
+                -(1. - sampler.get_1d()).ln() / sigma
+            } else {
+                f64::INFINITY
+            };
+            if step >= t1 - t {
+                let transmittance = attenuate(extinction, t1 - t);
+                return (None, response * transmittance, chance * transmittance);
+            }
+            t += step;
+            let transmittance = attenuate(extinction, step);
+            let density = self.density_at(&r.point_at_parameter(t));
+            if density >= 1. || sampler.get_1d() < density {
+                return (
+                    Some(t),
+                    response * density * scattering * transmittance,
+                    chance * density * extinction * transmittance,
+                );
+            }
+            // where the fog is thinner, carrying on as often as the share
+            // it is thinner by, for every color, the response the same
+            let carry = (1. - density) * extinction * transmittance;
+            let scale = (chance * carry).average();
+            response = response * carry / scale;
+            chance = chance * carry / scale;
+        }
+    }
+}
+
+/// What is left of light crossing `distance` of something with extinction
+/// `sigma`.
+fn attenuate(sigma: Vec3, distance: f64) -> Vec3 {
+    let at = |sigma: f64| {
+        if sigma > 0. {
+            (-sigma * distance).exp()
+        } else {
+            1.
+        }
+    };
+    Vec3(at(sigma.0), at(sigma.1), at(sigma.2))
+}
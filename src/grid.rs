@@ -0,0 +1,154 @@
+use crate::aabb::Aabb;
+use crate::vec3::Vec3;
+use crate::{HitRecord, Hittable, Ray};
+
+/// A uniform grid: the box around the objects cut into equal cells, each
+/// listing the objects overlapping it. Quick to build, and fast when the
+/// objects are small and spread evenly, like a field of particles; rays step
+/// from cell to cell along their path and stop at the first cell holding a
+/// hit.
+pub struct Grid {
+    objects: Vec<Box<dyn Hittable>>,
+    cells: Vec<Vec<usize>>,
+    resolution: [usize; 3],
+    bbox: Aabb,
+}
+
+/// Cells per object, on average.
+const DENSITY: f64 = 3.;
+
+fn axis(v: &Vec3, i: usize) -> f64 {
+    match i {
+        0 => v.x(),
+        1 => v.y(),
+        _ => v.z(),
+    }
+}
+
+impl Grid {
+    /// Build a grid over `objects` with cells about as wide as they are
+    /// deep and high.
+    ///
+    /// Panics if `objects` is empty or any of them is unbounded.
+    pub fn new(objects: Vec<Box<dyn Hittable>>) -> Grid {
+        assert!(!objects.is_empty(), "a grid needs at least one object");
+        let boxes: Vec<Aabb> = objects
+            .iter()
+            .map(|object| {
+                object
+                    .bounding_box()
+                    .expect("objects in a grid must be bounded")
+            })
+            .collect();
+        let bbox = boxes[1..]
+            .iter()
+            .fold(boxes[0], |sum, b| Aabb::surrounding(&sum, b));
+
+        let size = bbox.max - bbox.min;
+        let volume = size.x() * size.y() * size.z();
+        let cells_per_unit = (DENSITY * objects.len() as f64 / volume).cbrt();
+        let mut resolution = [1; 3];
+        for (i, n) in resolution.iter_mut().enumerate() {
+            *n = ((axis(&size, i) * cells_per_unit).round() as usize).clamp(1, 128);
+        }
+
+        let mut grid = Grid {
+            objects,
+            cells: vec![Vec::new(); resolution[0] * resolution[1] * resolution[2]],
+            resolution,
+            bbox,
+        };
+        for (index, b) in boxes.iter().enumerate() {
+            let lo = grid.cell_of(&b.min);
+            let hi = grid.cell_of(&b.max);
+            for x in lo[0]..=hi[0] {
+                for y in lo[1]..=hi[1] {
+                    for z in lo[2]..=hi[2] {
+                        let cell = grid.cell_index([x, y, z]);
+                        grid.cells[cell].push(index);
+                    }
+                }
+            }
+        }
+        grid
+    }
+
+    /// The cell holding `p`, clamped to the grid.
+    fn cell_of(&self, p: &Vec3) -> [usize; 3] {
+        let mut cell = [0; 3];
+        for (i, c) in cell.iter_mut().enumerate() {
+            let (lo, hi) = (axis(&self.bbox.min, i), axis(&self.bbox.max, i));
+            let f = (axis(p, i) - lo) / (hi - lo) * self.resolution[i] as f64;
+            *c = (f.max(0.) as usize).min(self.resolution[i] - 1);
+        }
+        cell
+    }
+
+    fn cell_index(&self, [x, y, z]: [usize; 3]) -> usize {
+        (z * self.resolution[1] + y) * self.resolution[0] + x
+    }
+}
+
+impl Hittable for Grid {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let (t0, t1) = self.bbox.clip(r, t_min, t_max)?;
+        let mut cell = self.cell_of(&r.point_at_parameter(t0));
+
+        // 3D DDA: for each axis, the ray parameter at the next cell boundary
+        // it crosses and the parameter step between boundaries
+        let mut next = [f64::INFINITY; 3];
+        let mut delta = [f64::INFINITY; 3];
+        let mut step = [0isize; 3];
+        for i in 0..3 {
+            let d = axis(r.direction(), i);
+            if d == 0. {
+                continue;
+            }
+            let lo = axis(&self.bbox.min, i);
+            let width = (axis(&self.bbox.max, i) - lo) / self.resolution[i] as f64;
+            let o = axis(r.origin(), i);
+            let boundary = if d > 0. {
+                lo + (cell[i] + 1) as f64 * width
+            } else {
+                lo + cell[i] as f64 * width
+            };
+            next[i] = (boundary - o) / d;
+            delta[i] = width / d.abs();
+            step[i] = if d > 0. { 1 } else { -1 };
+        }
+
+        let mut closest: Option<HitRecord> = None;
+        loop {
+            for &object in &self.cells[self.cell_index(cell)] {
+                let limit = closest.map_or(t_max, |h| h.t);
+                if let Some(hit_record) = self.objects[object].hit(r, t_min, limit) {
+                    closest = Some(hit_record);
+                }
+            }
+            let i = if next[0] <= next[1] && next[0] <= next[2] {
+                0
+            } else if next[1] <= next[2] {
+                1
+            } else {
+                2
+            };
+            // a hit beyond this cell may yet be beaten by an object in a
+            // later one
+            let exit = next[i].min(t1);
+            if closest.is_some_and(|h| h.t <= exit) || next[i] > t1 {
+                break;
+            }
+            let moved = cell[i] as isize + step[i];
+            if moved < 0 || moved >= self.resolution[i] as isize {
+                break;
+            }
+            cell[i] = moved as usize;
+            next[i] += delta[i];
+        }
+        closest
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bbox)
+    }
+}
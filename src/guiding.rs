@@ -0,0 +1,390 @@
+use std::f64::consts::PI;
+use std::sync::{Arc, Mutex};
+
+use crate::aabb::Aabb;
+use crate::pdf::Pdf;
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+
+/// Share of a directional tree's light a cell may hold before it is split
+/// into four.
+const SPLIT_SHARE: f64 = 0.01;
+
+/// Deepest a directional tree goes.
+const MAX_DEPTH: usize = 20;
+
+/// Paths a region of space may record in a pass before it is split in two,
+/// times the square root of how many samples the pass took relative to
+/// the first, which doubles every pass.
+const SPLIT_RECORDS: f64 = 12000.;
+
+/// Where light comes from, learned while rendering so paths can be sent
+/// where it is, after Müller et al. 2017 ("Practical Path Guiding for
+/// Efficient Light-Transport Simulation"). Space is split in a binary tree,
+/// finer where more paths pass, and each region keeps a quadtree over the
+/// sphere of directions, finer where more light arrives from. Rendering
+/// goes in passes: each pass records the light its paths find into one set
+/// of trees while sampling from those the last pass learned, and `update`
+/// hands over to the new ones. Paths from surfaces that spread light over
+/// all directions can then be sent along directions drawn half from the
+/// material and half from what was learned, finding light that arrives
+/// indirectly, like through a gap or off a lit wall, far more often.
+pub struct Guide {
+    tree: Mutex<SdTree>,
+}
+
+/// The spatial tree, each leaf holding the directional trees of its region.
+struct SdTree {
+    nodes: Vec<SpatialNode>,
+    /// Passes handed over so far.
+    passes: i32,
+}
+
+enum SpatialNode {
+    Inner {
+        axis: usize,
+        middle: f64,
+        children: [usize; 2],
+    },
+    Leaf {
+        bounds: Aabb,
+        /// What the last pass learned, drawn from this pass.
+        sampling: Arc<DTree>,
+        /// What this pass is learning.
+        building: DTree,
+        records: usize,
+    },
+}
+
+/// A quadtree over the square the sphere of directions is mapped onto,
+/// evenly by area, holding how much light arrived through each cell.
+#[derive(Clone)]
+struct DTree {
+    nodes: Vec<QuadNode>,
+}
+
+/// The light that arrived through each quarter of a cell, and the nodes
+/// splitting the quarters further, if any. The root is never a child, so 0
+/// stands for none.
+#[derive(Copy, Clone, Default)]
+struct QuadNode {
+    energy: [f64; 4],
+    children: [usize; 4],
+}
+
+impl Guide {
+    /// A guide over `bounds` that has learned nothing yet.
+    pub fn new(bounds: Aabb) -> Guide {
+        Guide {
+            tree: Mutex::new(SdTree {
+                nodes: vec![SpatialNode::Leaf {
+                    bounds,
+                    sampling: Arc::new(DTree::default()),
+                    building: DTree::default(),
+                    records: 0,
+                }],
+                passes: 0,
+            }),
+        }
+    }
+
+    /// The density over directions learned around `p` by the last pass,
+    /// or None where it found no light.
+    pub fn pdf(&self, p: &Vec3) -> Option<Box<dyn Pdf>> {
+        let mut tree = self.tree.lock().unwrap();
+        match tree.leaf(p) {
+            SpatialNode::Leaf { sampling, .. } if sampling.total() > 0. => {
+                Some(Box::new(GuidePdf {
+                    tree: sampling.clone(),
+                }))
+            }
+            _ => None,
+        }
+    }
+
+    /// Records light of brightness `radiance` arriving at `p` from
+    /// `direction`, which was drawn with density `density`.
+    pub fn record(&self, p: &Vec3, direction: &Vec3, radiance: f64, density: f64) {
+        if density <= 0. || !radiance.is_finite() {
+            return;
+        }
+        let mut tree = self.tree.lock().unwrap();
+        if let SpatialNode::Leaf {
+            building, records, ..
+        } = tree.leaf(p)
+        {
+            building.record(square(&direction.unit()), radiance / density);
+            *records += 1;
+        }
+    }
+
+    /// Ends a pass: regions that recorded many paths are split, what each
+    /// region learned is sampled from the next pass on, and each starts
+    /// learning afresh with its cells split where the most light came from.
+    pub fn update(&self) {
+        let mut tree = self.tree.lock().unwrap();
+        let threshold = SPLIT_RECORDS * 2f64.powi(tree.passes).sqrt();
+        tree.passes += 1;
+        let mut k = 0;
+        while k < tree.nodes.len() {
+            let split = match &tree.nodes[k] {
+                SpatialNode::Leaf { records, .. } => *records as f64 > threshold,
+                SpatialNode::Inner { .. } => false,
+            };
+            if split {
+                tree.split(k);
+            } else if let SpatialNode::Leaf {
+                sampling,
+                building,
+                records,
+                ..
+            } = &mut tree.nodes[k]
+            {
+                let refined = building.refined();
+                *sampling = Arc::new(std::mem::replace(building, refined));
+                *records = 0;
+                k += 1;
+            } else {
+                k += 1;
+            }
+        }
+    }
+}
+
+impl SdTree {
+    /// The leaf whose region holds `p`.
+    fn leaf(&mut self, p: &Vec3) -> &mut SpatialNode {
+        let mut k = 0;
+        while let SpatialNode::Inner {
+            axis,
+            middle,
+            children,
+        } = &self.nodes[k]
+        {
+            k = children[(component(p, *axis) >= *middle) as usize];
+        }
+        &mut self.nodes[k]
+    }
+
+    /// Splits leaf `k` across the middle of its longest side, each half
+    /// starting from what it knew and with half its records.
+    fn split(&mut self, k: usize) {
+        let (bounds, sampling, building, records) = match &self.nodes[k] {
+            SpatialNode::Leaf {
+                bounds,
+                sampling,
+                building,
+                records,
+            } => (*bounds, sampling.clone(), building.clone(), *records),
+            SpatialNode::Inner { .. } => return,
+        };
+        let size = bounds.max - bounds.min;
+        let axis = if size.x() >= size.y() && size.x() >= size.z() {
+            0
+        } else if size.y() >= size.z() {
+            1
+        } else {
+            2
+        };
+        let middle = 0.5 * (component(&bounds.min, axis) + component(&bounds.max, axis));
+        let (mut low, mut high) = (bounds.max, bounds.min);
+        set_component(&mut low, axis, middle);
+        set_component(&mut high, axis, middle);
+        let halves = [
+            Aabb {
+                min: bounds.min,
+                max: low,
+            },
+            Aabb {
+                min: high,
+                max: bounds.max,
+            },
+        ];
+        self.nodes[k] = SpatialNode::Inner {
+            axis,
+            middle,
+            children: [self.nodes.len(), self.nodes.len() + 1],
+        };
+        for half in halves {
+            self.nodes.push(SpatialNode::Leaf {
+                bounds: half,
+                sampling: sampling.clone(),
+                building: building.clone(),
+                records: records / 2,
+            });
+        }
+    }
+}
+
+impl Default for DTree {
+    fn default() -> DTree {
+        DTree {
+            nodes: vec![QuadNode::default()],
+        }
+    }
+}
+
+impl DTree {
+    /// All the light recorded.
+    fn total(&self) -> f64 {
+        self.nodes[0].energy.iter().sum()
+    }
+
+    /// Adds `value` to the cells holding point `(x, y)` of the square.
+    fn record(&mut self, (mut x, mut y): (f64, f64), value: f64) {
+        let mut k = 0;
+        loop {
+            let q = quadrant(&mut x, &mut y);
+            self.nodes[k].energy[q] += value;
+            k = self.nodes[k].children[q];
+            if k == 0 {
+                return;
+            }
+        }
+    }
+
+    /// The density, per unit area of the square, of point `(x, y)`:
+    /// each cell's share of the light it is in, spread evenly over it.
+    fn pdf(&self, (mut x, mut y): (f64, f64)) -> f64 {
+        let mut density = 1.;
+        let mut k = 0;
+        loop {
+            let node = &self.nodes[k];
+            let total: f64 = node.energy.iter().sum();
+            if total <= 0. {
+                return density;
+            }
+            let q = quadrant(&mut x, &mut y);
+            density *= 4. * node.energy[q] / total;
+            k = node.children[q];
+            if k == 0 {
+                return density;
+            }
+        }
+    }
+
+    /// A point of the square drawn with density `pdf`.
+    fn sample(&self, sampler: &mut dyn Sampler) -> (f64, f64) {
+        let (mut x, mut y, mut size) = (0., 0., 1.);
+        let mut k = 0;
+        loop {
+            let node = &self.nodes[k];
+            let total: f64 = node.energy.iter().sum();
+            if total <= 0. {
+                break;
+            }
+            // the quarter holding the light the number falls on
+            let mut target = sampler.get_1d() * total;
+            let mut q = 0;
+            while q < 3 && target >= node.energy[q] {
+                target -= node.energy[q];
+                q += 1;
+            }
+            size *= 0.5;
+            x += size * (q % 2) as f64;
+            y += size * (q / 2) as f64;
+            k = node.children[q];
+            if k == 0 {
+                break;
+            }
+        }
+        let (u, v) = sampler.get_2d();
+        (x + size * u, y + size * v)
+    }
+
+    /// An empty tree, its cells split wherever this one's hold more than
+    /// `SPLIT_SHARE` of its light.
+    fn refined(&self) -> DTree {
+        let mut tree = DTree::default();
+        let total = self.total();
+        if total > 0. {
+            self.refine(&mut tree, 0, Some(0), self.nodes[0].energy, total, 1);
+        }
+        tree
+    }
+
+    /// Splits the quarters of node `to` in `tree` that hold, according to
+    /// `energy`, more than their share of `total`; `from` is the node of
+    /// this tree they come from, if it goes that deep.
+    fn refine(
+        &self,
+        tree: &mut DTree,
+        to: usize,
+        from: Option<usize>,
+        energy: [f64; 4],
+        total: f64,
+        depth: usize,
+    ) {
+        for (q, &e) in energy.iter().enumerate() {
+            if e <= SPLIT_SHARE * total || depth >= MAX_DEPTH {
+                continue;
+            }
+            let child_from = from.map(|k| self.nodes[k].children[q]).filter(|&k| k != 0);
+            let child_energy = match child_from {
+                Some(k) => self.nodes[k].energy,
+                None => [e / 4.; 4],
+            };
+            tree.nodes.push(QuadNode::default());
+            let child = tree.nodes.len() - 1;
+            tree.nodes[to].children[q] = child;
+            self.refine(tree, child, child_from, child_energy, total, depth + 1);
+        }
+    }
+}
+
+/// Directions drawn from what a region learned.
+struct GuidePdf {
+    tree: Arc<DTree>,
+}
+
+impl Pdf for GuidePdf {
+    fn value(&self, direction: &Vec3) -> f64 {
+        self.tree.pdf(square(&direction.unit())) / (4. * PI)
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        let (x, y) = self.tree.sample(sampler);
+        let z = 2. * y - 1.;
+        let r = (1. - z * z).max(0.).sqrt();
+        let phi = 2. * PI * x;
+        Vec3(r * phi.cos(), r * phi.sin(), z)
+    }
+}
+
+/// Where unit vector `direction` lands on the unit square, by angle around
+/// z and height along it, which keeps areas in proportion.
+fn square(direction: &Vec3) -> (f64, f64) {
+    let phi = direction.y().atan2(direction.x());
+    let x = if phi < 0. {
+        phi / (2. * PI) + 1.
+    } else {
+        phi / (2. * PI)
+    };
+    let y = 0.5 * (direction.z().clamp(-1., 1.) + 1.);
+    (x.min(1. - f64::EPSILON), y.min(1. - f64::EPSILON))
+}
+
+/// Which quarter of the unit square `(x, y)` is in, numbered across then
+/// up, with `(x, y)` moved to where it is in that quarter scaled up.
+fn quadrant(x: &mut f64, y: &mut f64) -> usize {
+    let (right, top) = (*x >= 0.5, *y >= 0.5);
+    *x = 2. * *x - right as usize as f64;
+    *y = 2. * *y - top as usize as f64;
+    right as usize + 2 * top as usize
+}
+
+fn component(v: &Vec3, axis: usize) -> f64 {
+    match axis {
+        0 => v.x(),
+        1 => v.y(),
+        _ => v.z(),
+    }
+}
+
+fn set_component(v: &mut Vec3, axis: usize, value: f64) {
+    match axis {
+        0 => v.0 = value,
+        1 => v.1 = value,
+        _ => v.2 = value,
+    }
+}
@@ -0,0 +1,312 @@
+use std::f64::consts::{LN_2, PI};
+
+use crate::microfacet::fresnel_dielectric;
+use crate::onb::Onb;
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+use crate::{HitRecord, Material, Ray, Scatter};
+
+// Follows the hair scattering model of Chiang et al. 2016 ("A Practical and
+// Controllable Hair and Fur Model for Production Path Tracing") as presented
+// in pbrt-v3, which refines Marschner et al. 2003. Light is split into lobes
+// by the number of internal paths through the fiber: R (p = 0) reflects off
+// the cuticle, TT (p = 1) passes straight through, TRT (p = 2) reflects once
+// inside, and one residual lobe lumps together everything longer.
+//
+// Directions are expressed in a frame with x along the fiber, so a
+// direction's longitudinal angle theta is measured from the plane normal to
+// the fiber and its azimuth phi is measured around the fiber.
+
+/// Number of explicitly modeled lobes; the residual lobe comes after them.
+const P_MAX: usize = 3;
+
+pub struct Hair {
+    /// Absorption coefficient of the fiber interior, per unit radius.
+    pub sigma_a: Vec3,
+    /// Longitudinal roughness in [0, 1].
+    pub beta_m: f64,
+    /// Azimuthal roughness in [0, 1].
+    pub beta_n: f64,
+    /// Tilt of the cuticle scales, in degrees.
+    pub alpha: f64,
+    pub eta: f64,
+}
+
+impl Hair {
+    /// Hair colored by its melanin concentrations, with typical values
+    /// between 0 and 8; eumelanin darkens toward black and brown, pheomelanin
+    /// toward red.
+    pub fn from_melanin(eumelanin: f64, pheomelanin: f64, beta_m: f64, beta_n: f64) -> Hair {
+        Hair {
+            sigma_a: eumelanin * Vec3(0.419, 0.697, 1.37) + pheomelanin * Vec3(0.187, 0.4, 1.05),
+            beta_m,
+            beta_n,
+            alpha: 2.,
+            eta: 1.55,
+        }
+    }
+
+    /// Hair whose multiply scattered color approximates `color`.
+    pub fn from_color(color: Vec3, beta_m: f64, beta_n: f64) -> Hair {
+        let b = beta_n;
+        let denom = 5.969 - 0.215 * b + 2.532 * b.powi(2) - 10.73 * b.powi(3)
+            + 5.574 * b.powi(4)
+            + 0.245 * b.powi(5);
+        let sigma = |c: f64| (c.max(1e-4).ln() / denom).powi(2);
+        Hair {
+            sigma_a: Vec3(sigma(color.r()), sigma(color.g()), sigma(color.b())),
+            beta_m,
+            beta_n,
+            alpha: 2.,
+            eta: 1.55,
+        }
+    }
+
+    /// Longitudinal variance of each lobe.
+    fn variances(&self) -> [f64; P_MAX + 1] {
+        let b = self.beta_m;
+        let v0 = (0.726 * b + 0.812 * b * b + 3.7 * b.powi(20)).powi(2);
+        [v0, 0.25 * v0, 4. * v0, 4. * v0]
+    }
+
+    /// Azimuthal logistic scale.
+    fn logistic_scale(&self) -> f64 {
+        let b = self.beta_n;
+        (PI / 8.).sqrt() * (0.265 * b + 1.194 * b * b + 5.372 * b.powi(22))
+    }
+
+    /// The outgoing longitudinal angle, as (sin, cos), rotated by the scale
+    /// tilt appropriate to lobe `p`.
+    fn tilt(&self, p: usize, sin_theta_o: f64, cos_theta_o: f64) -> (f64, f64) {
+        let sin1 = self.alpha.to_radians().sin();
+        let cos1 = (1. - sin1 * sin1).sqrt();
+        let (sin2, cos2) = (2. * cos1 * sin1, cos1 * cos1 - sin1 * sin1);
+        let (sin4, cos4) = (2. * cos2 * sin2, cos2 * cos2 - sin2 * sin2);
+        match p {
+            0 => (
+                sin_theta_o * cos2 - cos_theta_o * sin2,
+                cos_theta_o * cos2 + sin_theta_o * sin2,
+            ),
+            1 => (
+                sin_theta_o * cos1 + cos_theta_o * sin1,
+                cos_theta_o * cos1 - sin_theta_o * sin1,
+            ),
+            2 => (
+                sin_theta_o * cos4 + cos_theta_o * sin4,
+                cos_theta_o * cos4 - sin_theta_o * sin4,
+            ),
+            _ => (sin_theta_o, cos_theta_o),
+        }
+    }
+}
+
+/// Modified Bessel function of the first kind, order zero.
+fn i0(x: f64) -> f64 {
+    let mut val = 0.;
+    let mut x2i = 1.;
+    let mut ifact = 1.;
+    let mut i4 = 1.;
+    for i in 0..10 {
+        if i > 1 {
+            ifact *= i as f64;
+        }
+        val += x2i / (i4 * ifact * ifact);
+        x2i *= x * x;
+        i4 *= 4.;
+    }
+    val
+}
+
+fn log_i0(x: f64) -> f64 {
+    if x > 12. {
+        x + 0.5 * (-(2. * PI).ln() + (1. / x).ln() + 1. / (8. * x))
+    } else {
+        i0(x).ln()
+    }
+}
+
+/// Longitudinal scattering function.
+fn mp(cos_theta_i: f64, cos_theta_o: f64, sin_theta_i: f64, sin_theta_o: f64, v: f64) -> f64 {
+    let a = cos_theta_i * cos_theta_o / v;
+    let b = sin_theta_i * sin_theta_o / v;
+    if v <= 0.1 {
+        (log_i0(a) - b - 1. / v + LN_2 + (1. / (2. * v)).ln()).exp()
+    } else {
+        (-b).exp() * i0(a) / ((1. / v).sinh() * 2. * v)
+    }
+}
+
+/// Attenuation of each lobe, given the transmittance `t` of one pass across
+/// the fiber.
+fn ap(cos_theta_o: f64, eta: f64, h: f64, t: Vec3) -> [Vec3; P_MAX + 1] {
+    let cos_gamma_o = (1. - h * h).max(0.).sqrt();
+    let f = fresnel_dielectric(cos_theta_o * cos_gamma_o, eta);
+    let r = f * Vec3(1., 1., 1.);
+    let tt = (1. - f) * (1. - f) * t;
+    let trt = tt * t * f;
+    let tf = t * f;
+    let residual = trt * tf;
+    let residual = Vec3(
+        residual.r() / (1. - tf.r()),
+        residual.g() / (1. - tf.g()),
+        residual.b() / (1. - tf.b()),
+    );
+    [r, tt, trt, residual]
+}
+
+/// Net azimuthal deflection of lobe `p`.
+fn phi(p: usize, gamma_o: f64, gamma_t: f64) -> f64 {
+    2. * p as f64 * gamma_t - 2. * gamma_o + p as f64 * PI
+}
+
+fn logistic(x: f64, s: f64) -> f64 {
+    let x = x.abs();
+    (-x / s).exp() / (s * (1. + (-x / s).exp()).powi(2))
+}
+
+fn logistic_cdf(x: f64, s: f64) -> f64 {
+    1. / (1. + (-x / s).exp())
+}
+
+fn trimmed_logistic(x: f64, s: f64, a: f64, b: f64) -> f64 {
+    logistic(x, s) / (logistic_cdf(b, s) - logistic_cdf(a, s))
+}
+
+fn sample_trimmed_logistic(u: f64, s: f64, a: f64, b: f64) -> f64 {
+    let k = logistic_cdf(b, s) - logistic_cdf(a, s);
+    let x = -s * (1. / (u * k + logistic_cdf(a, s)) - 1.).ln();
+    x.clamp(a, b)
+}
+
+/// Azimuthal scattering function.
+fn np(dphi: f64, p: usize, s: f64, gamma_o: f64, gamma_t: f64) -> f64 {
+    let mut d = dphi - phi(p, gamma_o, gamma_t);
+    while d > PI {
+        d -= 2. * PI;
+    }
+    while d < -PI {
+        d += 2. * PI;
+    }
+    trimmed_logistic(d, s, -PI, PI)
+}
+
+impl Material for Hair {
+    fn scatter(
+        &self,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        let wo_world = -r.direction().unit();
+        let x = hit_record.tangent.unit();
+        let z = wo_world - wo_world.dot(x) * x;
+        if z.squared_length() < 1e-12 {
+            // looking straight down the fiber
+            return None;
+        }
+        let z = z.unit();
+        // x along the fiber, z toward the viewer in the plane normal to it
+        let frame = Onb {
+            u: x,
+            v: z.cross(x),
+            w: z,
+        };
+        // offset across the fiber, from the true normal at the hit
+        let h = hit_record.normal.unit().dot(frame.v).clamp(-1., 1.);
+        let wo = frame.to_local(&wo_world);
+
+        let sin_theta_o = wo.x();
+        let cos_theta_o = (1. - sin_theta_o * sin_theta_o).max(0.).sqrt();
+        let phi_o = wo.z().atan2(wo.y());
+        let gamma_o = h.asin();
+
+        // geometry of the refracted path inside the fiber
+        let sin_theta_t = sin_theta_o / self.eta;
+        let cos_theta_t = (1. - sin_theta_t * sin_theta_t).max(0.).sqrt();
+        let etap = (self.eta * self.eta - sin_theta_o * sin_theta_o).sqrt() / cos_theta_o;
+        let sin_gamma_t = h / etap;
+        let cos_gamma_t = (1. - sin_gamma_t * sin_gamma_t).max(0.).sqrt();
+        let gamma_t = sin_gamma_t.asin();
+        let path = 2. * cos_gamma_t / cos_theta_t;
+        let sigma_a = match &hit_record.wavelengths {
+            Some(wavelengths) => wavelengths.from_rgb(self.sigma_a),
+            None => self.sigma_a,
+        };
+        let t = Vec3(
+            (-sigma_a.r() * path).exp(),
+            (-sigma_a.g() * path).exp(),
+            (-sigma_a.b() * path).exp(),
+        );
+
+        let attenuations = ap(cos_theta_o, self.eta, h, t);
+        let total: f64 = attenuations.iter().map(|a| a.luminance()).sum();
+        if total <= 0. {
+            return None;
+        }
+        let lobe_pdf: Vec<f64> = attenuations.iter().map(|a| a.luminance() / total).collect();
+        let v = self.variances();
+        let s = self.logistic_scale();
+
+        // choose a lobe, then sample its longitudinal and azimuthal terms
+        let mut u0 = sampler.get_1d();
+        let mut p = 0;
+        while p < P_MAX && u0 >= lobe_pdf[p] {
+            u0 -= lobe_pdf[p];
+            p += 1;
+        }
+        let (sin_theta_op, cos_theta_op) = self.tilt(p, sin_theta_o, cos_theta_o);
+        let u1 = sampler.get_1d().max(1e-5);
+        let cos_theta = 1. + v[p] * (u1 + (1. - u1) * (-2. / v[p]).exp()).ln();
+        let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+        let cos_phi = (2. * PI * sampler.get_1d()).cos();
+        let sin_theta_i = -cos_theta * sin_theta_op + sin_theta * cos_phi * cos_theta_op;
+        let cos_theta_i = (1. - sin_theta_i * sin_theta_i).max(0.).sqrt();
+
+        let u2 = sampler.get_1d();
+        let dphi = if p < P_MAX {
+            phi(p, gamma_o, gamma_t) + sample_trimmed_logistic(u2, s, -PI, PI)
+        } else {
+            2. * PI * u2
+        };
+        let phi_i = phi_o + dphi;
+        let wi = Vec3(
+            sin_theta_i,
+            cos_theta_i * phi_i.cos(),
+            cos_theta_i * phi_i.sin(),
+        );
+
+        // evaluate f * |cos| and the pdf of the sampled direction over all
+        // lobes
+        let mut f = Vec3(0., 0., 0.);
+        let mut pdf = 0.;
+        for (lobe, attenuation) in attenuations.iter().enumerate() {
+            let (sin_op, cos_op) = self.tilt(lobe, sin_theta_o, cos_theta_o);
+            let m = mp(cos_theta_i, cos_op.abs(), sin_theta_i, sin_op, v[lobe]);
+            let n = if lobe < P_MAX {
+                np(dphi, lobe, s, gamma_o, gamma_t)
+            } else {
+                1. / (2. * PI)
+            };
+            f = f + m * n * *attenuation;
+            pdf += m * n * lobe_pdf[lobe];
+        }
+        if pdf <= 0. || pdf.is_nan() {
+            return None;
+        }
+
+        Some(Scatter {
+            attenuation: f / pdf,
+            scattered: Ray {
+                a: hit_record.p,
+                b: frame.local(&wi),
+                time: r.time(),
+                wavelengths: r.wavelengths,
+            },
+            pdf: None,
+        })
+    }
+
+    fn glossy(&self, _hit_record: &HitRecord) -> bool {
+        true
+    }
+}
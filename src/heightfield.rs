@@ -0,0 +1,266 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::bitmap::Bitmap;
+use crate::triangle;
+use crate::vec3::Vec3;
+use crate::{HitRecord, Hittable, Material, Ray};
+
+/// Terrain from a regular grid of heights, intersected in place by walking
+/// the grid cells under the ray with a 2D DDA, so a large terrain needs
+/// neither a triangle per cell in memory nor a test against each of them.
+///
+/// The grid spans `size.x()` by `size.z()` from `origin`, with heights in
+/// [0, 1] scaled by `size.y()`. Each cell is drawn as two triangles, shaded
+/// with normals from the grid's slopes; (u, v) run along x and z.
+pub struct Heightfield {
+    pub heights: Vec<f64>,
+    pub nx: usize,
+    pub nz: usize,
+    pub origin: Vec3,
+    pub size: Vec3,
+    pub material: Arc<dyn Material>,
+    min_height: f64,
+    max_height: f64,
+}
+
+impl Heightfield {
+    /// `heights` holds `nx` values along x for each of `nz` rows along z.
+    pub fn new(
+        heights: Vec<f64>,
+        nx: usize,
+        nz: usize,
+        origin: Vec3,
+        size: Vec3,
+        material: Arc<dyn Material>,
+    ) -> Heightfield {
+        assert!(nx >= 2 && nz >= 2 && heights.len() == nx * nz);
+        let min_height = heights.iter().cloned().fold(f64::MAX, f64::min);
+        let max_height = heights.iter().cloned().fold(f64::MIN, f64::max);
+        Heightfield {
+            heights,
+            nx,
+            nz,
+            origin,
+            size,
+            material,
+            min_height,
+            max_height,
+        }
+    }
+
+    /// Heights from the luminance of an image, its top row at the far (+z)
+    /// edge.
+    pub fn from_bitmap(
+        map: &Bitmap,
+        origin: Vec3,
+        size: Vec3,
+        material: Arc<dyn Material>,
+    ) -> Heightfield {
+        let heights = (0..map.height)
+            .rev()
+            .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+            .map(|(x, y)| map.pixel(x, y).luminance())
+            .collect();
+        Heightfield::new(heights, map.width, map.height, origin, size, material)
+    }
+
+    /// Heights from a function of (u, v) in [0, 1], such as noise.
+    pub fn from_fn<F: Fn(f64, f64) -> f64>(
+        nx: usize,
+        nz: usize,
+        f: F,
+        origin: Vec3,
+        size: Vec3,
+        material: Arc<dyn Material>,
+    ) -> Heightfield {
+        let heights = (0..nz)
+            .flat_map(|j| (0..nx).map(move |i| (i, j)))
+            .map(|(i, j)| f(i as f64 / (nx - 1) as f64, j as f64 / (nz - 1) as f64))
+            .collect();
+        Heightfield::new(heights, nx, nz, origin, size, material)
+    }
+
+    fn cell_size(&self) -> (f64, f64) {
+        (
+            self.size.x() / (self.nx - 1) as f64,
+            self.size.z() / (self.nz - 1) as f64,
+        )
+    }
+
+    fn height(&self, i: usize, j: usize) -> f64 {
+        self.heights[j * self.nx + i]
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> Vec3 {
+        let (dx, dz) = self.cell_size();
+        self.origin
+            + Vec3(
+                i as f64 * dx,
+                self.height(i, j) * self.size.y(),
+                j as f64 * dz,
+            )
+    }
+
+    /// Normal at a grid point from central differences of the heights.
+    fn vertex_normal(&self, i: usize, j: usize) -> Vec3 {
+        let (dx, dz) = self.cell_size();
+        let (i0, i1) = (i.saturating_sub(1), (i + 1).min(self.nx - 1));
+        let (j0, j1) = (j.saturating_sub(1), (j + 1).min(self.nz - 1));
+        let slope_x =
+            (self.height(i1, j) - self.height(i0, j)) * self.size.y() / ((i1 - i0) as f64 * dx);
+        let slope_z =
+            (self.height(i, j1) - self.height(i, j0)) * self.size.y() / ((j1 - j0) as f64 * dz);
+        Vec3(-slope_x, 1., -slope_z).unit()
+    }
+
+    fn uv(&self, i: usize, j: usize) -> (f64, f64) {
+        (
+            i as f64 / (self.nx - 1) as f64,
+            j as f64 / (self.nz - 1) as f64,
+        )
+    }
+
+    /// The nearest hit on the two triangles of cell (i, j).
+    fn hit_cell(
+        &self,
+        r: &Ray,
+        i: usize,
+        j: usize,
+        t_min: f64,
+        t_max: f64,
+    ) -> Option<HitRecord<'_>> {
+        let corners = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+        let mut closest: Option<HitRecord> = None;
+        for triangle in &[[0, 2, 1], [0, 3, 2]] {
+            let [a, b, c] = [
+                corners[triangle[0]],
+                corners[triangle[1]],
+                corners[triangle[2]],
+            ];
+            let vertices = [
+                self.vertex(a.0, a.1),
+                self.vertex(b.0, b.1),
+                self.vertex(c.0, c.1),
+            ];
+            let limit = closest.map_or(t_max, |h| h.t);
+            if let Some((t, beta, gamma)) =
+                triangle::intersect(r, vertices[0], vertices[1], vertices[2])
+            {
+                if t <= t_min || t >= limit {
+                    continue;
+                }
+                let normals = [
+                    self.vertex_normal(a.0, a.1),
+                    self.vertex_normal(b.0, b.1),
+                    self.vertex_normal(c.0, c.1),
+                ];
+                let uvs = [self.uv(a.0, a.1), self.uv(b.0, b.1), self.uv(c.0, c.1)];
+                let hit_record = triangle::hit_record(
+                    r,
+                    t,
+                    (beta, gamma),
+                    vertices,
+                    Some(normals),
+                    uvs,
+                    &*self.material,
+                );
+                if hit_record.opaque() {
+                    closest = Some(hit_record);
+                }
+            }
+        }
+        closest
+    }
+}
+
+impl Hittable for Heightfield {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let o = *r.origin() - self.origin;
+        let d = *r.direction();
+
+        // clip the ray to the box around the terrain
+        let lo = [0., self.min_height * self.size.y(), 0.];
+        let hi = [
+            self.size.x(),
+            self.max_height * self.size.y(),
+            self.size.z(),
+        ];
+        let (oa, da) = ([o.x(), o.y(), o.z()], [d.x(), d.y(), d.z()]);
+        let (mut t0, mut t1) = (t_min, t_max);
+        for k in 0..3 {
+            let inv = 1. / da[k];
+            let (mut near, mut far) = ((lo[k] - oa[k]) * inv, (hi[k] - oa[k]) * inv);
+            if inv < 0. {
+                std::mem::swap(&mut near, &mut far);
+            }
+            t0 = t0.max(near);
+            t1 = t1.min(far);
+        }
+        if t0 > t1 {
+            return None;
+        }
+
+        // walk the cells the ray passes over, nearest first
+        let (dx, dz) = self.cell_size();
+        let entry = o + t0 * d;
+        let (cells_x, cells_z) = (self.nx - 1, self.nz - 1);
+        let mut i = ((entry.x() / dx) as usize).min(cells_x - 1);
+        let mut j = ((entry.z() / dz) as usize).min(cells_z - 1);
+        let step_i = if d.x() > 0. { 1 } else { -1 };
+        let step_j = if d.z() > 0. { 1 } else { -1 };
+        let next_boundary = |cell: usize, step: i64, size: f64| {
+            if step > 0 {
+                (cell + 1) as f64 * size
+            } else {
+                cell as f64 * size
+            }
+        };
+        let mut t_next_x = if d.x() != 0. {
+            (next_boundary(i, step_i, dx) - o.x()) / d.x()
+        } else {
+            f64::MAX
+        };
+        let mut t_next_z = if d.z() != 0. {
+            (next_boundary(j, step_j, dz) - o.z()) / d.z()
+        } else {
+            f64::MAX
+        };
+        let t_delta_x = (dx / d.x()).abs();
+        let t_delta_z = (dz / d.z()).abs();
+
+        loop {
+            if let Some(hit_record) = self.hit_cell(r, i, j, t_min, t_max) {
+                return Some(hit_record);
+            }
+            if t_next_x.min(t_next_z) > t1 {
+                return None;
+            }
+            if t_next_x < t_next_z {
+                if (step_i < 0 && i == 0) || (step_i > 0 && i + 1 == cells_x) {
+                    return None;
+                }
+                i = (i as i64 + step_i) as usize;
+                t_next_x += t_delta_x;
+            } else {
+                if (step_j < 0 && j == 0) || (step_j > 0 && j + 1 == cells_z) {
+                    return None;
+                }
+                j = (j as i64 + step_j) as usize;
+                t_next_z += t_delta_z;
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb {
+            min: self.origin + Vec3(0., self.min_height * self.size.y(), 0.),
+            max: self.origin
+                + Vec3(
+                    self.size.x(),
+                    self.max_height * self.size.y(),
+                    self.size.z(),
+                ),
+        })
+    }
+}
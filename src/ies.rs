@@ -0,0 +1,160 @@
+use std::io;
+use std::path::Path;
+
+use crate::vec3::Vec3;
+
+/// How bright a light fixture is in each direction, as measured by its
+/// maker and given in an IES LM-63 photometric file, so lights can shine
+/// with the beam of a real downlight, wall washer or street lamp.
+///
+/// Directions are by type C photometry: vertical angles from straight down
+/// out of the fixture, its nadir, at 0° to straight up at 180°, and
+/// horizontal angles round the vertical axis. Files giving only some of the
+/// horizontal angles are mirrored round to the rest, as the format says.
+pub struct IesProfile {
+    /// Vertical angles measured at, in degrees, in increasing order.
+    vertical: Vec<f64>,
+    /// Horizontal angles measured at, in degrees, in increasing order.
+    horizontal: Vec<f64>,
+    /// Candelas at each vertical angle, for each horizontal angle in turn.
+    candela: Vec<f64>,
+    /// The brightest the fixture is in any direction, in candelas.
+    pub max_candela: f64,
+}
+
+impl IesProfile {
+    /// Read an IES LM-63 file. Tilt data, for lamps whose output changes
+    /// with how the fixture is tilted, is skipped.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<IesProfile> {
+        IesProfile::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// `load` for the contents of a file.
+    pub fn parse(text: &str) -> io::Result<IesProfile> {
+        let invalid = |what: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("bad IES file: {}", what),
+            )
+        };
+        // keywords and labels, then everything after the tilt line is
+        // numbers separated by spaces, commas or new lines
+        let after = text
+            .split_once("TILT=")
+            .ok_or_else(|| invalid("no TILT line"))?
+            .1;
+        let (tilt, rest) = after.split_once('\n').unwrap_or((after, ""));
+        let mut numbers = rest
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|word| !word.is_empty())
+            .map(|word| word.parse::<f64>().map_err(|_| invalid(word)));
+        let mut next = || {
+            numbers
+                .next()
+                .unwrap_or_else(|| Err(invalid("too few values")))
+        };
+        if tilt.trim() == "INCLUDE" {
+            // lamp to fixture geometry, then tilt angles and multipliers
+            next()?;
+            let count = next()? as usize;
+            for _ in 0..2 * count {
+                next()?;
+            }
+        }
+        // lamps and lumens per lamp
+        next()?;
+        next()?;
+        let multiplier = next()?;
+        let vertical_count = next()? as usize;
+        let horizontal_count = next()? as usize;
+        if next()? != 1. {
+            return Err(invalid("only type C photometry is supported"));
+        }
+        // units and the size of the luminous opening
+        for _ in 0..4 {
+            next()?;
+        }
+        let ballast_factor = next()?;
+        // the ballast-lamp factor and input watts
+        next()?;
+        next()?;
+        if vertical_count == 0 || horizontal_count == 0 {
+            return Err(invalid("no angles"));
+        }
+        let vertical = (0..vertical_count)
+            .map(|_| next())
+            .collect::<io::Result<Vec<_>>>()?;
+        let horizontal = (0..horizontal_count)
+            .map(|_| next())
+            .collect::<io::Result<Vec<_>>>()?;
+        let candela = (0..vertical_count * horizontal_count)
+            .map(|_| Ok(next()? * multiplier * ballast_factor))
+            .collect::<io::Result<Vec<_>>>()?;
+        let max_candela = candela.iter().cloned().fold(0., f64::max);
+        Ok(IesProfile {
+            vertical,
+            horizontal,
+            candela,
+            max_candela,
+        })
+    }
+
+    /// How bright the fixture is towards unit vector `local`, over its
+    /// brightest: `local` is in the fixture's frame, with +z its nadir and
+    /// horizontal angles measured round from +x towards +y.
+    pub fn value(&self, local: &Vec3) -> f64 {
+        if self.max_candela <= 0. {
+            return 0.;
+        }
+        let vertical = local.z().clamp(-1., 1.).acos().to_degrees();
+        let mut horizontal = local.y().atan2(local.x()).to_degrees().rem_euclid(360.);
+        // mirrored round from the angles given
+        let last = self.horizontal[self.horizontal.len() - 1];
+        if last <= 0. {
+            horizontal = 0.;
+        } else if last <= 180. {
+            if horizontal > 180. {
+                horizontal = 360. - horizontal;
+            }
+            if last <= 90. && horizontal > 90. {
+                horizontal = 180. - horizontal;
+            }
+        }
+        let (v, s) = match locate(&self.vertical, vertical) {
+            Some(at) => at,
+            None => return 0.,
+        };
+        let (h, t) =
+            locate(&self.horizontal, horizontal).unwrap_or((self.horizontal.len() - 1, 0.));
+        let at = |h: usize, v: usize| {
+            let n = self.vertical.len();
+            self.candela[h.min(self.horizontal.len() - 1) * n + v.min(n - 1)]
+        };
+        let near = (1. - s) * at(h, v) + s * at(h, v + 1);
+        let far = (1. - s) * at(h + 1, v) + s * at(h + 1, v + 1);
+        ((1. - t) * near + t * far) / self.max_candela
+    }
+}
+
+/// The angle of `angles` at or below `angle`, and how far `angle` is on
+/// towards the next, or None if it is outside them all.
+fn locate(angles: &[f64], angle: f64) -> Option<(usize, f64)> {
+    let (first, last) = (angles[0], angles[angles.len() - 1]);
+    if angle < first || angle > last {
+        return None;
+    }
+    if angles.len() == 1 {
+        return Some((0, 0.));
+    }
+    let i = angles
+        .partition_point(|&a| a <= angle)
+        .clamp(1, angles.len() - 1)
+        - 1;
+    let span = angles[i + 1] - angles[i];
+    let t = if span > 0. {
+        (angle - angles[i]) / span
+    } else {
+        0.
+    };
+    Some((i, t.clamp(0., 1.)))
+}
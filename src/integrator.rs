@@ -0,0 +1,750 @@
+use crate::environment::{Environment, Portal};
+use crate::fog::Fog;
+use crate::guiding::Guide;
+use crate::light::Light;
+use crate::nested::MediumStack;
+use crate::onb::Onb;
+use crate::pdf::{HenyeyGreenstein, MixturePdf, Pdf};
+use crate::sampler::{cosine_direction, Sampler};
+use crate::vec3::Vec3;
+use crate::{HitRecord, Hittable, Ray, Scatter};
+
+/// Something that works out the light coming back along camera rays, so
+/// images can be rendered with any way of finding it.
+pub trait Integrator: Send + Sync {
+    /// The light coming back along camera ray `r`, following its path with
+    /// the numbers `sampler` gives.
+    fn radiance(&self, r: Ray, sampler: &mut dyn Sampler) -> Vec3;
+
+    /// `radiance` for each of a batch of camera rays, each with its own
+    /// sampler.
+    fn radiance_packet(&self, rays: &[Ray], samplers: &mut [Box<dyn Sampler>]) -> Vec<Vec3> {
+        rays.iter()
+            .zip(samplers)
+            .map(|(r, sampler)| self.radiance(*r, sampler))
+            .collect()
+    }
+
+    /// `radiance` along with how opaque what the ray sees is, the color
+    /// premultiplied by it, for compositing over other images: opaque
+    /// unless the integrator can tell the background apart.
+    fn radiance_alpha(&self, r: Ray, sampler: &mut dyn Sampler) -> (Vec3, f64) {
+        (self.radiance(r, sampler), 1.)
+    }
+}
+
+/// Follows paths of light back from the camera through `world`. At each
+/// bounce off a surface that spreads light over all directions it also
+/// picks a point on one of `lights` and adds the light coming straight
+/// from there, next event estimation, so small lights are found far more
+/// often than by paths happening to hit them. Light found both ways is
+/// weighted between them by multiple importance sampling, trusting
+/// whichever was more likely to find it: sampling the lights for diffuse
+/// surfaces and big lights, following the material for glossy
+/// reflections of small bright ones.
+#[derive(Copy, Clone)]
+pub struct PathTracer<'a> {
+    pub world: &'a dyn Hittable,
+    /// The emitters to sample directly, also in `world`. Empty for scenes
+    /// lit only by the sky.
+    pub lights: &'a dyn Hittable,
+    /// Lights with no size, which are only found by looking for them from
+    /// each surface.
+    pub delta_lights: &'a [Box<dyn Light>],
+    /// The light arriving from far away, seen by rays that miss everything.
+    pub environment: &'a dyn Environment,
+    /// Openings the environment's light comes in through, also looked for
+    /// from each surface; empty for scenes lit from all around.
+    pub portals: &'a [Portal],
+    /// The most light a sample may bring straight from an emitter or the
+    /// sky, seen or lighting the first surface, if any.
+    pub clamp_direct: Option<f64>,
+    /// The most light a sample may bring after more bounces than that, if
+    /// any. Rare bright paths then leave no speckles, at the cost of some
+    /// of the light they would have added on average.
+    pub clamp_indirect: Option<f64>,
+    /// The least roughness of glossy and specular surfaces once a path has
+    /// bounced off a surface that spreads light, if any. Light reaching a
+    /// diffuse surface by way of a mirror or glass, like a caustic from a
+    /// small light, is then blurred into something paths can find, at the
+    /// cost of some sharpness; surfaces seen directly or in mirrors stay
+    /// as they are.
+    pub regularize: Option<f64>,
+    /// How many bounces of each kind a path may take before it stops.
+    pub max_bounces: MaxBounces,
+    /// Where light was found to come from by earlier passes, to send paths
+    /// that way half the time, and to tell where this pass finds it.
+    pub guide: Option<&'a Guide>,
+    /// Fog filling the air, which paths scatter in and shadow rays are
+    /// dimmed by, if any.
+    pub fog: Option<&'a Fog>,
+}
+
+/// The most bounces of each kind a path may take: lowering them trades
+/// light that takes many bounces of that kind, like inside glass or between
+/// mirrors, for speed.
+#[derive(Copy, Clone, Debug)]
+pub struct MaxBounces {
+    /// Off surfaces that spread light all over.
+    pub diffuse: i32,
+    /// Off glossy and mirror-like surfaces; see `Material::glossy`.
+    pub glossy: i32,
+    /// Through surfaces, into or out of objects.
+    pub transmission: i32,
+}
+
+impl<'a> PathTracer<'a> {
+    /// The light coming back along `r`, `depth` bounces into its path,
+    /// following it with the numbers `sampler` gives.
+    pub fn color(&self, r: Ray, depth: i32, sampler: &mut dyn Sampler) -> Vec3 {
+        let hit = self.world.hit(&r, 0.0001, f64::MAX);
+        self.shade(r, hit, depth, sampler)
+    }
+
+    /// `color` for a batch of camera rays, finding where they first hit as
+    /// a packet, and then what the shadow rays from those points towards
+    /// the lights hit as another. Each ray's path goes on with its own
+    /// sampler. Shadow rays from further bounces are cast path by path,
+    /// the paths having gone their separate ways by then.
+    pub fn color_packet<S: Sampler>(&self, rays: &[Ray], samplers: &mut [S]) -> Vec<Vec3> {
+        let hits = self.world.hit_packet(rays, 0.0001, f64::MAX);
+        // the guide needs each path's light as it is found
+        if self.guide.is_some() {
+            return hits
+                .into_iter()
+                .zip(rays)
+                .zip(samplers)
+                .map(|((hit, r), sampler)| self.shade(*r, hit, 0, sampler))
+                .collect();
+        }
+        let mut shadows = Vec::new();
+        // the path each shadow ray belongs to
+        let mut paths = Vec::new();
+        let mut found: Vec<(Vec3, Vec3)> = Vec::new();
+        for (i, ((hit, r), sampler)) in hits
+            .into_iter()
+            .zip(rays)
+            .zip(samplers.iter_mut())
+            .enumerate()
+        {
+            found.push(self.trace(*r, hit, 0, sampler, Some(&mut shadows)));
+            paths.resize(shadows.len(), i);
+        }
+        let shadow_rays: Vec<Ray> = shadows.iter().map(|shadow| shadow.ray).collect();
+        let shadow_hits = self.world.hit_packet(&shadow_rays, 0.0001, f64::MAX);
+        for ((shadow, hit), &i) in shadows.iter().zip(shadow_hits).zip(&paths) {
+            found[i].0 = found[i].0 + self.shadow_light(shadow, hit, &mut samplers[i]);
+        }
+        found
+            .into_iter()
+            .map(|(direct, indirect)| {
+                clamp(direct, self.clamp_direct) + clamp(indirect, self.clamp_indirect)
+            })
+            .collect()
+    }
+
+    /// The color seen along a camera ray along with its alpha, premultiplied
+    /// for compositing the render over a backplate: transparent black where
+    /// the ray sees the sky and opaque on objects. Shadow catchers come out
+    /// with an alpha giving how much of the sky's light other objects
+    /// block there, one sample at a time, so they darken the backplate just
+    /// where shadows fall. Where a catcher with a reflectance mirrors an
+    /// object, that much of the object's color is laid over the backplate;
+    /// where it mirrors the sky, the backplate is taken to show that already.
+    pub fn color_alpha(&self, r: Ray, sampler: &mut dyn Sampler) -> (Vec3, f64) {
+        let hit_record = match self.world.hit(&r, 0.0001, f64::MAX) {
+            Some(hit_record) => hit_record,
+            None => return (Vec3(0., 0., 0.), 0.),
+        };
+        let reflectance = match hit_record.material.shadow_catcher() {
+            Some(reflectance) => reflectance,
+            None => return (self.shade(r, Some(hit_record), 0, sampler), 1.),
+        };
+        let frame = Onb::from_w(&hit_record.normal);
+        let direction = frame.local(&cosine_direction(sampler.get_2d()));
+        let unshadowed = self.environment.radiance(&direction).luminance();
+        let shadowed = self
+            .color(
+                Ray {
+                    a: hit_record.p,
+                    b: direction,
+                    time: r.time(),
+                    wavelengths: r.wavelengths,
+                },
+                1,
+                sampler,
+            )
+            .luminance();
+        // the share of the sky's light getting past other objects, all of
+        // it where the sky sends none to block
+        let lit = if unshadowed > 0. {
+            (shadowed / unshadowed).clamp(0., 1.)
+        } else {
+            1.
+        };
+        if reflectance > 0. {
+            let mirrored = Ray {
+                a: hit_record.p,
+                b: r.direction().reflect(&hit_record.normal),
+                time: r.time(),
+                wavelengths: r.wavelengths,
+            };
+            if let Some(seen) = self.world.hit(&mirrored, 0.0001, f64::MAX) {
+                if seen.material.shadow_catcher().is_none() {
+                    let color = self.shade(mirrored, Some(seen), 1, sampler);
+                    return (reflectance * color, 1. - (1. - reflectance) * lit);
+                }
+            }
+        }
+        (Vec3(0., 0., 0.), 1. - lit)
+    }
+
+    /// The light coming back along `r`, given what it hits first, each
+    /// part clamped as the settings say.
+    fn shade(
+        &self,
+        r: Ray,
+        hit: Option<HitRecord<'a>>,
+        depth: i32,
+        sampler: &mut dyn Sampler,
+    ) -> Vec3 {
+        let (direct, indirect) = self.trace(r, hit, depth, sampler, None);
+        clamp(direct, self.clamp_direct) + clamp(indirect, self.clamp_indirect)
+    }
+
+    /// The light coming back along `r`, given what it hits first,
+    /// following the path on from there one bounce at a time: `throughput`
+    /// is what the light found at the current bounce is multiplied by on
+    /// its way back to the camera, and `emission` the share of the light
+    /// given off by what the ray hits that sampling the lights hasn't
+    /// already counted. Light found by the first surface is kept apart
+    /// from the rest so each can be clamped on its own. Given `deferred`,
+    /// the shadow rays from the first surface are left in it rather than
+    /// cast, their light still to be added to the first part.
+    fn trace(
+        &self,
+        mut r: Ray,
+        mut hit: Option<HitRecord<'a>>,
+        mut depth: i32,
+        sampler: &mut dyn Sampler,
+        mut deferred: Option<&mut Vec<ShadowRay>>,
+    ) -> (Vec3, Vec3) {
+        let mut media = MediumStack::default();
+        let mut direct = Vec3(0., 0., 0.);
+        let mut indirect = Vec3(0., 0., 0.);
+        let mut throughput = Vec3(1., 1., 1.);
+        let mut emission = 1.;
+        // the same for the environment's light
+        let mut background = 1.;
+        // whether the path has bounced off a surface that spreads light
+        let mut spread = false;
+        // bounces taken of each kind
+        let mut bounces = MaxBounces {
+            diffuse: 0,
+            glossy: 0,
+            transmission: 0,
+        };
+        // for the guide: where the path scattered, which way and how
+        // likely that was, what the light from there on was multiplied
+        // by, and the light found before
+        let mut vertices = Vec::new();
+        // the color the fog is crossed by, picked once for the whole path
+        // so paths through colored fog are weighted by how likely they were
+        // to be picked by any color, and how likely, relatively, for each
+        let channel = match self.fog {
+            Some(_) => ((sampler.get_1d() * 3.) as usize).min(2),
+            None => 0,
+        };
+        let mut chances = Vec3(1., 1., 1.);
+        loop {
+            if let Some(fog) = self.fog {
+                let t_max = hit.as_ref().map_or(f64::MAX, |hit_record| hit_record.t);
+                let (scattered, response, density) = fog.sample(&r, t_max, channel, sampler);
+                // weighted by the chance of the whole path for each color,
+                // kept with an average of one
+                let chance = (chances * density).average();
+                throughput = throughput * response / chance;
+                chances = chances * density / chance;
+                if let Some(t) = scattered {
+                    // scattered by the fog
+                    if bounces.diffuse >= self.max_bounces.diffuse {
+                        break;
+                    }
+                    bounces.diffuse += 1;
+                    let p = r.point_at_parameter(t);
+                    let pdf = HenyeyGreenstein {
+                        g: fog.g,
+                        forward: *r.direction(),
+                    };
+                    let phase = |direction: &Vec3| throughput * pdf.value(direction);
+                    let shadows = self.lights_at(&r, &p, &phase, &pdf, sampler);
+                    let light = self.gather(shadows, depth, &mut deferred, sampler);
+                    if depth == 0 {
+                        direct = direct + light;
+                    } else {
+                        indirect = indirect + light;
+                    }
+                    let direction = pdf.generate(sampler);
+                    let density = pdf.value(&direction);
+                    emission = power_heuristic(density, self.lights.pdf_value(&p, &direction));
+                    let environment = self
+                        .environment
+                        .pdf(&direction)
+                        .hypot(self.portal_pdf(&p, &direction));
+                    background = power_heuristic(density, environment);
+                    spread = true;
+                    depth += 1;
+                    r = Ray {
+                        a: p,
+                        b: direction,
+                        time: r.time(),
+                        wavelengths: r.wavelengths,
+                    };
+                    hit = self.world.hit(&r, 0.0001, f64::MAX);
+                    continue;
+                }
+            }
+            // light from the end of a path one bounce long at most
+            let radiance = if depth <= 1 {
+                &mut direct
+            } else {
+                &mut indirect
+            };
+            let mut hit_record = match hit {
+                Some(hit_record) => hit_record,
+                None => {
+                    *radiance = *radiance + background * throughput * self.environment.along(&r);
+                    break;
+                }
+            };
+            if let Some(medium) = hit_record.material.medium() {
+                if !media.is_visible(hit_record.material, medium) {
+                    // inside a higher priority object: carry straight on
+                    media.cross(hit_record.material, medium, hit_record.front_face);
+                    r = Ray {
+                        a: hit_record.p,
+                        b: *r.direction(),
+                        time: r.time(),
+                        wavelengths: r.wavelengths,
+                    };
+                    hit = self.world.hit(&r, 0.0001, f64::MAX);
+                    continue;
+                }
+                hit_record.exterior_ior = media.exterior_ior(hit_record.material);
+            }
+            if !hit_record.front_face && !hit_record.material.two_sided() {
+                break;
+            }
+            if spread {
+                hit_record.min_roughness = self.regularize.unwrap_or(0.);
+            }
+            *radiance = *radiance + emission * throughput * hit_record.emitted();
+            let scatter = match self.guided(&r, &hit_record, sampler) {
+                Some(scatter) => Some(scatter),
+                None => hit_record.material.scatter(&r, &hit_record, sampler),
+            };
+            let scatter = match scatter {
+                Some(scatter) if hit_record.consistent(scatter.scattered.direction()) => scatter,
+                _ => break,
+            };
+            let (count, max) = if scatter.scattered.direction().dot(hit_record.normal) < 0. {
+                (&mut bounces.transmission, self.max_bounces.transmission)
+            } else if hit_record.material.glossy(&hit_record) {
+                (&mut bounces.glossy, self.max_bounces.glossy)
+            } else {
+                (&mut bounces.diffuse, self.max_bounces.diffuse)
+            };
+            if *count >= max {
+                break;
+            }
+            *count += 1;
+            if let Some(medium) = hit_record.material.medium() {
+                if scatter.scattered.direction().dot(hit_record.normal) < 0. {
+                    media.cross(hit_record.material, medium, hit_record.front_face);
+                }
+            }
+            // only surfaces with a density can say how much light from the
+            // lights they scatter, or how likely they were to find it
+            // themselves
+            emission = match &scatter.pdf {
+                Some(pdf) => {
+                    let direction = scatter.scattered.direction();
+                    let bsdf = |direction: &Vec3| {
+                        throughput * hit_record.material.eval(&r, &hit_record, direction)
+                    };
+                    let shadows = self.lights_at(&r, &hit_record.p, &bsdf, &**pdf, sampler);
+                    let light = self.gather(shadows, depth, &mut deferred, sampler);
+                    if depth == 0 {
+                        direct = direct + light;
+                    } else {
+                        indirect = indirect + light;
+                    }
+                    let light_density = self.lights.pdf_value(&hit_record.p, direction);
+                    let density = pdf.value(direction);
+                    if self.guide.is_some() {
+                        let after = throughput * scatter.weight();
+                        vertices.push((
+                            hit_record.p,
+                            *direction,
+                            density,
+                            after,
+                            direct + indirect,
+                        ));
+                    }
+                    let environment = self
+                        .environment
+                        .pdf(direction)
+                        .hypot(self.portal_pdf(&hit_record.p, direction));
+                    background = power_heuristic(density, environment);
+                    power_heuristic(density, light_density)
+                }
+                None => {
+                    background = 1.;
+                    1.
+                }
+            };
+            spread |= scatter.pdf.is_some();
+            // the material's response divided by how likely it was to
+            // scatter this way
+            throughput = throughput * scatter.weight();
+            depth += 1;
+            r = scatter.scattered;
+            hit = self.world.hit(&r, 0.0001, f64::MAX);
+        }
+        if let Some(guide) = self.guide {
+            // the light each scattered direction brought, found by taking
+            // what was found before from the total and undoing what the
+            // path multiplied it by
+            for (p, direction, density, after, found) in vertices {
+                let incoming = (direct + indirect - found).luminance() / after.luminance();
+                guide.record(&p, &direction, incoming, density);
+            }
+        }
+        (direct, indirect)
+    }
+
+    /// For surfaces spreading light over all directions, where the guide
+    /// has learned something about the light around them, a scatter
+    /// drawn half the time from what it learned and otherwise from the
+    /// material.
+    fn guided(
+        &self,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        let guided = self.guide?.pdf(&hit_record.p)?;
+        let pdf = hit_record.material.pdf(r, hit_record)?;
+        let mixture = MixturePdf {
+            a: pdf,
+            b: guided,
+            weight: 0.5,
+        };
+        Some(Scatter::from_pdf(
+            Box::new(mixture),
+            hit_record.material,
+            r,
+            hit_record,
+            sampler,
+        ))
+    }
+
+    /// The shadow rays looking for the light reaching `p`, from the
+    /// lights, the environment and the lights with no size, that is
+    /// scattered back along `r` by `scatter`, the share of the light from
+    /// each direction sent that way, weighted against finding it by
+    /// scattering with `pdf`.
+    fn lights_at(
+        &self,
+        r: &Ray,
+        p: &Vec3,
+        scatter: &dyn Fn(&Vec3) -> Vec3,
+        pdf: &dyn Pdf,
+        sampler: &mut dyn Sampler,
+    ) -> Vec<ShadowRay> {
+        let mut shadows = Vec::new();
+        shadows.extend(self.direct(r, p, scatter, pdf, sampler));
+        self.background(r, p, scatter, pdf, sampler, &mut shadows);
+        self.delta(r, p, scatter, &mut shadows);
+        shadows
+    }
+
+    /// The light `shadows` find, cast one by one, or, for the first bounce
+    /// when there are `deferred` shadow rays, none yet, the rays being
+    /// left with those.
+    fn gather(
+        &self,
+        shadows: Vec<ShadowRay>,
+        depth: i32,
+        deferred: &mut Option<&mut Vec<ShadowRay>>,
+        sampler: &mut dyn Sampler,
+    ) -> Vec3 {
+        if let (0, Some(deferred)) = (depth, deferred) {
+            deferred.extend(shadows);
+            return Vec3(0., 0., 0.);
+        }
+        let mut total = Vec3(0., 0., 0.);
+        for shadow in &shadows {
+            let hit = self.world.hit(&shadow.ray, 0.0001, f64::MAX);
+            total = total + self.shadow_light(shadow, hit, sampler);
+        }
+        total
+    }
+
+    /// The light `shadow` brings, given what it hits.
+    fn shadow_light(
+        &self,
+        shadow: &ShadowRay,
+        hit: Option<HitRecord>,
+        sampler: &mut dyn Sampler,
+    ) -> Vec3 {
+        match (shadow.beyond, hit) {
+            // the light, unless something is in the way
+            (None, Some(light)) if light.front_face || light.material.two_sided() => {
+                let transmittance = self.transmittance(&shadow.ray, light.t, sampler);
+                shadow.weight * light.emitted() * transmittance
+            }
+            (None, _) => Vec3(0., 0., 0.),
+            (Some((radiance, t_max)), hit) => {
+                if hit.is_some_and(|hit| hit.t < t_max) {
+                    return Vec3(0., 0., 0.);
+                }
+                shadow.weight * radiance * self.transmittance(&shadow.ray, t_max, sampler)
+            }
+        }
+    }
+
+    /// The shadow ray towards a point picked on one of the lights, for
+    /// the light `scatter` sends back along `r`, weighted against finding
+    /// it by scattering with `pdf`.
+    fn direct(
+        &self,
+        r: &Ray,
+        p: &Vec3,
+        scatter: &dyn Fn(&Vec3) -> Vec3,
+        pdf: &dyn Pdf,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ShadowRay> {
+        let p = *p;
+        let direction = self.lights.random(&p, sampler);
+        let density = self.lights.pdf_value(&p, &direction);
+        if density <= 0. {
+            return None;
+        }
+        let weight = power_heuristic(density, pdf.value(&direction));
+        Some(ShadowRay {
+            ray: Ray {
+                a: p,
+                b: direction,
+                time: r.time(),
+                wavelengths: r.wavelengths,
+            },
+            weight: scatter(&direction) * weight / density,
+            beyond: None,
+        })
+    }
+
+    /// The shadow rays towards the environment, for the light `scatter`
+    /// sends back along `r`, in a direction drawn from all around and one
+    /// through a portal, if there are any, each weighted against finding it
+    /// the other ways, including scattering with `pdf`.
+    fn background(
+        &self,
+        r: &Ray,
+        p: &Vec3,
+        scatter: &dyn Fn(&Vec3) -> Vec3,
+        pdf: &dyn Pdf,
+        sampler: &mut dyn Sampler,
+        shadows: &mut Vec<ShadowRay>,
+    ) {
+        let p = *p;
+        let towards = |direction| Ray {
+            a: p,
+            b: direction,
+            time: r.time(),
+            wavelengths: r.wavelengths,
+        };
+        if let Some(direction) = self.environment.sample(sampler) {
+            let density = self.environment.pdf(&direction);
+            let other = self.portal_pdf(&p, &direction);
+            shadows.extend(self.background_along(
+                towards(direction),
+                scatter,
+                pdf,
+                (density, other),
+            ));
+        }
+        let n = self.portals.len();
+        if n > 0 {
+            let i = ((sampler.get_1d() * n as f64) as usize).min(n - 1);
+            let direction = self.portals[i].sample(&p, sampler.get_2d());
+            let density = self.portal_pdf(&p, &direction);
+            let other = self.environment.pdf(&direction);
+            shadows.extend(self.background_along(
+                towards(direction),
+                scatter,
+                pdf,
+                (density, other),
+            ));
+        }
+    }
+
+    /// The shadow ray looking for the light from the environment arriving
+    /// back along `shadow_ray`, whose direction was drawn with `density`
+    /// and found by the other way of looking for it with density `other`,
+    /// that `scatter` sends on.
+    fn background_along(
+        &self,
+        shadow_ray: Ray,
+        scatter: &dyn Fn(&Vec3) -> Vec3,
+        pdf: &dyn Pdf,
+        (density, other): (f64, f64),
+    ) -> Option<ShadowRay> {
+        if density <= 0. {
+            return None;
+        }
+        let direction = shadow_ray.direction();
+        // the power heuristic over all three ways
+        let weight = power_heuristic(density, pdf.value(direction).hypot(other));
+        Some(ShadowRay {
+            weight: scatter(direction) * weight / density,
+            beyond: Some((self.environment.along(&shadow_ray), f64::MAX)),
+            ray: shadow_ray,
+        })
+    }
+
+    /// The share of the light that gets along `r` up to `t_max` through the
+    /// fog, if there is any.
+    fn transmittance(&self, r: &Ray, t_max: f64, sampler: &mut dyn Sampler) -> Vec3 {
+        match self.fog {
+            Some(fog) => fog.transmittance(r, t_max, sampler),
+            None => Vec3(1., 1., 1.),
+        }
+    }
+
+    /// The density, per unit solid angle, with which a direction through a
+    /// point picked on one of the portals from `p` is `direction`.
+    fn portal_pdf(&self, p: &Vec3, direction: &Vec3) -> f64 {
+        let total: f64 = self
+            .portals
+            .iter()
+            .map(|portal| portal.pdf(p, direction))
+            .sum();
+        total / self.portals.len().max(1) as f64
+    }
+
+    /// The shadow rays towards each of the lights with no size, for the
+    /// light from them that `scatter` sends back along `r`.
+    fn delta(
+        &self,
+        r: &Ray,
+        p: &Vec3,
+        scatter: &dyn Fn(&Vec3) -> Vec3,
+        shadows: &mut Vec<ShadowRay>,
+    ) {
+        for light in self.delta_lights {
+            let illumination = match light.illuminate(p) {
+                Some(illumination) => illumination,
+                None => continue,
+            };
+            let irradiance = match &r.wavelengths {
+                Some(wavelengths) => wavelengths.from_rgb(illumination.irradiance),
+                None => illumination.irradiance,
+            };
+            // up to the light, unless it is infinitely far away
+            let t_max = if illumination.distant {
+                f64::MAX
+            } else {
+                0.9999
+            };
+            shadows.push(ShadowRay {
+                ray: Ray {
+                    a: *p,
+                    b: illumination.direction,
+                    time: r.time(),
+                    wavelengths: r.wavelengths,
+                },
+                weight: scatter(&illumination.direction),
+                beyond: Some((irradiance, t_max)),
+            });
+        }
+    }
+}
+
+/// A ray cast from a point towards a light to see how much of the light
+/// gets there.
+struct ShadowRay {
+    ray: Ray,
+    /// What the light found is multiplied by on its way to the camera.
+    weight: Vec3,
+    /// None if the light is whatever the ray hits; otherwise the light
+    /// from beyond the ray's parameter reaching the given one, found if
+    /// nothing is in the way before that.
+    beyond: Option<(Vec3, f64)>,
+}
+
+impl Integrator for PathTracer<'_> {
+    fn radiance(&self, r: Ray, sampler: &mut dyn Sampler) -> Vec3 {
+        self.color(r, 0, sampler)
+    }
+
+    fn radiance_packet(&self, rays: &[Ray], samplers: &mut [Box<dyn Sampler>]) -> Vec<Vec3> {
+        self.color_packet(rays, samplers)
+    }
+
+    fn radiance_alpha(&self, r: Ray, sampler: &mut dyn Sampler) -> (Vec3, f64) {
+        self.color_alpha(r, sampler)
+    }
+}
+
+/// `c` scaled down, keeping its hue, so no component is over `max`.
+fn clamp(c: Vec3, max: Option<f64>) -> Vec3 {
+    let largest = c.r().max(c.g()).max(c.b());
+    match max {
+        Some(max) if largest > max => max / largest * c,
+        _ => c,
+    }
+}
+
+/// Ambient occlusion: how open the sky is above the first surface a camera
+/// ray hits, white where nothing within `max_distance` blocks it and black
+/// where it is completely hidden. Each sample sends one ray out from the
+/// surface in a cosine distributed direction, so it comes out the way
+/// uniform light from all around would light a white diffuse surface,
+/// quickly and with little noise, for previews and for baking occlusion
+/// into textures.
+#[derive(Copy, Clone)]
+pub struct AmbientOcclusion<'a> {
+    pub world: &'a dyn Hittable,
+    /// How far away things still block the sky, in scene units.
+    pub max_distance: f64,
+}
+
+impl Integrator for AmbientOcclusion<'_> {
+    fn radiance(&self, r: Ray, sampler: &mut dyn Sampler) -> Vec3 {
+        let hit_record = match self.world.hit(&r, 0.0001, f64::MAX) {
+            Some(hit_record) => hit_record,
+            None => return Vec3(1., 1., 1.),
+        };
+        let frame = Onb::from_w(&hit_record.normal);
+        let occlusion_ray = Ray {
+            a: hit_record.p,
+            b: frame.local(&cosine_direction(sampler.get_2d())),
+            time: r.time(),
+            wavelengths: r.wavelengths,
+        };
+        match self.world.hit(&occlusion_ray, 0.0001, self.max_distance) {
+            Some(_) => Vec3(0., 0., 0.),
+            None => Vec3(1., 1., 1.),
+        }
+    }
+}
+
+/// Veach's power heuristic: the share of a sample drawn with density `f`
+/// when it could also have been drawn with density `g`.
+fn power_heuristic(f: f64, g: f64) -> f64 {
+    if f <= 0. {
+        return 0.;
+    }
+    f * f / (f * f + g * g)
+}
@@ -0,0 +1,185 @@
+use crate::aabb::Aabb;
+use crate::vec3::Vec3;
+use crate::{HitRecord, Hittable, Ray};
+
+/// A kd-tree: space cut in two by axis-aligned planes, recursively, with the
+/// objects overlapping each final cell listed in it. Rays visit the cells
+/// they pass through front to back and stop at the first cell holding a
+/// hit, so unlike a BVH an object can be listed, and tested, more than
+/// once.
+pub struct KdTree {
+    objects: Vec<Box<dyn Hittable>>,
+    nodes: Vec<KdNode>,
+    bbox: Aabb,
+}
+
+enum KdNode {
+    /// Children are the half below `split` along `axis` and the half above.
+    Interior {
+        axis: usize,
+        split: f64,
+        below: usize,
+        above: usize,
+    },
+    Leaf(Vec<usize>),
+}
+
+/// Cells with this few objects aren't split further.
+const LEAF_SIZE: usize = 2;
+
+fn axis(v: &Vec3, i: usize) -> f64 {
+    match i {
+        0 => v.x(),
+        1 => v.y(),
+        _ => v.z(),
+    }
+}
+
+impl KdTree {
+    /// Build a tree over `objects`, cutting each cell through the middle of
+    /// its longest side until it holds few objects or cutting stops helping.
+    ///
+    /// Panics if `objects` is empty or any of them is unbounded.
+    pub fn new(objects: Vec<Box<dyn Hittable>>) -> KdTree {
+        assert!(!objects.is_empty(), "a kd-tree needs at least one object");
+        let boxes: Vec<Aabb> = objects
+            .iter()
+            .map(|object| {
+                object
+                    .bounding_box()
+                    .expect("objects in a kd-tree must be bounded")
+            })
+            .collect();
+        let bbox = boxes[1..]
+            .iter()
+            .fold(boxes[0], |sum, b| Aabb::surrounding(&sum, b));
+        let max_depth = 8 + (1.3 * (objects.len() as f64).log2()) as usize;
+        let mut tree = KdTree {
+            objects,
+            nodes: Vec::new(),
+            bbox,
+        };
+        tree.build(&boxes, (0..boxes.len()).collect(), bbox, max_depth);
+        tree
+    }
+
+    /// Add the node for `cell`, holding `members`, and return its index.
+    fn build(&mut self, boxes: &[Aabb], members: Vec<usize>, cell: Aabb, depth: usize) -> usize {
+        let index = self.nodes.len();
+        self.nodes.push(KdNode::Leaf(Vec::new()));
+        if members.len() <= LEAF_SIZE || depth == 0 {
+            self.nodes[index] = KdNode::Leaf(members);
+            return index;
+        }
+
+        let size = cell.max - cell.min;
+        let i = if size.x() >= size.y() && size.x() >= size.z() {
+            0
+        } else if size.y() >= size.z() {
+            1
+        } else {
+            2
+        };
+        let split = axis(&cell.center(), i);
+        let below: Vec<usize> = members
+            .iter()
+            .cloned()
+            .filter(|&m| axis(&boxes[m].min, i) <= split)
+            .collect();
+        let above: Vec<usize> = members
+            .iter()
+            .cloned()
+            .filter(|&m| axis(&boxes[m].max, i) >= split)
+            .collect();
+        // a cut every object straddles only makes work
+        if below.len() == members.len() && above.len() == members.len() {
+            self.nodes[index] = KdNode::Leaf(members);
+            return index;
+        }
+
+        let (mut below_cell, mut above_cell) = (cell, cell);
+        match i {
+            0 => {
+                below_cell.max.0 = split;
+                above_cell.min.0 = split;
+            }
+            1 => {
+                below_cell.max.1 = split;
+                above_cell.min.1 = split;
+            }
+            _ => {
+                below_cell.max.2 = split;
+                above_cell.min.2 = split;
+            }
+        }
+        let below = self.build(boxes, below, below_cell, depth - 1);
+        let above = self.build(boxes, above, above_cell, depth - 1);
+        self.nodes[index] = KdNode::Interior {
+            axis: i,
+            split,
+            below,
+            above,
+        };
+        index
+    }
+}
+
+impl Hittable for KdTree {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let (t0, t1) = self.bbox.clip(r, t_min, t_max)?;
+        let mut closest: Option<HitRecord> = None;
+        // cells still to visit, nearest on top, with the stretch of the ray
+        // inside each
+        let mut stack = vec![(0, t0, t1)];
+        while let Some((node, near, far)) = stack.pop() {
+            if closest.is_some_and(|h| h.t <= near) {
+                break;
+            }
+            match &self.nodes[node] {
+                KdNode::Interior {
+                    axis: i,
+                    split,
+                    below,
+                    above,
+                } => {
+                    let o = axis(r.origin(), *i);
+                    let d = axis(r.direction(), *i);
+                    let (first, second) = if o < *split || (o == *split && d <= 0.) {
+                        (*below, *above)
+                    } else {
+                        (*above, *below)
+                    };
+                    // NaN when the ray runs within the plane, which objects
+                    // on it are listed on both sides of
+                    let t_split = (split - o) / d;
+                    if t_split.is_nan() || t_split > far || t_split <= 0. {
+                        stack.push((first, near, far));
+                    } else if t_split < near {
+                        stack.push((second, near, far));
+                    } else {
+                        stack.push((second, t_split, far));
+                        stack.push((first, near, t_split));
+                    }
+                }
+                KdNode::Leaf(members) => {
+                    for &m in members {
+                        let limit = closest.map_or(t_max, |h| h.t);
+                        if let Some(hit_record) = self.objects[m].hit(r, t_min, limit) {
+                            closest = Some(hit_record);
+                        }
+                    }
+                    // a hit beyond this cell may yet be beaten by an object
+                    // in a later one
+                    if closest.is_some_and(|h| h.t <= far) {
+                        break;
+                    }
+                }
+            }
+        }
+        closest
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bbox)
+    }
+}
@@ -0,0 +1,123 @@
+use crate::microfacet::fresnel_dielectric;
+use crate::sampler::Sampler;
+use crate::spectrum::Wavelengths;
+use crate::texture::Texture;
+use crate::vec3::Vec3;
+use crate::{HitRecord, Material, Ray, Scatter};
+
+/// Give up on paths still bouncing between the base and the underside of the
+/// coat after this many round trips.
+const MAX_INTERNAL_BOUNCES: usize = 8;
+
+/// A smooth dielectric coat over an arbitrary base material, like lacquer on
+/// wood or the clear coat on car paint.
+///
+/// Light is followed through the stack as a random walk: it either reflects
+/// off the coat or refracts in, is absorbed by the coat on the way down,
+/// scatters off the base, and then either escapes through the coat or is
+/// reflected back down to the base again.
+pub struct Layered {
+    pub base: Box<dyn Material>,
+    pub coat_ior: f64,
+    /// Fraction of light that makes it through the coat at normal incidence.
+    pub coat_color: Box<dyn Texture>,
+}
+
+/// Transmittance for one pass through a coat of color `coat` at `cosine` to
+/// the normal.
+fn absorption(coat: Vec3, cosine: f64) -> Vec3 {
+    let k = 1. / cosine.max(1e-4);
+    Vec3(coat.r().powf(k), coat.g().powf(k), coat.b().powf(k))
+}
+
+impl Material for Layered {
+    fn scatter(
+        &self,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        let n = hit_record.normal;
+        let p = hit_record.p;
+        let d = r.direction().unit();
+        if !hit_record.front_face {
+            // the stack is one-sided
+            return None;
+        }
+        let cos_i = -d.dot(n);
+        // reflection and refraction are chosen by the Fresnel term, which
+        // then cancels out of the weights
+        if sampler.get_1d() < fresnel_dielectric(cos_i, self.coat_ior) {
+            return Some(Scatter {
+                attenuation: Vec3(1., 1., 1.),
+                scattered: Ray {
+                    a: p,
+                    b: d.reflect(&n),
+                    time: r.time(),
+                    wavelengths: r.wavelengths,
+                },
+                pdf: None,
+            });
+        }
+
+        let coat = hit_record.texture(&*self.coat_color);
+        let mut direction = d.refract(&n, 1. / self.coat_ior)?;
+        let mut attenuation = Vec3(1., 1., 1.);
+        for _ in 0..MAX_INTERNAL_BOUNCES {
+            attenuation = attenuation * absorption(coat, -direction.unit().dot(n));
+            let bounce = self.base.scatter(
+                &Ray {
+                    a: p,
+                    b: direction,
+                    time: r.time(),
+                    wavelengths: r.wavelengths,
+                },
+                hit_record,
+                sampler,
+            )?;
+            let up = bounce.scattered.direction().unit();
+            let cos_up = up.dot(n);
+            if cos_up <= 0. {
+                return None;
+            }
+            attenuation = attenuation * bounce.weight() * absorption(coat, cos_up);
+            let escapes = sampler.get_1d() >= fresnel_dielectric(cos_up, 1. / self.coat_ior);
+            match up.refract(&-n, self.coat_ior) {
+                Some(out) if escapes => {
+                    return Some(Scatter {
+                        attenuation,
+                        scattered: Ray {
+                            a: p,
+                            b: out,
+                            time: r.time(),
+                            wavelengths: r.wavelengths,
+                        },
+                        pdf: None,
+                    });
+                }
+                _ => direction = up.reflect(&n),
+            }
+        }
+        None
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Vec3) -> Vec3 {
+        self.base.emitted(u, v, p)
+    }
+
+    fn emitted_spectral(&self, u: f64, v: f64, p: &Vec3, wavelengths: &Wavelengths) -> Vec3 {
+        self.base.emitted_spectral(u, v, p, wavelengths)
+    }
+
+    fn alpha(&self, u: f64, v: f64, p: &Vec3) -> f64 {
+        self.base.alpha(u, v, p)
+    }
+
+    fn two_sided(&self) -> bool {
+        self.base.two_sided()
+    }
+
+    fn glossy(&self, hit_record: &HitRecord) -> bool {
+        self.base.glossy(hit_record)
+    }
+}
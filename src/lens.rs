@@ -0,0 +1,239 @@
+use crate::vec3::Vec3;
+
+/// One surface of a compound lens: a spherical cap, or a flat opening for
+/// the aperture stop. Sizes are in millimetres.
+#[derive(Copy, Clone, Debug)]
+pub struct LensElement {
+    /// Positive when the center of curvature lies toward the film, and zero
+    /// for the aperture stop.
+    pub curvature_radius: f64,
+    /// Distance along the axis to the next surface toward the film.
+    pub thickness: f64,
+    /// Refractive index of what lies between this surface and the next, 1
+    /// for air.
+    pub ior: f64,
+    /// Diameter of the part of the surface light gets through.
+    pub aperture: f64,
+}
+
+/// A stack of lens elements in front of the film, through which camera
+/// rays are traced so the lens's own distortion, vignetting and bokeh show
+/// up in the image.
+///
+/// Lens space has the film at z = 0 and the scene toward +z, in millimetres.
+#[derive(Clone, Debug)]
+pub struct LensSystem {
+    /// From the front, facing the scene, to the rear. The rear element's
+    /// thickness is ignored: `focus` sets the distance to the film.
+    pub elements: Vec<LensElement>,
+    /// Diagonal of the film in millimetres.
+    pub film_diagonal: f64,
+    /// Scene units per millimetre: 0.001 for a scene in metres.
+    pub scale: f64,
+    film_distance: f64,
+    /// Radius of the middle part of the rear element that rays from
+    /// anywhere on the film can get through the lens from, the only part
+    /// worth aiming at.
+    pupil_radius: f64,
+}
+
+impl LensSystem {
+    /// A lens from `elements`, focused at infinity.
+    pub fn new(elements: Vec<LensElement>, film_diagonal: f64, scale: f64) -> LensSystem {
+        let mut lens = LensSystem {
+            elements,
+            film_diagonal,
+            scale,
+            film_distance: 0.,
+            pupil_radius: 0.,
+        };
+        lens.focus(f64::INFINITY);
+        lens
+    }
+
+    /// A 50mm f/2 double Gauss lens, a classic standard lens design, in
+    /// front of full frame film.
+    pub fn double_gauss(scale: f64) -> LensSystem {
+        let element = |curvature_radius, thickness, ior, aperture| LensElement {
+            curvature_radius,
+            thickness,
+            ior,
+            aperture,
+        };
+        LensSystem::new(
+            vec![
+                element(29.475, 3.76, 1.67, 25.2),
+                element(84.83, 0.12, 1., 25.2),
+                element(19.275, 4.025, 1.67, 23.),
+                element(40.77, 3.275, 1.699, 23.),
+                element(12.75, 5.705, 1., 18.),
+                element(0., 4.5, 1., 17.1),
+                element(-14.495, 1.18, 1.603, 17.),
+                element(40.77, 6.065, 1.658, 20.),
+                element(-20.385, 0.19, 1., 20.),
+                element(437.065, 3.22, 1.717, 20.),
+                element(-39.73, 0., 1., 20.),
+            ],
+            43.27,
+            scale,
+        )
+    }
+
+    /// Move the film so things `distance` scene units in front of the lens
+    /// are sharp, and find the part of the rear element rays from the film
+    /// get through from.
+    pub fn focus(&mut self, distance: f64) {
+        self.place_film(distance);
+        self.pupil_radius = self.find_pupil();
+    }
+
+    /// Follow a ray from `distance` away close to the axis through the lens
+    /// and put the film where it crosses the axis behind it.
+    fn place_film(&mut self, distance: f64) {
+        self.film_distance = 0.;
+        let front = self.elements[0];
+        let height = 0.05 * front.aperture / 2.;
+        let target = Vec3(0., height, self.surface_z(0));
+        let direction = if distance.is_finite() {
+            target - Vec3(0., 0., self.surface_z(0) + distance / self.scale)
+        } else {
+            Vec3(0., 0., -1.)
+        };
+        let start = target - direction;
+        let mut ior = 1.;
+        let mut ray = (start, direction);
+        for (i, element) in self.elements.iter().enumerate() {
+            ray = match self.pass(i, ray, ior, element.ior) {
+                Some(ray) => ray,
+                None => return,
+            };
+            ior = element.ior;
+        }
+        let (p, d) = ray;
+        // the rear element sits at z = 0 while film_distance is zero
+        self.film_distance = -(p.z() - p.y() / d.y() * d.z());
+    }
+
+    /// Try rays from points out to the corner of the film, the lens being
+    /// round, toward a grid of points across the rear element, and return
+    /// the radius around the axis, plus a grid step, that the ones getting
+    /// through start within.
+    fn find_pupil(&self) -> f64 {
+        const STEPS: usize = 64;
+        let rim = self.elements[self.elements.len() - 1].aperture / 2.;
+        let step = 2. * rim / STEPS as f64;
+        let mut radius: f64 = 0.;
+        for i in 0..=16 {
+            let film = i as f64 / 16. * self.film_diagonal / 2.;
+            for x in 0..=STEPS {
+                for y in 0..=STEPS {
+                    let (rx, ry) = (x as f64 * step - rim, y as f64 * step - rim);
+                    let r = (rx * rx + ry * ry).sqrt();
+                    if r > radius && self.trace(Vec3(film, 0., 0.), (rx, ry)).is_some() {
+                        radius = r;
+                    }
+                }
+            }
+        }
+        (radius + step).min(rim)
+    }
+
+    /// The z of the vertex of surface `i`.
+    fn surface_z(&self, i: usize) -> f64 {
+        let last = self.elements.len() - 1;
+        self.film_distance
+            + self.elements[i..last]
+                .iter()
+                .map(|e| e.thickness)
+                .sum::<f64>()
+    }
+
+    /// The z of the front of the lens.
+    pub fn front_z(&self) -> f64 {
+        self.surface_z(0)
+    }
+
+    /// The film's width and height in millimetres for images `aspect` times
+    /// wider than they are high.
+    pub fn film_size(&self, aspect: f64) -> (f64, f64) {
+        let height = self.film_diagonal / (1. + aspect * aspect).sqrt();
+        (aspect * height, height)
+    }
+
+    /// Carry ray `(p, d)` across surface `i`, from a medium of index
+    /// `ior_in` to one of `ior_out`, or None if the surface's rim stops it
+    /// or it reflects back.
+    fn pass(
+        &self,
+        i: usize,
+        (p, d): (Vec3, Vec3),
+        ior_in: f64,
+        ior_out: f64,
+    ) -> Option<(Vec3, Vec3)> {
+        let element = self.elements[i];
+        let z = self.surface_z(i);
+        let radius = element.curvature_radius;
+        let (t, normal) = if radius == 0. {
+            let t = (z - p.z()) / d.z();
+            (t, Vec3(0., 0., 1.))
+        } else {
+            let center = Vec3(0., 0., z - radius);
+            let oc = p - center;
+            let a = d.dot(d);
+            let b = 2. * oc.dot(d);
+            let c = oc.dot(oc) - radius * radius;
+            let discriminant = b * b - 4. * a * c;
+            if discriminant < 0. {
+                return None;
+            }
+            let roots = (
+                (-b - discriminant.sqrt()) / (2. * a),
+                (-b + discriminant.sqrt()) / (2. * a),
+            );
+            // the cap is the half of the sphere the vertex is on
+            let t = if (d.z() > 0.) == (radius < 0.) {
+                roots.0
+            } else {
+                roots.1
+            };
+            (t, (p + t * d - center) / radius.abs())
+        };
+        if t <= 0. {
+            return None;
+        }
+        let hit = p + t * d;
+        let rim = element.aperture / 2.;
+        if hit.x() * hit.x() + hit.y() * hit.y() > rim * rim {
+            return None;
+        }
+        if radius == 0. || ior_in == ior_out {
+            return Some((hit, d));
+        }
+        let normal = if normal.dot(d) > 0. { -normal } else { normal };
+        Some((hit, d.refract(&normal, ior_in / ior_out)?))
+    }
+
+    /// A ray from point (x, y) on the film out through the lens toward the
+    /// point of its rear element at `rear` in a unit disk, in lens space,
+    /// or None if the lens stops it. Rays are aimed at the same area of the
+    /// rear element from everywhere on the film, so how many get through
+    /// falls off toward the corners just as the light a real lens lets
+    /// through does.
+    pub fn trace_from_film(&self, (x, y): (f64, f64), rear: (f64, f64)) -> Option<(Vec3, Vec3)> {
+        let r = self.pupil_radius;
+        self.trace(Vec3(x, y, 0.), (r * rear.0, r * rear.1))
+    }
+
+    /// The ray from `film` toward point `rear` of the rear element, out
+    /// through the lens.
+    fn trace(&self, film: Vec3, (x, y): (f64, f64)) -> Option<(Vec3, Vec3)> {
+        let last = self.elements.len() - 1;
+        let mut ray = (film, Vec3(x, y, self.surface_z(last)) - film);
+        for i in (0..=last).rev() {
+            let ior_in = self.elements[i].ior;
+            let ior_out = if i == 0 { 1. } else { self.elements[i - 1].ior };
+            ray = self.pass(i, ray, ior_in, ior_out)?;
+        }
+        Some(ray)
+    }
+}
@@ -0,0 +1,1129 @@
+pub mod aabb;
+pub mod bidirectional;
+pub mod bitmap;
+pub mod blue_noise;
+pub mod bvh;
+pub mod camera;
+pub mod csg;
+pub mod cuboid;
+pub mod curve;
+pub mod cutout;
+pub mod cylinder;
+#[cfg(feature = "embree")]
+pub mod embree;
+pub mod environment;
+pub mod film;
+pub mod fog;
+pub mod grid;
+pub mod guiding;
+pub mod hair;
+pub mod heightfield;
+pub mod ies;
+pub mod integrator;
+pub mod kdtree;
+pub mod layered;
+pub mod lens;
+pub mod light;
+pub mod light_tree;
+pub mod mesh;
+pub mod metropolis;
+pub mod microfacet;
+pub mod nested;
+pub mod normal_map;
+pub mod onb;
+pub mod pdf;
+pub mod perlin;
+pub mod principled;
+pub mod quad;
+pub mod quadric;
+pub mod registry;
+pub mod restir;
+pub mod sampler;
+pub mod scenes;
+pub mod sdf;
+pub mod sheen;
+pub mod sided;
+pub mod sky;
+pub mod spectrum;
+pub mod subdivision;
+pub mod texture;
+pub mod thin_film;
+pub mod tlas;
+pub mod torus;
+pub mod transform;
+pub mod triangle;
+pub mod vec3;
+pub mod volume;
+
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::microfacet::{DielectricPdf, Ggx};
+use crate::pdf::{CosinePdf, MixturePdf, Pdf};
+use crate::sampler::{random_at, sample_ball, Sampler};
+use crate::spectrum::Wavelengths;
+use crate::texture::Texture;
+use crate::vec3::Vec3;
+
+#[derive(Debug, Copy, Clone)]
+pub struct Ray {
+    pub a: Vec3,
+    pub b: Vec3,
+    /// When the ray was cast, within the camera's shutter interval; the
+    /// rays it scatters into keep it.
+    pub time: f64,
+    /// In spectral rendering, the wavelengths the colors along the ray's
+    /// path are values at, in place of red, green and blue; None when
+    /// rendering in RGB. The rays it scatters into keep them.
+    pub wavelengths: Option<Wavelengths>,
+}
+
+impl Ray {
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn origin(&self) -> &Vec3 {
+        &self.a
+    }
+    pub fn direction(&self) -> &Vec3 {
+        &self.b
+    }
+    pub fn point_at_parameter(&self, t: f64) -> Vec3 {
+        self.a + (self.b * t)
+    }
+}
+
+/// Where a ray goes on from a surface, and what the light coming back
+/// along it is multiplied by.
+pub struct Scatter {
+    /// For a scatter with a `pdf`, the material's response to light from
+    /// the scattered direction, the BSDF times the cosine, still to be
+    /// divided by the density; otherwise the whole weight.
+    pub attenuation: Vec3,
+    pub scattered: Ray,
+    /// The density the direction was drawn from, for surfaces spreading
+    /// light over all directions, which lets the integrator weigh it
+    /// against other ways of choosing directions. None for specular
+    /// surfaces, whose directions are fixed, and ones that can't say how
+    /// likely a direction is.
+    pub pdf: Option<Box<dyn Pdf>>,
+}
+
+impl Scatter {
+    /// A scatter of `r` in a direction drawn from `pdf`, weighted by
+    /// `material`.
+    pub fn from_pdf(
+        pdf: Box<dyn Pdf>,
+        material: &dyn Material,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Scatter {
+        let direction = pdf.generate(sampler);
+        Scatter {
+            attenuation: material.eval(r, hit_record, &direction),
+            scattered: Ray {
+                a: hit_record.p,
+                b: direction,
+                time: r.time(),
+                wavelengths: r.wavelengths,
+            },
+            pdf: Some(pdf),
+        }
+    }
+
+    /// A scatter of `r` in a direction drawn from `material`'s own density,
+    /// or None if it has none.
+    pub fn sample(
+        material: &dyn Material,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        let pdf = material.pdf(r, hit_record)?;
+        Some(Scatter::from_pdf(pdf, material, r, hit_record, sampler))
+    }
+
+    /// What the light coming back along `scattered` is multiplied by.
+    pub fn weight(&self) -> Vec3 {
+        match &self.pdf {
+            Some(pdf) => {
+                let density = pdf.value(self.scattered.direction());
+                if density > 0. {
+                    self.attenuation / density
+                } else {
+                    Vec3(0., 0., 0.)
+                }
+            }
+            None => self.attenuation,
+        }
+    }
+}
+
+/// Materials are shared between objects and threads, so must be `Send` and
+/// `Sync`.
+pub trait Material: Send + Sync {
+    /// Where `r` goes on from `hit_record`, if anywhere, choosing with
+    /// the next dimensions of `sampler`.
+    fn scatter(
+        &self,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter>;
+
+    /// The density `scatter` draws directions from, for materials spreading
+    /// light over all directions; None for specular ones.
+    fn pdf(&self, _r: &Ray, _hit_record: &HitRecord) -> Option<Box<dyn Pdf>> {
+        None
+    }
+
+    /// How much of the light arriving from `direction` the surface sends
+    /// back along `r`: the BSDF times the cosine of the angle to the
+    /// normal. Black for specular materials, which only send light on in
+    /// the directions they choose.
+    fn eval(&self, _r: &Ray, _hit_record: &HitRecord, _direction: &Vec3) -> Vec3 {
+        Vec3(0., 0., 0.)
+    }
+
+    /// Radiance given off by the surface at surface coordinates (u, v) and
+    /// point `p`; black for anything that isn't a light.
+    fn emitted(&self, _u: f64, _v: f64, _p: &Vec3) -> Vec3 {
+        Vec3(0., 0., 0.)
+    }
+
+    /// `emitted` at each of `wavelengths`, for spectral rendering. Lights
+    /// given as a spectrum override this; the rest give off a spectrum of
+    /// their color.
+    fn emitted_spectral(&self, u: f64, v: f64, p: &Vec3, wavelengths: &Wavelengths) -> Vec3 {
+        wavelengths.from_rgb(self.emitted(u, v, p))
+    }
+
+    /// Opacity in [0, 1] at surface coordinates (u, v) and point `p`; rays
+    /// pass straight through the rest of the time, so cut-out shapes like
+    /// leaves need no geometry of their own.
+    fn alpha(&self, _u: f64, _v: f64, _p: &Vec3) -> f64 {
+        1.
+    }
+
+    /// Whether both sides of surfaces made of this material emit and scatter
+    /// light; if not, their backs are black.
+    fn two_sided(&self) -> bool {
+        true
+    }
+
+    /// Whether the surface mostly sends light on in a lobe about the mirror
+    /// direction, like metal and glass, rather than spreading it all over,
+    /// for counting a path's bounces by kind; see `MaxBounces`.
+    fn glossy(&self, _hit_record: &HitRecord) -> bool {
+        false
+    }
+
+    /// For a surface camera rays see only as the shadows falling on it and
+    /// the objects it mirrors, the share of the light it mirrors; None for
+    /// any other. See `color_alpha`.
+    fn shadow_catcher(&self) -> Option<f64> {
+        None
+    }
+
+    /// The medium filling the inside of closed objects made of this material,
+    /// for resolving nested and overlapping transparent objects.
+    fn medium(&self) -> Option<nested::Medium> {
+        None
+    }
+}
+
+pub struct Lambertian {
+    pub albedo: Box<dyn Texture>,
+}
+
+impl Material for Lambertian {
+    fn scatter(
+        &self,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        Scatter::sample(self, r, hit_record, sampler)
+    }
+
+    fn pdf(&self, _r: &Ray, hit_record: &HitRecord) -> Option<Box<dyn Pdf>> {
+        Some(Box::new(CosinePdf::new(&hit_record.normal)))
+    }
+
+    fn eval(&self, _r: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Vec3 {
+        hit_record.texture(&*self.albedo) * diffuse(hit_record, direction)
+    }
+}
+
+/// The BSDF of a white diffuse surface times the cosine for light from
+/// `direction`.
+fn diffuse(hit_record: &HitRecord, direction: &Vec3) -> f64 {
+    (direction.unit().dot(hit_record.normal) / std::f64::consts::PI).max(0.)
+}
+
+/// Oren-Nayar rough diffuse reflection, modeling the surface as V-shaped
+/// Lambertian microfacets. `roughness` is the standard deviation of the facet
+/// angle in radians; zero reduces to Lambertian.
+pub struct OrenNayar {
+    pub albedo: Box<dyn Texture>,
+    pub roughness: f64,
+}
+
+impl Material for OrenNayar {
+    fn scatter(
+        &self,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        Scatter::sample(self, r, hit_record, sampler)
+    }
+
+    fn pdf(&self, r: &Ray, hit_record: &HitRecord) -> Option<Box<dyn Pdf>> {
+        if r.direction().dot(hit_record.normal) >= 0. {
+            return None;
+        }
+        Some(Box::new(CosinePdf::new(&hit_record.normal)))
+    }
+
+    fn eval(&self, r: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Vec3 {
+        let frame = onb::Onb::from_w(&hit_record.normal);
+        let wo = frame.to_local(&-r.direction().unit());
+        let wi = frame.to_local(&direction.unit());
+        if wo.z() <= 0. || wi.z() <= 0. {
+            return Vec3(0., 0., 0.);
+        }
+
+        let sigma2 = self.roughness * self.roughness;
+        let a = 1. - 0.5 * sigma2 / (sigma2 + 0.33);
+        let b = 0.45 * sigma2 / (sigma2 + 0.09);
+        let sin_i = (1. - wi.z() * wi.z()).max(0.).sqrt();
+        let sin_o = (1. - wo.z() * wo.z()).max(0.).sqrt();
+        let max_cos = if sin_i > 1e-4 && sin_o > 1e-4 {
+            ((wi.x() * wo.x() + wi.y() * wo.y()) / (sin_i * sin_o)).max(0.)
+        } else {
+            0.
+        };
+        // sin(alpha) * tan(beta) with alpha the larger of the two angles
+        let (sin_alpha, tan_beta) = if wi.z() > wo.z() {
+            (sin_o, sin_i / wi.z())
+        } else {
+            (sin_i, sin_o / wo.z())
+        };
+        let albedo = hit_record.texture(&*self.albedo);
+        albedo * (a + b * max_cos * sin_alpha * tan_beta) * wi.z() / std::f64::consts::PI
+    }
+}
+
+pub struct Metal {
+    pub albedo: Box<dyn Texture>,
+    pub fuzz: Box<dyn Texture>,
+    pub film: Option<thin_film::ThinFilm>,
+}
+
+impl Metal {
+    /// The share of light reflected at `cos_i` to the normal.
+    fn reflectance(&self, hit_record: &HitRecord, cos_i: f64) -> Vec3 {
+        let albedo = hit_record.texture(&*self.albedo);
+        match self.film {
+            Some(film) => {
+                let wavelengths = hit_record
+                    .wavelengths
+                    .map_or(thin_film::WAVELENGTHS, |w| w.0);
+                film.reflectance(cos_i, thin_film::Substrate::Conductor(albedo), wavelengths)
+            }
+            None => albedo,
+        }
+    }
+
+    /// Where paths are regularized, the GGX surface they see the metal as,
+    /// with its fuzz as roughness.
+    fn regularized(&self, hit_record: &HitRecord) -> Option<Ggx> {
+        (hit_record.min_roughness > 0.)
+            .then(|| Ggx::from_roughness(hit_record.roughness(&*self.fuzz)))
+    }
+}
+
+impl Material for Metal {
+    fn scatter(
+        &self,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        if self.regularized(hit_record).is_some() {
+            return Scatter::sample(self, r, hit_record, sampler);
+        }
+        let reflected = r.direction().unit().reflect(&hit_record.normal);
+        let fuzz = hit_record.texture_scalar(&*self.fuzz);
+        let scattered = Ray {
+            a: hit_record.p,
+            b: reflected + 1.0_f64.min(fuzz) * sample_ball(sampler.get_2d(), sampler.get_1d()),
+            time: r.time(),
+            wavelengths: r.wavelengths,
+        };
+        if scattered.direction().dot(hit_record.normal) > 0. {
+            let cos_i = -r.direction().unit().dot(hit_record.normal);
+            Some(Scatter {
+                attenuation: self.reflectance(hit_record, cos_i),
+                scattered,
+                pdf: None,
+            })
+        } else {
+            None
+        }
+    }
+
+    fn pdf(&self, r: &Ray, hit_record: &HitRecord) -> Option<Box<dyn Pdf>> {
+        let ggx = self.regularized(hit_record)?;
+        microfacet::conductor_pdf(ggx, onb::Onb::from_w(&hit_record.normal), r)
+    }
+
+    fn eval(&self, r: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Vec3 {
+        match self.regularized(hit_record) {
+            Some(ggx) => {
+                let frame = onb::Onb::from_w(&hit_record.normal);
+                let fresnel = |c| self.reflectance(hit_record, c);
+                microfacet::conductor_eval(&ggx, fresnel, &frame, r, direction)
+            }
+            None => Vec3(0., 0., 0.),
+        }
+    }
+
+    fn glossy(&self, _hit_record: &HitRecord) -> bool {
+        true
+    }
+}
+
+pub struct Dielectric {
+    /// Index of refraction, at the sodium D line (589.3 nm) when dispersive.
+    pub ref_idx: f64,
+    /// Cauchy's B coefficient in square micrometres, how much faster the
+    /// index rises toward blue: about 0.004 for crown glass and 0.014 for
+    /// diamond. Zero disables dispersion.
+    pub dispersion: f64,
+    /// Optional coating on the outside of the surface.
+    pub film: Option<thin_film::ThinFilm>,
+    /// Where transparent objects overlap, the one with the highest priority
+    /// fills the overlap; see `nested`.
+    pub priority: u32,
+}
+
+impl Dielectric {
+    /// Index of refraction at `wavelength` nanometres, by Cauchy's equation.
+    pub fn ior_at(&self, wavelength: f64) -> f64 {
+        let inv_square = |nm: f64| 1e6 / (nm * nm);
+        self.ref_idx + self.dispersion * (inv_square(wavelength) - inv_square(589.3))
+    }
+
+    /// Where paths are regularized, the rough surface seen along `r`, at
+    /// the index of refraction without dispersion or film.
+    fn regularized(&self, r: &Ray, hit_record: &HitRecord) -> Option<DielectricPdf> {
+        if hit_record.min_roughness <= 0. {
+            return None;
+        }
+        let eta = self.ref_idx / hit_record.exterior_ior;
+        let frame = onb::Onb::from_w(&hit_record.normal);
+        let wo = frame.to_local(&-r.direction().unit());
+        if wo.z() <= 0. {
+            return None;
+        }
+        Some(DielectricPdf {
+            ggx: Ggx::from_roughness(hit_record.min_roughness),
+            frame,
+            wo,
+            eta: if hit_record.front_face { eta } else { 1. / eta },
+        })
+    }
+}
+
+/// Christophe Schlick's polynomial approximation of the Fresnel reflectance.
+fn schlick(cosine: f64, ref_idx: f64) -> f64 {
+    let r0 = (1. - ref_idx) / (1. + ref_idx);
+    let r0 = r0 * r0;
+    r0 + (1. - r0) * (1. - cosine).powi(5)
+}
+
+impl Material for Dielectric {
+    fn scatter(
+        &self,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        if self.regularized(r, hit_record).is_some() {
+            return Scatter::sample(self, r, hit_record, sampler);
+        }
+        // A dispersive surface bends each color differently, so follows a
+        // single randomly chosen channel with its own index, weighting it by
+        // three to make up for the other two. Each surface chooses afresh,
+        // which is unbiased but noisier than keeping one channel per path.
+        // Spectral paths go on with their hero wavelength alone instead.
+        let (ref_idx, mask, wavelengths) = match r.wavelengths {
+            _ if self.dispersion == 0. => (self.ref_idx, Vec3(1., 1., 1.), r.wavelengths),
+            Some(wavelengths) => {
+                let (hero, mask) = wavelengths.hero_only();
+                (self.ior_at(hero.hero()), mask, Some(hero))
+            }
+            None => {
+                let channel = ((sampler.get_1d() * 3.) as usize).min(2);
+                let masks = [Vec3(3., 0., 0.), Vec3(0., 3., 0.), Vec3(0., 0., 3.)];
+                (
+                    self.ior_at(thin_film::WAVELENGTHS[channel]),
+                    masks[channel],
+                    None,
+                )
+            }
+        };
+        // relative to whatever the surface borders on, air unless nested
+        let eta = ref_idx / hit_record.exterior_ior;
+        let d = *r.direction();
+        let n = hit_record.normal;
+        let cos_i = -d.dot(n) / d.length();
+        let (ni_over_nt, cosine) = if hit_record.front_face {
+            (1.0 / eta, cos_i)
+        } else {
+            // leaving the surface: Schlick wants the angle on the outside
+            let cosine = (1. - eta * eta * (1. - cos_i * cos_i)).max(0.).sqrt();
+            (eta, cosine)
+        };
+        let reflectance = match self.film {
+            Some(film) if hit_record.front_face => {
+                let wavelengths = wavelengths.map_or(thin_film::WAVELENGTHS, |w| w.0);
+                film.reflectance(
+                    cosine,
+                    thin_film::Substrate::Dielectric(ref_idx),
+                    wavelengths,
+                )
+            }
+            _ => schlick(cosine, eta) * Vec3(1., 1., 1.),
+        };
+        // Reflect with probability given by the average Fresnel term, and
+        // always on total internal reflection. Glass absorbs nothing, so the
+        // attenuation only corrects for a reflectance that varies by channel.
+        let reflect_prob = (reflectance.r() + reflectance.g() + reflectance.b()) / 3.;
+        let (direction, attenuation) = match d.refract(&n, ni_over_nt) {
+            Some(refracted) if sampler.get_1d() >= reflect_prob => (
+                refracted,
+                (Vec3(1., 1., 1.) - reflectance) / (1. - reflect_prob),
+            ),
+            Some(_) => (d.reflect(&n), reflectance / reflect_prob),
+            None => (d.reflect(&n), Vec3(1., 1., 1.)),
+        };
+        Some(Scatter {
+            attenuation: attenuation * mask,
+            scattered: Ray {
+                a: hit_record.p,
+                b: direction,
+                time: r.time(),
+                wavelengths,
+            },
+            pdf: None,
+        })
+    }
+
+    fn pdf(&self, r: &Ray, hit_record: &HitRecord) -> Option<Box<dyn Pdf>> {
+        Some(Box::new(self.regularized(r, hit_record)?))
+    }
+
+    fn eval(&self, r: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Vec3 {
+        match self.regularized(r, hit_record) {
+            Some(rough) => rough.eval(direction) * Vec3(1., 1., 1.),
+            None => Vec3(0., 0., 0.),
+        }
+    }
+
+    fn medium(&self) -> Option<nested::Medium> {
+        Some(nested::Medium {
+            ior: self.ref_idx,
+            priority: self.priority,
+        })
+    }
+
+    fn glossy(&self, _hit_record: &HitRecord) -> bool {
+        true
+    }
+}
+
+/// An emitter: absorbs every incoming ray and gives off `emit` radiance.
+pub struct DiffuseLight {
+    pub emit: Box<dyn Texture>,
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _r: &Ray,
+        _hit_record: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        None
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Vec3) -> Vec3 {
+        self.emit.value(u, v, p)
+    }
+
+    fn emitted_spectral(&self, u: f64, v: f64, p: &Vec3, wavelengths: &Wavelengths) -> Vec3 {
+        match self.emit.spectrum() {
+            Some(spectrum) => wavelengths.of(spectrum),
+            None => wavelengths.from_rgb(self.emit.value(u, v, p)),
+        }
+    }
+}
+
+/// Stands in for the ground of a photographic backplate: camera rays only
+/// record the shadows falling on it and the objects it mirrors (see
+/// `color_alpha`), while to everything else it is a diffuse surface of color
+/// `albedo`, so objects still pick up its bounce light and reflections.
+pub struct ShadowCatcher {
+    pub albedo: Box<dyn Texture>,
+    /// The share of the light the ground mirrors, for a polished or wet
+    /// floor, or 0 to record only shadows.
+    pub reflectance: f64,
+}
+
+impl Material for ShadowCatcher {
+    fn scatter(
+        &self,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        Scatter::sample(self, r, hit_record, sampler)
+    }
+
+    fn pdf(&self, _r: &Ray, hit_record: &HitRecord) -> Option<Box<dyn Pdf>> {
+        Some(Box::new(CosinePdf::new(&hit_record.normal)))
+    }
+
+    fn eval(&self, _r: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Vec3 {
+        hit_record.texture(&*self.albedo) * diffuse(hit_record, direction)
+    }
+
+    fn shadow_catcher(&self) -> Option<f64> {
+        Some(self.reflectance)
+    }
+}
+
+/// Blends two materials: each scatter picks `b` with probability `weight`
+/// and `a` otherwise, which on average gives the weighted mix of the two.
+/// A textured weight works as a mask, e.g. for lit windows on a facade.
+/// Two materials with densities mix into one with the mixture of them.
+pub struct MixMaterial {
+    pub a: Box<dyn Material>,
+    pub b: Box<dyn Material>,
+    pub weight: Box<dyn Texture>,
+}
+
+impl Material for MixMaterial {
+    fn scatter(
+        &self,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        if let Some(pdf) = self.pdf(r, hit_record) {
+            return Some(Scatter::from_pdf(pdf, self, r, hit_record, sampler));
+        }
+        let weight = hit_record.texture_scalar(&*self.weight);
+        if sampler.get_1d() < weight {
+            self.b.scatter(r, hit_record, sampler)
+        } else {
+            self.a.scatter(r, hit_record, sampler)
+        }
+    }
+
+    fn pdf(&self, r: &Ray, hit_record: &HitRecord) -> Option<Box<dyn Pdf>> {
+        Some(Box::new(MixturePdf {
+            a: self.a.pdf(r, hit_record)?,
+            b: self.b.pdf(r, hit_record)?,
+            weight: hit_record.texture_scalar(&*self.weight),
+        }))
+    }
+
+    fn eval(&self, r: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Vec3 {
+        let weight = hit_record.texture_scalar(&*self.weight);
+        (1. - weight) * self.a.eval(r, hit_record, direction)
+            + weight * self.b.eval(r, hit_record, direction)
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Vec3) -> Vec3 {
+        let weight = self.weight.scalar(u, v, p);
+        (1. - weight) * self.a.emitted(u, v, p) + weight * self.b.emitted(u, v, p)
+    }
+
+    fn emitted_spectral(&self, u: f64, v: f64, p: &Vec3, wavelengths: &Wavelengths) -> Vec3 {
+        let weight = self.weight.scalar(u, v, p);
+        (1. - weight) * self.a.emitted_spectral(u, v, p, wavelengths)
+            + weight * self.b.emitted_spectral(u, v, p, wavelengths)
+    }
+
+    fn alpha(&self, u: f64, v: f64, p: &Vec3) -> f64 {
+        let weight = self.weight.scalar(u, v, p);
+        (1. - weight) * self.a.alpha(u, v, p) + weight * self.b.alpha(u, v, p)
+    }
+
+    fn glossy(&self, hit_record: &HitRecord) -> bool {
+        let weight = hit_record.texture_scalar(&*self.weight);
+        if weight < 0.5 {
+            self.a.glossy(hit_record)
+        } else {
+            self.b.glossy(hit_record)
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct HitRecord<'a> {
+    pub t: f64,
+    pub p: Vec3,
+    /// Unit shading normal, always facing against the incoming ray.
+    pub normal: Vec3,
+    /// Normal of the underlying geometry, on the same side as `normal`. The
+    /// two only differ where a material bends the shading normal.
+    pub geometric_normal: Vec3,
+    /// Whether the ray hit the outside of the surface, i.e. the side the
+    /// geometry's outward normal points to.
+    pub front_face: bool,
+    /// Direction across the surface that anisotropic materials align to; on
+    /// hair fibers it runs along the strand.
+    pub tangent: Vec3,
+    /// Surface coordinates, each in [0, 1], that textures are looked up by.
+    /// Every primitive fills these in: spheres use longitude and latitude,
+    /// fibers the angle around and distance along the strand, and meshes
+    /// interpolate their per-vertex coordinates.
+    pub u: f64,
+    pub v: f64,
+    /// Rough width of the ray where it hits, in units of u, for filtering
+    /// textures; see `PIXEL_SPREAD`.
+    pub uv_width: f64,
+    /// Index of refraction of the medium the surface borders on, 1 for air.
+    /// The integrator sets it where transparent objects are nested.
+    pub exterior_ior: f64,
+    /// Least roughness glossy and specular materials take on here: zero,
+    /// unless the integrator raises it on surfaces a path reaches after
+    /// spreading out, to regularize it; see `PathTracer::regularize`.
+    pub min_roughness: f64,
+    /// The wavelengths of the ray that hit, which colors looked up here are
+    /// values at in spectral rendering.
+    pub wavelengths: Option<Wavelengths>,
+    pub material: &'a dyn Material,
+}
+
+/// Angle a ray is taken to widen by per unit of distance when estimating its
+/// footprint on a surface for texture filtering: about one pixel of the demo
+/// camera. Footprints only account for the last segment of a path, so they
+/// underestimate the blur seen through reflections.
+pub const PIXEL_SPREAD: f64 = 0.01;
+
+/// Orient a primitive's outward normal against the ray, returning it along
+/// with whether the ray hit the front face.
+pub fn face_normal(r: &Ray, outward_normal: Vec3) -> (Vec3, bool) {
+    if r.direction().dot(outward_normal) < 0. {
+        (outward_normal, true)
+    } else {
+        (-outward_normal, false)
+    }
+}
+
+impl HitRecord<'_> {
+    /// Local shading frame with the tangent along x and the normal along z.
+    pub fn frame(&self) -> onb::Onb {
+        onb::Onb::from_normal_tangent(&self.normal, &self.tangent)
+    }
+
+    /// Whether `direction` is on the same side of the geometric surface as it
+    /// is of the shading surface. Paths where the two disagree would leak
+    /// light through the surface, so the integrator drops them.
+    pub fn consistent(&self, direction: &Vec3) -> bool {
+        direction.dot(self.normal) * direction.dot(self.geometric_normal) > 0.
+    }
+
+    /// Look up a color texture at this hit, filtered over the ray's
+    /// footprint, at the ray's wavelengths if it has any.
+    pub fn texture(&self, texture: &dyn Texture) -> Vec3 {
+        match (&self.wavelengths, texture.spectrum()) {
+            (None, _) => texture.at_hit(self),
+            (Some(wavelengths), Some(spectrum)) => wavelengths.of(spectrum),
+            (Some(wavelengths), None) => wavelengths.from_rgb(texture.at_hit(self)),
+        }
+    }
+
+    /// Look up a scalar texture at this hit, filtered as `texture` does.
+    pub fn texture_scalar(&self, texture: &dyn Texture) -> f64 {
+        texture.at_hit(self).luminance()
+    }
+
+    /// Look up a roughness texture at this hit, at least `min_roughness`.
+    pub fn roughness(&self, texture: &dyn Texture) -> f64 {
+        self.texture_scalar(texture).max(self.min_roughness)
+    }
+
+    /// Radiance the material gives off here, at the ray's wavelengths if it
+    /// has any.
+    pub fn emitted(&self) -> Vec3 {
+        match &self.wavelengths {
+            Some(wavelengths) => {
+                self.material
+                    .emitted_spectral(self.u, self.v, &self.p, wavelengths)
+            }
+            None => self.material.emitted(self.u, self.v, &self.p),
+        }
+    }
+
+    /// Whether the ray stops here, rather than passing through a transparent
+    /// part of the material. Primitives check this before reporting a hit, so
+    /// partial opacity is decided at random with the material's alpha, by a
+    /// number hashed from the point so it comes out the same every render.
+    pub fn opaque(&self) -> bool {
+        let alpha = self.material.alpha(self.u, self.v, &self.p);
+        alpha >= 1. || random_at(&self.p) < alpha
+    }
+}
+
+pub trait Hittable: Send + Sync {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>>;
+
+    /// A box the object lies entirely within, or None if it is unbounded.
+    fn bounding_box(&self) -> Option<Aabb>;
+
+    /// A box around the part of the object inside `clip`, or None if none
+    /// of it is. Shapes that can cheaply do better than the overlap of their
+    /// bounding box with `clip` override this, so BVHs split across them
+    /// wrap each side tightly.
+    fn clipped_box(&self, clip: &Aabb) -> Option<Aabb> {
+        Aabb::intersection(&self.bounding_box()?, clip)
+    }
+
+    /// `hit` for each of a batch of rays. Accelerators override this to
+    /// share the work of walking their structure between rays that go the
+    /// same way, like the samples of one pixel.
+    fn hit_packet(&self, rays: &[Ray], t_min: f64, t_max: f64) -> Vec<Option<HitRecord<'_>>> {
+        rays.iter().map(|r| self.hit(r, t_min, t_max)).collect()
+    }
+
+    /// For objects that can be sampled as lights, the density, per unit
+    /// solid angle seen from `origin`, with which `random` picks
+    /// `direction`; zero if it misses the object.
+    fn pdf_value(&self, _origin: &Vec3, _direction: &Vec3) -> f64 {
+        0.
+    }
+
+    /// A direction from `origin` toward a point picked on the object with
+    /// the next dimensions of `sampler`.
+    fn random(&self, _origin: &Vec3, _sampler: &mut dyn Sampler) -> Vec3 {
+        Vec3(1., 0., 0.)
+    }
+
+    /// For objects that can be sampled as lights, a point picked evenly
+    /// over their surface, seen from the front, with the density per unit
+    /// area it was picked with; light paths start from these.
+    fn sample_surface(&self, _sampler: &mut dyn Sampler) -> Option<(HitRecord<'_>, f64)> {
+        None
+    }
+
+    /// For objects that can be sampled as lights, the density per unit
+    /// area with which `sample_surface` picks the point where the ray from
+    /// `origin` along `direction` first meets the object; zero if it misses
+    /// the object.
+    fn surface_pdf(&self, _origin: &Vec3, _direction: &Vec3) -> f64 {
+        0.
+    }
+}
+
+/// Shared objects, like the ones several instances place, are objects too.
+impl<T: Hittable + ?Sized> Hittable for Arc<T> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        (**self).hit(r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        (**self).bounding_box()
+    }
+
+    fn clipped_box(&self, clip: &Aabb) -> Option<Aabb> {
+        (**self).clipped_box(clip)
+    }
+
+    fn hit_packet(&self, rays: &[Ray], t_min: f64, t_max: f64) -> Vec<Option<HitRecord<'_>>> {
+        (**self).hit_packet(rays, t_min, t_max)
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        (**self).pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        (**self).random(origin, sampler)
+    }
+
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(HitRecord<'_>, f64)> {
+        (**self).sample_surface(sampler)
+    }
+
+    fn surface_pdf(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        (**self).surface_pdf(origin, direction)
+    }
+}
+
+pub struct Sphere {
+    pub center: Vec3,
+    pub radius: f64,
+    pub material: Arc<dyn Material>,
+}
+
+impl Hittable for Sphere {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let oc = *r.origin() - self.center;
+        let a = r.direction().dot(*r.direction());
+        let b = 2.0 * oc.dot(*r.direction());
+        let c = oc.dot(oc) - self.radius * self.radius;
+        let discriminant = b * b - 4. * a * c;
+        if discriminant <= 0. {
+            return None;
+        }
+        let sol_pos = (-b + discriminant.sqrt()) / (2.0 * a);
+        let sol_neg = (-b - discriminant.sqrt()) / (2.0 * a);
+        // the far side shows through where the near one is cut out
+        [sol_neg, sol_pos]
+            .iter()
+            .filter(|&&t| t > t_min && t < t_max)
+            .map(|&t| self.hit_record(r, t))
+            .find(HitRecord::opaque)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let r = self.radius.abs() * Vec3(1., 1., 1.);
+        Some(Aabb {
+            min: self.center - r,
+            max: self.center + r,
+        })
+    }
+
+    /// Directions are picked evenly within the cone the sphere fills, seen
+    /// from outside it.
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let ray = Ray {
+            a: *origin,
+            b: *direction,
+            time: 0.,
+            wavelengths: None,
+        };
+        let distance_squared = (self.center - *origin).squared_length();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared || self.hit(&ray, 0.0001, f64::MAX).is_none() {
+            return 0.;
+        }
+        let cos_theta_max = (1. - radius_squared / distance_squared).sqrt();
+        1. / (2. * std::f64::consts::PI * (1. - cos_theta_max))
+    }
+
+    fn random(&self, origin: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let to_center = self.center - *origin;
+        let distance_squared = to_center.squared_length();
+        let cos_theta_max = (1. - self.radius * self.radius / distance_squared)
+            .max(0.)
+            .sqrt();
+        let (u, v) = sampler.get_2d();
+        let z = 1. + v * (cos_theta_max - 1.);
+        let r = (1. - z * z).max(0.).sqrt();
+        let phi = 2. * std::f64::consts::PI * u;
+        onb::Onb::from_w(&to_center).local(&Vec3(r * phi.cos(), r * phi.sin(), z))
+    }
+
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(HitRecord<'_>, f64)> {
+        let (u, v) = sampler.get_2d();
+        let z = 1. - 2. * u;
+        let r = (1. - z * z).max(0.).sqrt();
+        let phi = 2. * std::f64::consts::PI * v;
+        let outward = Vec3(r * phi.cos(), r * phi.sin(), z);
+        // met from just outside
+        let p = self.center + self.radius * outward;
+        let ray = Ray {
+            a: p + outward,
+            b: -outward,
+            time: 0.,
+            wavelengths: None,
+        };
+        let area = 4. * std::f64::consts::PI * self.radius * self.radius;
+        Some((self.hit_record(&ray, 1.), 1. / area))
+    }
+
+    fn surface_pdf(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let ray = Ray {
+            a: *origin,
+            b: *direction,
+            time: 0.,
+            wavelengths: None,
+        };
+        match self.hit(&ray, 0.0001, f64::MAX) {
+            Some(_) => 1. / (4. * std::f64::consts::PI * self.radius * self.radius),
+            None => 0.,
+        }
+    }
+}
+
+impl Sphere {
+    fn hit_record(&self, r: &Ray, t: f64) -> HitRecord<'_> {
+        let p = r.point_at_parameter(t);
+        let local = p - self.center;
+        let (normal, front_face) = face_normal(r, local / self.radius);
+        let (u, v) = sphere_uv(&(local / self.radius));
+        HitRecord {
+            t,
+            p,
+            normal,
+            geometric_normal: normal,
+            front_face,
+            // direction of increasing u, along lines of latitude
+            tangent: Vec3(local.z(), 0., -local.x()),
+            u,
+            v,
+            // one unit of u runs once around the equator
+            uv_width: t * r.direction().length() * PIXEL_SPREAD
+                / (2. * std::f64::consts::PI * self.radius.abs()),
+            exterior_ior: 1.,
+            min_roughness: 0.,
+            wavelengths: r.wavelengths,
+            material: &*self.material,
+        }
+    }
+}
+
+/// Longitude/latitude coordinates of a point on the unit sphere: u wraps
+/// around the y axis starting from -x, v runs from the bottom pole to the top.
+pub fn sphere_uv(p: &Vec3) -> (f64, f64) {
+    let theta = (-p.y()).clamp(-1., 1.).acos();
+    let phi = (-p.z()).atan2(p.x()) + std::f64::consts::PI;
+    (
+        phi / (2. * std::f64::consts::PI),
+        theta / std::f64::consts::PI,
+    )
+}
+
+#[derive(Default)]
+pub struct World {
+    pub hittables: Vec<Box<dyn Hittable>>,
+}
+
+impl World {
+    /// The same objects with the bounded ones gathered into a BVH, leaving
+    /// just it and any unbounded objects to test one by one.
+    pub fn into_bvh(self) -> World {
+        self.accelerated(|objects| Box::new(bvh::Bvh::new(objects)))
+    }
+
+    /// Like `into_bvh`, with a BVH that may also split space through big
+    /// objects; see `Bvh::with_spatial_splits`.
+    pub fn into_spatial_bvh(self) -> World {
+        self.accelerated(|objects| Box::new(bvh::Bvh::with_spatial_splits(objects)))
+    }
+
+    /// Like `into_bvh`, with a kd-tree instead.
+    pub fn into_kd_tree(self) -> World {
+        self.accelerated(|objects| Box::new(kdtree::KdTree::new(objects)))
+    }
+
+    /// Like `into_bvh`, with a uniform grid instead.
+    pub fn into_grid(self) -> World {
+        self.accelerated(|objects| Box::new(grid::Grid::new(objects)))
+    }
+
+    /// For a world of lights, the same lights with the bounded ones
+    /// gathered into a `LightTree`, so they are sampled by how much light
+    /// each would bring rather than evenly.
+    pub fn into_light_tree(self) -> World {
+        self.accelerated(|objects| Box::new(light_tree::LightTree::new(objects)))
+    }
+
+    fn accelerated(self, build: impl FnOnce(Vec<Box<dyn Hittable>>) -> Box<dyn Hittable>) -> World {
+        let (bounded, mut hittables): (Vec<_>, Vec<_>) = self
+            .hittables
+            .into_iter()
+            .partition(|h| h.bounding_box().is_some());
+        if !bounded.is_empty() {
+            hittables.push(build(bounded));
+        }
+        World { hittables }
+    }
+}
+
+impl Hittable for World {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.hittables
+            .iter()
+            .filter_map(|h| h.hit(r, t_min, t_max))
+            .min_by_key(|r| ordered_float::OrderedFloat(r.t))
+    }
+
+    fn hit_packet(&self, rays: &[Ray], t_min: f64, t_max: f64) -> Vec<Option<HitRecord<'_>>> {
+        let mut closest: Vec<Option<HitRecord>> = rays.iter().map(|_| None).collect();
+        for h in &self.hittables {
+            for (c, hit_record) in closest.iter_mut().zip(h.hit_packet(rays, t_min, t_max)) {
+                if let Some(hit_record) = hit_record {
+                    if c.is_none_or(|c| hit_record.t < c.t) {
+                        *c = Some(hit_record);
+                    }
+                }
+            }
+        }
+        closest
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let mut boxes = self.hittables.iter().map(|h| h.bounding_box());
+        let first = boxes.next()??;
+        boxes.try_fold(first, |sum, b| Some(Aabb::surrounding(&sum, &b?)))
+    }
+
+    /// Sampled as lights, the objects are picked between evenly.
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let total: f64 = self
+            .hittables
+            .iter()
+            .map(|h| h.pdf_value(origin, direction))
+            .sum();
+        total / self.hittables.len().max(1) as f64
+    }
+
+    fn random(&self, origin: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let n = self.hittables.len();
+        if n == 0 {
+            return Vec3(1., 0., 0.);
+        }
+        let i = ((sampler.get_1d() * n as f64) as usize).min(n - 1);
+        self.hittables[i].random(origin, sampler)
+    }
+
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(HitRecord<'_>, f64)> {
+        let n = self.hittables.len();
+        if n == 0 {
+            return None;
+        }
+        let i = ((sampler.get_1d() * n as f64) as usize).min(n - 1);
+        let (hit_record, density) = self.hittables[i].sample_surface(sampler)?;
+        Some((hit_record, density / n as f64))
+    }
+
+    /// The point is on whichever object the ray meets first.
+    fn surface_pdf(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let ray = Ray {
+            a: *origin,
+            b: *direction,
+            time: 0.,
+            wavelengths: None,
+        };
+        let first = self
+            .hittables
+            .iter()
+            .filter_map(|h| Some((h.hit(&ray, 0.0001, f64::MAX)?.t, h)))
+            .min_by_key(|(t, _)| ordered_float::OrderedFloat(*t));
+        match first {
+            Some((_, h)) => h.surface_pdf(origin, direction) / self.hittables.len() as f64,
+            None => 0.,
+        }
+    }
+}
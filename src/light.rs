@@ -0,0 +1,136 @@
+use crate::ies::IesProfile;
+use crate::onb::Onb;
+use crate::texture::Texture;
+use crate::vec3::Vec3;
+
+/// A light with no size, which paths can never hit, only find by looking
+/// for it from each surface they reach. Modelling one as a tiny bright
+/// sphere instead would make both ways of finding it noisy.
+pub trait Light: Send + Sync {
+    /// The light reaching `p`, if any.
+    fn illuminate(&self, p: &Vec3) -> Option<Illumination>;
+}
+
+/// Light arriving at a point from a `Light`.
+#[derive(Debug, Copy, Clone)]
+pub struct Illumination {
+    /// From the point to the light; its length is the distance to it, but
+    /// for lights infinitely far away, for which it is a unit vector.
+    pub direction: Vec3,
+    /// Whether the light is infinitely far away.
+    pub distant: bool,
+    /// The irradiance on a surface facing the light.
+    pub irradiance: Vec3,
+}
+
+/// Light given off evenly in all directions from `position`, with
+/// `intensity` watts per steradian, so it falls off with the square of
+/// the distance.
+pub struct PointLight {
+    pub position: Vec3,
+    pub intensity: Vec3,
+    /// A measured fixture the light shines like instead, hanging with its
+    /// nadir straight down and horizontal angles round from +x towards +z:
+    /// `intensity` in its brightest direction and scaled by the profile in
+    /// the others.
+    pub profile: Option<IesProfile>,
+}
+
+impl Light for PointLight {
+    fn illuminate(&self, p: &Vec3) -> Option<Illumination> {
+        let direction = self.position - *p;
+        let distance_squared = direction.squared_length();
+        if distance_squared == 0. {
+            return None;
+        }
+        let shape = match &self.profile {
+            Some(profile) => {
+                let d = -direction.unit();
+                profile.value(&Vec3(d.x(), d.z(), -d.y()))
+            }
+            None => 1.,
+        };
+        Some(Illumination {
+            direction,
+            distant: false,
+            irradiance: shape * self.intensity / distance_squared,
+        })
+    }
+}
+
+/// Light arriving along parallel rays from far away, like the sun's:
+/// `irradiance` on surfaces facing back along `direction`, wherever they
+/// are.
+pub struct DirectionalLight {
+    /// The way the light travels.
+    pub direction: Vec3,
+    pub irradiance: Vec3,
+}
+
+impl Light for DirectionalLight {
+    fn illuminate(&self, _p: &Vec3) -> Option<Illumination> {
+        Some(Illumination {
+            direction: -self.direction.unit(),
+            distant: true,
+            irradiance: self.irradiance,
+        })
+    }
+}
+
+/// Light shone from `position` along `direction` in a cone, like a stage
+/// light's: `intensity` watts per steradian within `inner` radians of its
+/// axis, fading smoothly to nothing at `outer`, and falling off with the
+/// square of the distance.
+pub struct SpotLight {
+    pub position: Vec3,
+    pub direction: Vec3,
+    pub intensity: Vec3,
+    pub inner: f64,
+    pub outer: f64,
+    /// A pattern the light is shone through, which it casts like a slide
+    /// in a projector: the color it is multiplied by where the cone meets
+    /// a plane across it, with (u, v) running from 0 to 1 across the
+    /// square around the outer cone.
+    pub gobo: Option<Box<dyn Texture>>,
+    /// A measured fixture the light shines like within its cone, its nadir
+    /// along `direction`: `intensity` in its brightest direction and scaled
+    /// by the profile in the others. Give the cone a wide `outer` angle for
+    /// the fixture's own beam alone.
+    pub profile: Option<IesProfile>,
+}
+
+impl Light for SpotLight {
+    fn illuminate(&self, p: &Vec3) -> Option<Illumination> {
+        let to_point = *p - self.position;
+        let distance_squared = to_point.squared_length();
+        if distance_squared == 0. {
+            return None;
+        }
+        let frame = Onb::from_w(&self.direction);
+        let local = frame.to_local(&to_point.unit());
+        let (cos_inner, cos_outer) = (self.inner.cos(), self.outer.cos());
+        if local.z() <= cos_outer {
+            return None;
+        }
+        let t = ((local.z() - cos_outer) / (cos_inner - cos_outer)).min(1.);
+        let falloff = t * t * (3. - 2. * t);
+        let pattern = match &self.gobo {
+            Some(gobo) => {
+                let size = self.outer.tan();
+                let u = 0.5 * (local.x() / local.z() / size + 1.);
+                let v = 0.5 * (local.y() / local.z() / size + 1.);
+                gobo.value(u, v, p)
+            }
+            None => Vec3(1., 1., 1.),
+        };
+        let shape = match &self.profile {
+            Some(profile) => profile.value(&local),
+            None => 1.,
+        };
+        Some(Illumination {
+            direction: -to_point,
+            distant: false,
+            irradiance: falloff * shape * pattern * self.intensity / distance_squared,
+        })
+    }
+}
@@ -1,279 +1,432 @@
-mod vec3;
-
 use std::fs::File;
 use std::io::BufWriter;
 use std::io::Write;
 use std::path::Path;
 // To use encoder.set()
 use chrono::Utc;
-use ordered_float;
 use png::HasParameters;
 
-use crate::vec3::Vec3;
-
-#[derive(Debug, Copy, Clone)]
-pub struct Ray {
-    pub a: Vec3,
-    pub b: Vec3,
-}
+use path_tracer::bidirectional::Bidirectional;
+use path_tracer::camera::{Camera, Equirectangular, Perspective};
+use path_tracer::environment::{Environment, EnvironmentMap};
+use path_tracer::film::{Film, Filter};
+use path_tracer::guiding::Guide;
+use path_tracer::integrator::{AmbientOcclusion, Integrator, MaxBounces, PathTracer};
+use path_tracer::metropolis::Metropolis;
+use path_tracer::restir::Restir;
+use path_tracer::sampler::{Sampler, SamplerKind};
+use path_tracer::scenes;
+use path_tracer::spectrum::Wavelengths;
+use path_tracer::vec3::Vec3;
+use path_tracer::Hittable;
 
-impl Ray {
-    fn origin(&self) -> &Vec3 {
-        &self.a
+fn main() {
+    let nx = 400;
+    let ny = 200;
+    let sampling = Sampling {
+        // each pixel is sampled in rounds of a random point in each cell of
+        // a grid of strata by strata cells
+        strata: 4,
+        // until the brightness it has come to is within this fraction of
+        // the true one, 95% of the time
+        tolerance: 0.2,
+        // or there have been this many rounds
+        max_rounds: 8,
+        // where the numbers paths are made from come from: Random, Sobol,
+        // Halton, or BlueNoise for the least distracting noise at low
+        // sample counts
+        sampler: SamplerKind::Sobol,
+        // and which numbers they are: the same seed renders the same image
+        seed: 0,
+        // how samples count towards the pixels around them: Box, Tent,
+        // Gaussian or Mitchell, from sharpest to smoothest edges
+        filter: Filter::Gaussian { sigma: 0.5 },
+        // whether paths carry a few wavelengths of light each rather than
+        // red, green and blue, for glass splitting light into rainbows and
+        // lights and materials given as spectra
+        spectral: false,
+        // whether the background comes out transparent, for compositing
+        // the render over other images; it still lights the scene
+        transparent: false,
+    };
+    // follow paths out from the lights too, for scenes lit mostly
+    // indirectly or through glass
+    let bidirectional = false;
+    // or learn where light comes from over passes of 1, 2, 4... samples a
+    // pixel first, then send paths that way, for light that arrives
+    // indirectly
+    let guiding = false;
+    let guiding_passes = 4;
+    // or render with chains of paths that explore around the ones bringing
+    // light, for light that is hard to find, like through a gap
+    let metropolis = false;
+    // or render just the light straight from the lights, each pixel
+    // picking which to sample by resampling many picks, its own and its
+    // neighbours', for scenes with many lights
+    let restir = false;
+    // pick which light to sample by how much light each would bring, from
+    // a tree of them, rather than evenly, for scenes with many lights
+    let light_tree = true;
+    // or just how hidden each surface is from the sky, for a quick preview,
+    // with things further away than this not counting
+    let ambient_occlusion = false;
+    let occlusion_distance = f64::MAX;
+    // for stereo modes: the distance between the eyes, and how far away
+    // their lines of sight meet, in scene units
+    let ipd = 0.064;
+    let convergence = 10.;
+    // light the scene with something else in place of its own sky: a
+    // Solid color, a Gradient between two, or a Procedural function of the
+    // direction
+    let background: Option<Box<dyn Environment>> = None;
+    // or an equirectangular HDR or EXR panorama, its values scaled by this
+    // much
+    let environment_map: Option<&str> = None;
+    let environment_scale = 1.;
+    let name = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| scenes::NAMES[0].to_string());
+    let mut scene = match scenes::by_name(&name, nx as f64 / ny as f64) {
+        Some(scene) => scene,
+        None => {
+            eprintln!(
+                "unknown scene {}, expected one of: {}",
+                name,
+                scenes::NAMES.join(", ")
+            );
+            std::process::exit(1);
+        }
+    };
+    // an optional second argument overrides the scene's accelerator
+    match std::env::args().nth(2).as_deref() {
+        Some("none") => scene.accelerator = scenes::Accelerator::None,
+        Some("bvh") => scene.accelerator = scenes::Accelerator::Bvh,
+        Some("sbvh") => scene.accelerator = scenes::Accelerator::SpatialBvh,
+        Some("kdtree") => scene.accelerator = scenes::Accelerator::KdTree,
+        Some("grid") => scene.accelerator = scenes::Accelerator::Grid,
+        Some(other) => {
+            eprintln!(
+                "unknown accelerator {}, expected none, bvh, sbvh, kdtree or grid",
+                other
+            );
+            std::process::exit(1);
+        }
+        None => {}
     }
-    fn direction(&self) -> &Vec3 {
-        &self.b
+    if let Some(background) = background {
+        scene.environment = background;
     }
-    fn point_at_parameter(&self, t: f64) -> Vec3 {
-        self.a + (self.b * t)
+    if let Some(path) = environment_map {
+        match EnvironmentMap::load(path, environment_scale) {
+            Ok(map) => scene.environment = Box::new(map),
+            Err(e) => {
+                eprintln!("couldn't load environment map {}: {}", path, e);
+                std::process::exit(1);
+            }
+        }
     }
-}
-
-#[derive(Debug, Copy, Clone)]
-pub struct Scatter {
-    pub attenuation: Vec3,
-    pub scattered: Ray,
-}
-
-pub trait Material {
-    fn scatter(&self, r: &Ray, hit_record: &HitRecord) -> Option<Scatter>;
-}
-
-pub struct Lambertian {
-    albedo: Vec3,
-}
-
-impl Material for Lambertian {
-    fn scatter(&self, _r: &Ray, hit_record: &HitRecord) -> Option<Scatter> {
-        // bounce in a random new direction
-        // TODO: try out suggestion in book, "Note we could just as well only
-        // scatter with some probability p and have attenuation be albedo/p.
-        // Your choice."
-        let target = hit_record.p + hit_record.normal + random_in_unit_sphere();
-        Some(Scatter {
-            attenuation: self.albedo,
-            scattered: Ray {
-                a: hit_record.p,
-                b: target - hit_record.p,
-            },
-        })
+    let mode = std::env::args().nth(3);
+    let modes = ["cubemap", "cross", "stereo", "stereo360"];
+    if mode.as_deref().is_some_and(|m| !modes.contains(&m)) {
+        eprintln!(
+            "unknown mode {}, expected one of: {}",
+            mode.unwrap(),
+            modes.join(", ")
+        );
+        std::process::exit(1);
     }
-}
-
-pub struct Metal {
-    albedo: Vec3,
-    fuzz: f64,
-}
+    let exposure = match scene.exposure {
+        Some(e) if !ambient_occlusion => e.scale(),
+        _ => 1.,
+    };
+    let pose = *scene.camera.pose();
+    let center = pose.origin;
+    let cam = scene.camera;
+    let world = scene.accelerator.apply(scene.world);
+    let lights = if light_tree {
+        scene.lights.into_light_tree()
+    } else {
+        scene.lights
+    };
+    let guide = world.bounding_box().filter(|_| guiding).map(Guide::new);
+    let tracer = PathTracer {
+        world: &world,
+        lights: &lights,
+        delta_lights: &scene.delta_lights,
+        environment: &*scene.environment,
+        portals: &scene.portals,
+        // cap the light a sample brings, straight from the lights and over
+        // longer paths, to trade a little brightness for no fireflies
+        clamp_direct: None,
+        clamp_indirect: None,
+        // blur glossy surfaces to at least this roughness after a diffuse
+        // bounce, to bring out caustics from small lights, slightly biased
+        regularize: None,
+        // stop paths after this many bounces of each kind
+        max_bounces: MaxBounces {
+            diffuse: 50,
+            glossy: 50,
+            transmission: 50,
+        },
+        guide: guide.as_ref(),
+        fog: scene.fog.as_ref(),
+    };
+    let bdpt = Bidirectional {
+        world: &world,
+        lights: &lights,
+        environment: &*scene.environment,
+    };
+    let ao = AmbientOcclusion {
+        world: &world,
+        max_distance: occlusion_distance,
+    };
+    let integrator: &dyn Integrator = if ambient_occlusion {
+        &ao
+    } else if bidirectional {
+        &bdpt
+    } else {
+        &tracer
+    };
 
-impl Material for Metal {
-    fn scatter(&self, r: &Ray, hit_record: &HitRecord) -> Option<Scatter> {
-        let reflected = r.direction().unit().reflect(&hit_record.normal);
-        let scattered = Ray {
-            a: hit_record.p,
-            b: reflected + 1.0_f64.min(self.fuzz) * random_in_unit_sphere(),
-        };
-        if scattered.direction().dot(hit_record.normal) > 0. {
-            Some(Scatter {
-                attenuation: self.albedo,
-                scattered: scattered,
-            })
-        } else {
-            None
+    if let Some(guide) = &guide {
+        for pass in 0..guiding_passes {
+            let sampling = Sampling {
+                strata: 1,
+                max_rounds: 1 << pass,
+                tolerance: 0.,
+                ..sampling
+            };
+            render(&*cam, &tracer, nx, ny, sampling, exposure);
+            guide.update();
         }
     }
-}
-
-#[derive(Copy, Clone)]
-pub struct HitRecord<'a> {
-    pub t: f64,
-    pub p: Vec3,
-    pub normal: Vec3,
-    pub material: &'a Material,
-}
 
-pub trait Hittable {
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
-}
-
-pub struct Sphere {
-    center: Vec3,
-    radius: f64,
-    material: Box<Material>,
-}
-
-impl Hittable for Sphere {
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let oc = *r.origin() - self.center;
-        let a = r.direction().dot(*r.direction());
-        let b = 2.0 * oc.dot(*r.direction());
-        let c = oc.dot(oc) - self.radius * self.radius;
-        let discriminant = b * b - 4. * a * c;
-        if discriminant <= 0. {
-            return None;
-        }
-        let sol_pos = (-b + discriminant.sqrt()) / (2.0 * a);
-        let sol_neg = (-b - discriminant.sqrt()) / (2.0 * a);
-        let t: Option<f64> = {
-            if sol_neg > t_min && sol_neg < t_max {
-                Some(sol_neg)
-            } else if sol_pos > t_min && sol_pos < t_max {
-                Some(sol_pos)
-            } else {
-                None
-            }
+    // an optional third argument renders a pair of views for the left and
+    // right eyes, one over the other, either through the scene's camera or
+    // all the way around from where it stands
+    if let Some(mode @ ("stereo" | "stereo360")) = mode.as_deref() {
+        let (view, width, name): (Box<dyn Camera>, _, _) = if mode == "stereo" {
+            (cam, nx, "stereo.png")
+        } else {
+            // level, facing the way the camera does
+            let ahead = center - Vec3(pose.w.x(), 0., pose.w.z());
+            let view = Equirectangular::new(center, ahead, Vec3(0., 1., 0.));
+            (Box::new(view), 2 * ny, "stereo360.png")
         };
-        match t {
-            Some(t_val) => {
-                let p = r.point_at_parameter(t_val);
-                Some(HitRecord {
-                    t: t_val,
-                    p,
-                    normal: (p - self.center) / self.radius,
-                    material: &(*self.material),
-                })
-            }
-            None => None,
+        let mut data = Vec::new();
+        for eye in &view.stereo_pair(ipd, convergence) {
+            data.extend(render(&**eye, integrator, width, ny, sampling, exposure));
         }
+        save_png(&data, name, width, 2 * ny);
+        return;
     }
-}
-
-pub struct World {
-    hittables: Vec<Box<Hittable>>,
-}
 
-impl Hittable for World {
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        self.hittables
+    // or a cube map around the camera, as six images or one cross of them
+    if mode.is_some() {
+        let faces: Vec<(&str, Vec<u8>)> = Perspective::cube_map(center)
             .iter()
-            .filter_map(|h| h.hit(&r, t_min, t_max))
-            .min_by_key(|r| ordered_float::OrderedFloat(r.t))
-    }
-}
-
-fn color(r: Ray, world: &Hittable, depth: i32) -> Vec3 {
-    match world.hit(&r, 0.0001, std::f64::MAX) {
-        Some(hit_record) => match hit_record.material.scatter(&r, &hit_record) {
-            Some(scatter) if depth < 50 => {
-                scatter.attenuation * color(scatter.scattered, world, depth + 1)
+            .map(|(name, face)| {
+                let data = render(face, integrator, ny, ny, sampling, exposure);
+                (*name, data)
+            })
+            .collect();
+        if mode.as_deref() == Some("cubemap") {
+            for (name, data) in &faces {
+                save_png(data, &format!("cube_{}.png", name), ny, ny);
             }
-            _ => Vec3(0., 0., 0.),
-        },
-        None => {
-            let unit_direction = r.direction().unit();
-            let t = 0.5 * (unit_direction.y() + 1.0);
-            (1.0 - t) * Vec3(1.0, 1.0, 1.0) + t * Vec3(0.5, 0.7, 1.0)
+        } else {
+            let data = cross(&faces, ny as usize);
+            save_png(&data, "cube_cross.png", 4 * ny, 3 * ny);
         }
+        return;
     }
-}
 
-fn random_in_unit_sphere() -> Vec3 {
-    let mut p: Vec3;
-    loop {
-        p =
-            2.0 * Vec3(
-                rand::random::<f64>(),
-                rand::random::<f64>(),
-                rand::random::<f64>(),
-            ) - Vec3(1.0, 1.0, 1.0);
-        if p.squared_length() < 1.0 {
-            break;
-        }
-    }
-    p
+    let img_data = if metropolis {
+        let mlt = Metropolis {
+            integrator,
+            chains: 256,
+            mutations_per_pixel: (sampling.strata * sampling.strata) as usize,
+            large_step_probability: 0.3,
+            seed: sampling.seed,
+        };
+        mlt.render(&*cam, nx as usize, ny as usize)
+            .into_iter()
+            .flat_map(|c| rgba(exposure * c, 1.))
+            .collect()
+    } else if restir {
+        let restir = Restir {
+            world: &world,
+            lights: &lights,
+            environment: &*scene.environment,
+            candidates: 32,
+            neighbours: 5,
+            radius: 30.,
+            frames: (sampling.strata * sampling.strata) as usize,
+            seed: sampling.seed,
+        };
+        restir
+            .render(&*cam, nx as usize, ny as usize)
+            .into_iter()
+            .flat_map(|c| rgba(exposure * c, 1.))
+            .collect()
+    } else {
+        render(&*cam, integrator, nx, ny, sampling, exposure)
+    };
+    let now = Utc::now();
+    save_png(&img_data, &format!("test_{}.png", now.timestamp()), nx, ny);
+    save_png(&img_data, "test.png", nx, ny);
 }
 
-pub struct Camera {
-    origin: Vec3,
-    lower_left_corner: Vec3,
-    horizontal: Vec3,
-    vertical: Vec3,
+/// How many samples to take of each pixel, and where.
+#[derive(Copy, Clone)]
+struct Sampling {
+    strata: i32,
+    tolerance: f64,
+    max_rounds: i32,
+    sampler: SamplerKind,
+    seed: u32,
+    filter: Filter,
+    spectral: bool,
+    transparent: bool,
 }
 
-impl Camera {
-    fn get_ray(&self, u: f64, v: f64) -> Ray {
-        Ray {
-            a: self.origin,
-            b: self.lower_left_corner + u * self.horizontal + v * self.vertical,
+impl Sampling {
+    /// Whether `n` samples whose brightnesses add up to `sum`, and their
+    /// squares to `sum_squares`, put the pixel's brightness within
+    /// `tolerance` of the true one, 95% of the time. Dark pixels are held
+    /// to the error allowed one a hundredth as bright as white.
+    fn converged(&self, n: i32, sum: f64, sum_squares: f64) -> bool {
+        if n < 2 {
+            return false;
         }
+        let n = n as f64;
+        let mean = sum / n;
+        let variance = ((sum_squares - sum * mean) / (n - 1.)).max(0.);
+        1.96 * (variance / n).sqrt() <= self.tolerance * mean.max(0.01)
     }
 }
 
-fn main() {
-    let nx = 400;
-    let ny = 200;
-    let num_samples_per_pixel = 50;
-    let cam = Camera {
-        origin: Vec3(0.0, 0.0, 0.0),
-        lower_left_corner: Vec3(-2.0, -1.0, -1.0),
-        horizontal: Vec3(4.0, 0.0, 0.0),
-        vertical: Vec3(0.0, 2.0, 0.0),
-    };
-    let world = World {
-        hittables: vec![
-            Box::new(Sphere {
-                center: Vec3(0., 0., -1.),
-                radius: 0.5,
-                material: Box::new(Lambertian {
-                    albedo: Vec3(0.8, 0.3, 0.3),
-                }),
-            }),
-            Box::new(Sphere {
-                center: Vec3(0., -100.5, -1.),
-                radius: 100.,
-                material: Box::new(Lambertian {
-                    albedo: Vec3(0.8, 0.8, 0.0),
-                }),
-            }),
-            Box::new(Sphere {
-                center: Vec3(1., 0., -1.),
-                radius: 0.5,
-                material: Box::new(Metal {
-                    albedo: Vec3(0.8, 0.6, 0.2),
-                    fuzz: 0.,
-                }),
-            }),
-            Box::new(Sphere {
-                center: Vec3(-1., 0., -1.),
-                radius: 0.5,
-                material: Box::new(Metal {
-                    albedo: Vec3(0.8, 0.8, 0.8),
-                    fuzz: 0.7
-                }),
-            }),
-        ],
-    };
-
-    let mut img_data = Vec::new();
+/// RGBA pixels, top row first, of what `cam` sees by `integrator`, radiance
+/// multiplied by `exposure` for pixel values. Each pixel is sampled in
+/// rounds of a jittered point in each cell of its grid of strata until it
+/// has converged, so samples go where the image is noisiest, and each
+/// sample counts towards the pixels around it by the reconstruction filter.
+/// Spectral samples are turned into colors first.
+fn render(
+    cam: &dyn Camera,
+    integrator: &dyn Integrator,
+    nx: i32,
+    ny: i32,
+    sampling: Sampling,
+    exposure: f64,
+) -> Vec<u8> {
+    let strata = sampling.strata;
+    let mut film = Film::new(nx as usize, ny as usize, sampling.filter);
     for j in (0..ny).rev() {
         for i in 0..nx {
-            let mut col = Vec3(0., 0., 0.);
-            for _k in 0..num_samples_per_pixel {
-                let u = ((i as f64) + rand::random::<f64>()) / (nx as f64);
-                let v = ((j as f64) + rand::random::<f64>()) / (ny as f64);
-                let r = cam.get_ray(u, v);
-                col = col + color(r, &world, 0);
+            // the samples' brightness too, for how far off the pixel may be
+            let (mut sum, mut sum_squares) = (0., 0.);
+            let mut n = 0;
+            for round in 0..sampling.max_rounds {
+                // a pixel's samples go nearly the same way, so they make a
+                // good packet
+                let mut rays = Vec::new();
+                let mut samplers = Vec::new();
+                let mut points = Vec::new();
+                for k in 0..strata * strata {
+                    let index = (round * strata * strata + k) as u32;
+                    let mut sampler =
+                        sampling
+                            .sampler
+                            .sampler(sampling.seed, (i as u32, j as u32), index);
+                    let (du, dv) = sampler.get_2d();
+                    let x = i as f64 + ((k % strata) as f64 + du) / (strata as f64);
+                    let y = j as f64 + ((k / strata) as f64 + dv) / (strata as f64);
+                    match cam.generate_ray(x / nx as f64, y / ny as f64, &mut sampler) {
+                        Some(mut ray) => {
+                            if sampling.spectral {
+                                ray.wavelengths = Some(Wavelengths::sample(sampler.get_1d()));
+                            }
+                            rays.push(ray);
+                            samplers.push(sampler);
+                            points.push((x, y));
+                        }
+                        // blocked by the camera
+                        None => film.add(x, y, Vec3(0., 0., 0.)),
+                    }
+                }
+                let colors: Vec<(Vec3, f64)> = if sampling.transparent {
+                    rays.iter()
+                        .zip(&mut samplers)
+                        .map(|(r, sampler)| integrator.radiance_alpha(*r, &mut **sampler))
+                        .collect()
+                } else {
+                    let colors = integrator.radiance_packet(&rays, &mut samplers);
+                    colors.into_iter().map(|c| (c, 1.)).collect()
+                };
+                for (((c, alpha), &(x, y)), ray) in colors.into_iter().zip(&points).zip(&rays) {
+                    // the light at each wavelength as a color
+                    let c = match &ray.wavelengths {
+                        Some(wavelengths) => wavelengths.to_rgb(c),
+                        None => c,
+                    };
+                    film.add_alpha(x, y, c, alpha);
+                    sum += c.luminance();
+                    sum_squares += c.luminance() * c.luminance();
+                }
+                n += strata * strata;
+                if sampling.converged(n, sum, sum_squares) {
+                    break;
+                }
             }
-            col = col / (num_samples_per_pixel as f64);
-            let ir = (255.99 * col.r().sqrt()) as u8; // sqrt for gamma 2
-            let ig = (255.99 * col.g().sqrt()) as u8;
-            let ib = (255.99 * col.b().sqrt()) as u8;
-            img_data.push(ir);
-            img_data.push(ig);
-            img_data.push(ib);
-            img_data.push(255);
         }
         print!(".");
         std::io::stdout().flush().unwrap();
     }
-    println!("");
-    let now = Utc::now();
-    save_png(&img_data, &format!("test_{}.png", now.timestamp()), nx, ny);
-    save_png(&img_data, "test.png", nx, ny);
+    println!();
+    film.pixels()
+        .into_iter()
+        .zip(film.alphas())
+        .flat_map(|(col, alpha)| rgba(exposure * col, alpha))
+        .collect()
+}
+
+/// The pixel value for radiance `col`, premultiplied by `alpha`.
+fn rgba(col: Vec3, alpha: f64) -> [u8; 4] {
+    // PNGs aren't premultiplied
+    let col = if alpha > 0. { col / alpha } else { col };
+    let ir = (255.99 * col.r().sqrt()) as u8; // sqrt for gamma 2
+    let ig = (255.99 * col.g().sqrt()) as u8;
+    let ib = (255.99 * col.b().sqrt()) as u8;
+    [ir, ig, ib, (255.99 * alpha.clamp(0., 1.)) as u8]
+}
+
+/// Cube map faces of `size` pixels square laid out as a cross, four faces
+/// wide and three high, with the -z face in the middle.
+fn cross(faces: &[(&str, Vec<u8>)], size: usize) -> Vec<u8> {
+    let mut data = vec![0; 4 * size * 3 * size * 4];
+    let places = [
+        ("py", 1, 0),
+        ("nx", 0, 1),
+        ("nz", 1, 1),
+        ("px", 2, 1),
+        ("pz", 3, 1),
+        ("ny", 1, 2),
+    ];
+    for &(name, column, row) in &places {
+        let face = &faces.iter().find(|(n, _)| *n == name).unwrap().1;
+        for y in 0..size {
+            let from = y * size * 4;
+            let to = ((row * size + y) * 4 * size + column * size) * 4;
+            data[to..to + size * 4].copy_from_slice(&face[from..from + size * 4]);
+        }
+    }
+    data
 }
 
 fn save_png(data: &[u8], name: &str, width: i32, height: i32) {
     let file = File::create(Path::new(name)).unwrap();
-    let ref mut w = BufWriter::new(file);
+    let w = &mut BufWriter::new(file);
     let mut encoder = png::Encoder::new(w, width as u32, height as u32);
     encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
     let mut writer = encoder.write_header().unwrap();
@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::bvh::{Bvh, Primitives};
+use crate::registry::MaterialRegistry;
+use crate::triangle;
+use crate::vec3::Vec3;
+use crate::{HitRecord, Hittable, Material, Ray};
+
+/// Many triangles sharing one set of vertex buffers. Each face is three
+/// indices into `positions`, and into `normals` and `uvs` as well when those
+/// aren't empty, so a vertex shared by several faces is stored once. Faces
+/// can be made of different materials from a table, so a model with several
+/// parts stays one mesh.
+pub struct TriangleMesh {
+    pub positions: Vec<Vec3>,
+    /// Per-vertex normals for smooth shading, or empty for flat faces.
+    pub normals: Vec<Vec3>,
+    /// Per-vertex surface coordinates, or empty.
+    pub uvs: Vec<(f64, f64)>,
+    pub indices: Vec<[u32; 3]>,
+    pub materials: Vec<Arc<dyn Material>>,
+    /// Per-face indices into `materials`, or empty for all faces to use the
+    /// first.
+    pub face_materials: Vec<u32>,
+}
+
+impl TriangleMesh {
+    /// Read the vertices and faces of a Wavefront OBJ file, splitting faces
+    /// with more than three sides into fans. Faces after a `usemtl` line get
+    /// the material of that name from `registry`, and ones before any, or
+    /// naming a material it doesn't have, get `default`.
+    ///
+    /// Surface coordinates and normals are read too, and each different
+    /// combination of position, coordinates and normal the faces use
+    /// becomes a vertex of its own, so vertices along a seam are split and
+    /// the rest shared. Corners without coordinates get (0, 0), and ones
+    /// without a normal, in a file giving some, an average of the faces
+    /// around them as `compute_normals` does.
+    pub fn load_obj<P: AsRef<Path>>(
+        path: P,
+        registry: &MaterialRegistry,
+        default: Arc<dyn Material>,
+    ) -> io::Result<TriangleMesh> {
+        let invalid = |line: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("bad OBJ line: {}", line),
+            )
+        };
+        let numbers = |words: std::str::SplitWhitespace, n: usize, line: &str| {
+            let values = words
+                .take(n)
+                .map(str::parse::<f64>)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| invalid(line))?;
+            if values.len() == n {
+                Ok(values)
+            } else {
+                Err(invalid(line))
+            }
+        };
+        let (mut file_positions, mut file_uvs, mut file_normals) =
+            (Vec::new(), Vec::new(), Vec::new());
+        // the position, coordinates and normal of each vertex, and the
+        // vertex for each such combination
+        let mut corners: Vec<Corner> = Vec::new();
+        let mut vertices: HashMap<Corner, u32> = HashMap::new();
+        let mut indices = Vec::new();
+        let mut materials = vec![default];
+        let mut material_slots: HashMap<String, u32> = HashMap::new();
+        let mut face_materials = Vec::new();
+        let mut current = 0;
+        for line in std::fs::read_to_string(path)?.lines() {
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("v") => {
+                    let xyz = numbers(words, 3, line)?;
+                    file_positions.push(Vec3(xyz[0], xyz[1], xyz[2]));
+                }
+                Some("vt") => {
+                    let uv = numbers(words, 2, line)?;
+                    file_uvs.push((uv[0], uv[1]));
+                }
+                Some("vn") => {
+                    let xyz = numbers(words, 3, line)?;
+                    file_normals.push(Vec3(xyz[0], xyz[1], xyz[2]));
+                }
+                Some("f") => {
+                    let counts = [file_positions.len(), file_uvs.len(), file_normals.len()];
+                    let mut face = Vec::new();
+                    for word in words {
+                        let corner = parse_corner(word, counts).ok_or_else(|| invalid(line))?;
+                        let next = corners.len() as u32;
+                        face.push(*vertices.entry(corner).or_insert_with(|| {
+                            corners.push(corner);
+                            next
+                        }));
+                    }
+                    if face.len() < 3 {
+                        return Err(invalid(line));
+                    }
+                    for i in 1..face.len() - 1 {
+                        indices.push([face[0], face[i], face[i + 1]]);
+                        face_materials.push(current);
+                    }
+                }
+                Some("usemtl") => {
+                    let name = words.next().ok_or_else(|| invalid(line))?;
+                    current = match registry.get(name) {
+                        Some(material) => {
+                            *material_slots.entry(name.to_string()).or_insert_with(|| {
+                                materials.push(material);
+                                materials.len() as u32 - 1
+                            })
+                        }
+                        None => 0,
+                    };
+                }
+                _ => {}
+            }
+        }
+        let uvs = if corners.iter().any(|&(_, uv, _)| uv.is_some()) {
+            corners
+                .iter()
+                .map(|&(_, uv, _)| uv.map_or((0., 0.), |i| file_uvs[i as usize]))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let mut mesh = TriangleMesh {
+            positions: corners
+                .iter()
+                .map(|&(position, _, _)| file_positions[position as usize])
+                .collect(),
+            normals: Vec::new(),
+            uvs,
+            indices,
+            materials,
+            face_materials,
+        };
+        if corners.iter().any(|&(_, _, normal)| normal.is_some()) {
+            mesh.compute_normals();
+            for (vertex, &(_, _, normal)) in corners.iter().enumerate() {
+                if let Some(i) = normal {
+                    mesh.normals[vertex] = file_normals[i as usize];
+                }
+            }
+        }
+        Ok(mesh)
+    }
+
+    fn material(&self, face: usize) -> &dyn Material {
+        let slot = self.face_materials.get(face).map_or(0, |&i| i as usize);
+        &*self.materials[slot]
+    }
+
+    /// Fill in `normals` for smooth shading by averaging the normals of the
+    /// faces around each vertex, weighted by their areas. Hits still use the
+    /// flat face normal as their geometric normal.
+    pub fn compute_normals(&mut self) {
+        let mut normals = vec![Vec3(0., 0., 0.); self.positions.len()];
+        for face in 0..self.indices.len() {
+            let [a, b, c] = self.vertices(face);
+            // twice the face area, in the direction of its normal
+            let n = (b - a).cross(c - a);
+            for &i in &self.indices[face] {
+                normals[i as usize] = normals[i as usize] + n;
+            }
+        }
+        self.normals = normals
+            .into_iter()
+            .map(|n| if n.squared_length() > 0. { n.unit() } else { n })
+            .collect();
+    }
+
+    fn vertices(&self, face: usize) -> [Vec3; 3] {
+        let [a, b, c] = self.indices[face];
+        [
+            self.positions[a as usize],
+            self.positions[b as usize],
+            self.positions[c as usize],
+        ]
+    }
+
+    pub(crate) fn face_hit_record(
+        &self,
+        r: &Ray,
+        face: usize,
+        t: f64,
+        bary: (f64, f64),
+    ) -> HitRecord<'_> {
+        let [a, b, c] = self.indices[face];
+        let [a, b, c] = [a as usize, b as usize, c as usize];
+        let normals = if self.normals.is_empty() {
+            None
+        } else {
+            Some([self.normals[a], self.normals[b], self.normals[c]])
+        };
+        let uvs = if self.uvs.is_empty() {
+            [(0., 0.), (1., 0.), (0., 1.)]
+        } else {
+            [self.uvs[a], self.uvs[b], self.uvs[c]]
+        };
+        triangle::hit_record(
+            r,
+            t,
+            bary,
+            self.vertices(face),
+            normals,
+            uvs,
+            self.material(face),
+        )
+    }
+
+    fn hit_face(&self, r: &Ray, face: usize, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let [a, b, c] = self.vertices(face);
+        let (t, beta, gamma) = triangle::intersect(r, a, b, c)?;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+        Some(self.face_hit_record(r, face, t, (beta, gamma))).filter(HitRecord::opaque)
+    }
+
+    /// The faces in a BVH, for meshes too big to test face by face. The
+    /// tree is over the faces' indices, so the mesh stays as it is.
+    pub fn into_bvh(self) -> Bvh<TriangleMesh> {
+        Bvh::new(self)
+    }
+
+    /// The faces in a BVH that may split space through them, for meshes
+    /// with long thin faces; see `Bvh::with_spatial_splits`.
+    pub fn into_spatial_bvh(self) -> Bvh<TriangleMesh> {
+        Bvh::with_spatial_splits(self)
+    }
+
+    /// The mesh traced by Intel Embree; see `EmbreeMesh`.
+    #[cfg(feature = "embree")]
+    pub fn into_embree(self) -> crate::embree::EmbreeMesh {
+        crate::embree::EmbreeMesh::new(self)
+    }
+}
+
+/// The indices of a face corner's position, and surface coordinates and
+/// normal if it has them, into the lists an OBJ file gives.
+type Corner = (u32, Option<u32>, Option<u32>);
+
+/// A face corner written "v", "v/vt", "v//vn" or "v/vt/vn", given how many
+/// positions, coordinates and normals there are so far. Indices count from
+/// one, or back from the last so far if negative. None if it is malformed
+/// or an index is out of range.
+fn parse_corner(word: &str, counts: [usize; 3]) -> Option<Corner> {
+    let resolve = |field: &str, count: usize| {
+        let index = field.parse::<i64>().ok()?;
+        let index = if index < 0 {
+            count as i64 + index
+        } else {
+            index - 1
+        };
+        if index < 0 || index >= count as i64 {
+            None
+        } else {
+            Some(index as u32)
+        }
+    };
+    let mut fields = word.split('/');
+    let position = resolve(fields.next()?, counts[0])?;
+    let mut optional = |count: usize| match fields.next() {
+        None | Some("") => Some(None),
+        Some(field) => resolve(field, count).map(Some),
+    };
+    let uv = optional(counts[1])?;
+    let normal = optional(counts[2])?;
+    if fields.next().is_some() {
+        return None;
+    }
+    Some((position, uv, normal))
+}
+
+impl Primitives for TriangleMesh {
+    fn count(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn hit_primitive(&self, face: usize, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.hit_face(r, face, t_min, t_max)
+    }
+
+    fn primitive_box(&self, face: usize) -> Option<Aabb> {
+        Some(Aabb::from_points(&self.vertices(face)))
+    }
+
+    fn clipped_primitive_box(&self, face: usize, clip: &Aabb) -> Option<Aabb> {
+        triangle::clipped_box(self.vertices(face), clip)
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mut closest = None;
+        let mut t_max = t_max;
+        for face in 0..self.indices.len() {
+            if let Some(hit_record) = self.hit_face(r, face, t_min, t_max) {
+                t_max = hit_record.t;
+                closest = Some(hit_record);
+            }
+        }
+        closest
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        if self.positions.is_empty() {
+            None
+        } else {
+            Some(Aabb::from_points(&self.positions))
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use crate::camera::Camera;
+use crate::integrator::Integrator;
+use crate::sampler::{RandomSampler, Sampler};
+use crate::vec3::Vec3;
+
+/// Fresh paths each chain picks its first from, in proportion to how
+/// bright they are.
+const BOOTSTRAP: usize = 64;
+
+/// Primary sample space Metropolis light transport, after Kelemen et al.
+/// 2002 ("A Simple and Robust Mutation Strategy for the Metropolis Light
+/// Transport Algorithm"). Each path is made from the numbers the sampler
+/// gives, from where in the image on, so rather than drawing fresh numbers
+/// for every path, chains of paths are made by nudging each of the last
+/// path's numbers a little, keeping the new path with a probability given
+/// by how much brighter it is than the last. Chains spend their time on
+/// paths in proportion to the light they bring, so once one finds light
+/// that is hard to find, like light leaking through the gap around a
+/// door, it goes on to explore the paths around it rather than losing it
+/// again. Now and then the numbers are drawn afresh, so chains move
+/// between parts of the image that no small steps connect.
+pub struct Metropolis<'a> {
+    pub integrator: &'a dyn Integrator,
+    /// Markov chains to run, each from a start of its own.
+    pub chains: usize,
+    /// Paths to follow for each pixel of the image, over all the chains.
+    pub mutations_per_pixel: usize,
+    /// How often a path's numbers are drawn afresh rather than nudged.
+    pub large_step_probability: f64,
+    /// Picks all the random numbers, so the same seed renders the same
+    /// image.
+    pub seed: u32,
+}
+
+impl Metropolis<'_> {
+    /// The radiance of each of `nx` by `ny` pixels, top row first, of what
+    /// `cam` sees.
+    pub fn render(&self, cam: &dyn Camera, nx: usize, ny: usize) -> Vec<Vec3> {
+        let mut image = vec![Vec3(0., 0., 0.); nx * ny];
+        // no more chains than there are paths to follow, and at least one
+        // path for each, so every chain counts towards the image
+        let total = (self.mutations_per_pixel * nx * ny).max(1);
+        let chains = self.chains.clamp(1, total);
+        let mutations = total / chains;
+        let mut brightness = 0.;
+        for chain in 0..chains {
+            let mut random = RandomSampler::new(self.seed as u64, chain as u64);
+            // a first path picked among fresh ones in proportion to their
+            // brightness, as if the chain had already been running; their
+            // average brightness is that of the whole image
+            let mut samples = PrimarySamples::new(random);
+            let mut current = (0, Vec3(0., 0., 0.));
+            let mut total = 0.;
+            for _ in 0..BOOTSTRAP {
+                let seed = random.next_u32() as u64;
+                let mut candidate = PrimarySamples::new(RandomSampler::new(seed, chain as u64));
+                let path = self.path(cam, nx, ny, &mut candidate);
+                let f = path.1.luminance();
+                total += f;
+                if f > 0. && random.get_1d() * total < f {
+                    samples = candidate;
+                    current = path;
+                }
+            }
+            brightness += total / BOOTSTRAP as f64;
+
+            for _ in 0..mutations {
+                samples.mutate(random.get_1d() < self.large_step_probability);
+                let proposed = self.path(cam, nx, ny, &mut samples);
+                let (f_current, f_proposed) = (current.1.luminance(), proposed.1.luminance());
+                let accept = if f_current > 0. {
+                    (f_proposed / f_current).min(1.)
+                } else {
+                    1.
+                };
+                // both paths count, by how likely each is to be the next
+                // in the chain, so rejected ones aren't wasted
+                if f_proposed > 0. {
+                    image[proposed.0] = image[proposed.0] + accept / f_proposed * proposed.1;
+                }
+                if f_current > 0. {
+                    image[current.0] = image[current.0] + (1. - accept) / f_current * current.1;
+                }
+                if random.get_1d() < accept {
+                    current = proposed;
+                } else {
+                    samples.reject();
+                }
+            }
+        }
+        // each path counts for its share of the image's brightness
+        let scale = brightness / chains as f64 * (nx * ny) as f64 / (chains * mutations) as f64;
+        image.into_iter().map(|c| scale * c).collect()
+    }
+
+    /// The pixel the path made from `samples` lands on, counting from the
+    /// top left, and the light it brings there.
+    fn path(
+        &self,
+        cam: &dyn Camera,
+        nx: usize,
+        ny: usize,
+        samples: &mut PrimarySamples,
+    ) -> (usize, Vec3) {
+        let (u, v) = samples.get_2d();
+        let i = ((u * nx as f64) as usize).min(nx - 1);
+        let j = ((v * ny as f64) as usize).min(ny - 1);
+        let radiance = match cam.generate_ray(u, v, samples) {
+            Some(r) => self.integrator.radiance(r, samples),
+            None => Vec3(0., 0., 0.),
+        };
+        ((ny - 1 - j) * nx + i, radiance)
+    }
+}
+
+/// The numbers a path is made from, kept to be nudged into the next path's
+/// and put back if that path is rejected. Dimensions no path has used yet
+/// are drawn from `random` as they are first needed.
+struct PrimarySamples {
+    values: Vec<f64>,
+    backup: Vec<f64>,
+    dimension: usize,
+    random: RandomSampler,
+}
+
+impl PrimarySamples {
+    fn new(random: RandomSampler) -> PrimarySamples {
+        PrimarySamples {
+            values: Vec::new(),
+            backup: Vec::new(),
+            dimension: 0,
+            random,
+        }
+    }
+
+    /// Starts a new path from the last one's numbers, each nudged a
+    /// little, or with all of them drawn afresh for a `large` step.
+    fn mutate(&mut self, large: bool) {
+        self.backup.clone_from(&self.values);
+        if large {
+            self.values.clear();
+        } else {
+            for value in &mut self.values {
+                *value = perturb(*value, &mut self.random);
+            }
+        }
+        self.dimension = 0;
+    }
+
+    /// Goes back to the numbers from before the last `mutate`.
+    fn reject(&mut self) {
+        std::mem::swap(&mut self.values, &mut self.backup);
+    }
+}
+
+impl Sampler for PrimarySamples {
+    fn get_1d(&mut self) -> f64 {
+        if self.dimension == self.values.len() {
+            let value = self.random.get_1d();
+            self.values.push(value);
+        }
+        self.dimension += 1;
+        self.values[self.dimension - 1]
+    }
+}
+
+/// `x` moved up or down by between 1/1024 and 1/64, more often by little,
+/// wrapping around [0, 1), at random by `random`.
+fn perturb(x: f64, random: &mut RandomSampler) -> f64 {
+    let (s1, s2): (f64, f64) = (1. / 1024., 1. / 64.);
+    let dv = s2 * (-(s2 / s1).ln() * random.get_1d()).exp();
+    let y = if random.get_1d() < 0.5 {
+        x + dv
+    } else {
+        x - dv
+    };
+    y - y.floor()
+}
@@ -0,0 +1,467 @@
+use std::f64::consts::PI;
+
+use crate::onb::Onb;
+use crate::pdf::Pdf;
+use crate::sampler::Sampler;
+use crate::texture::{ConstantTexture, Texture};
+use crate::vec3::Vec3;
+use crate::{HitRecord, Material, Ray, Scatter};
+
+// All directions here are in the local shading frame, where the surface
+// normal is +z.
+
+/// The GGX (Trowbridge-Reitz) microfacet distribution, with separate
+/// roughness along the local x (tangent) and y (bitangent) axes.
+#[derive(Debug, Copy, Clone)]
+pub struct Ggx {
+    pub alpha_x: f64,
+    pub alpha_y: f64,
+}
+
+/// Perceptual roughness in [0, 1] to a distribution alpha, clamped away from
+/// zero so the distribution stays well defined.
+fn roughness_to_alpha(roughness: f64) -> f64 {
+    (roughness * roughness).max(1e-3)
+}
+
+impl Ggx {
+    pub fn isotropic(alpha: f64) -> Ggx {
+        Ggx {
+            alpha_x: alpha,
+            alpha_y: alpha,
+        }
+    }
+
+    pub fn from_roughness(roughness: f64) -> Ggx {
+        Ggx::isotropic(roughness_to_alpha(roughness))
+    }
+
+    pub fn from_anisotropic_roughness(roughness_x: f64, roughness_y: f64) -> Ggx {
+        Ggx {
+            alpha_x: roughness_to_alpha(roughness_x),
+            alpha_y: roughness_to_alpha(roughness_y),
+        }
+    }
+
+    /// Density of microfacet normals `h`.
+    pub fn d(&self, h: &Vec3) -> f64 {
+        if h.z() <= 0. {
+            return 0.;
+        }
+        let x = h.x() / self.alpha_x;
+        let y = h.y() / self.alpha_y;
+        let denom = x * x + y * y + h.z() * h.z();
+        1. / (PI * self.alpha_x * self.alpha_y * denom * denom)
+    }
+
+    /// Smith's auxiliary function for direction `w`.
+    pub fn lambda(&self, w: &Vec3) -> f64 {
+        let cos2 = w.z() * w.z();
+        if cos2 == 0. {
+            return f64::INFINITY;
+        }
+        let x = self.alpha_x * w.x();
+        let y = self.alpha_y * w.y();
+        (-1. + (1. + (x * x + y * y) / cos2).sqrt()) / 2.
+    }
+
+    /// Masking of a single direction.
+    pub fn g1(&self, w: &Vec3) -> f64 {
+        1. / (1. + self.lambda(w))
+    }
+
+    /// Height-correlated masking-shadowing of a pair of directions.
+    pub fn g2(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        1. / (1. + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Sample a microfacet normal from the distribution of normals visible
+    /// from `wo` (Heitz 2018, "Sampling the GGX Distribution of Visible
+    /// Normals").
+    pub fn sample_visible_normal(&self, wo: &Vec3, u1: f64, u2: f64) -> Vec3 {
+        // stretch the view direction to the hemisphere configuration
+        let vh = Vec3(self.alpha_x * wo.x(), self.alpha_y * wo.y(), wo.z()).unit();
+        let lensq = vh.x() * vh.x() + vh.y() * vh.y();
+        let t1 = if lensq > 0. {
+            Vec3(-vh.y(), vh.x(), 0.) / lensq.sqrt()
+        } else {
+            Vec3(1., 0., 0.)
+        };
+        let t2 = vh.cross(t1);
+        // uniformly sample the projected area, warped toward the visible half
+        let r = u1.sqrt();
+        let phi = 2. * PI * u2;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1. + vh.z());
+        let p2 = (1. - s) * (1. - p1 * p1).sqrt() + s * r * phi.sin();
+        let nh = p1 * t1 + p2 * t2 + (1. - p1 * p1 - p2 * p2).max(0.).sqrt() * vh;
+        // unstretch
+        Vec3(
+            self.alpha_x * nh.x(),
+            self.alpha_y * nh.y(),
+            nh.z().max(1e-6),
+        )
+        .unit()
+    }
+}
+
+/// Schlick's Fresnel approximation for a colored reflectance at normal
+/// incidence.
+pub fn fresnel_schlick(f0: Vec3, cos_theta: f64) -> Vec3 {
+    let m = (1. - cos_theta).clamp(0., 1.).powi(5);
+    f0 + (Vec3(1., 1., 1.) - f0) * m
+}
+
+/// Exact Fresnel reflectance of unpolarized light for a dielectric boundary,
+/// where `eta` is the ratio of the refractive index on the far side to the
+/// near side. Returns 1 on total internal reflection.
+pub fn fresnel_dielectric(cos_i: f64, eta: f64) -> f64 {
+    let cos_i = cos_i.clamp(0., 1.);
+    let sin2_t = (1. - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1. {
+        return 1.;
+    }
+    let cos_t = (1. - sin2_t).sqrt();
+    let rs = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    let rp = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    (rs * rs + rp * rp) / 2.
+}
+
+/// Exact Fresnel reflectance of unpolarized light off a conductor with
+/// complex refractive index `eta + i k`.
+fn fresnel_conductor_channel(cos_i: f64, eta: f64, k: f64) -> f64 {
+    let cos2 = cos_i * cos_i;
+    let sin2 = 1. - cos2;
+    let eta2 = eta * eta;
+    let k2 = k * k;
+    let t0 = eta2 - k2 - sin2;
+    let a2_plus_b2 = (t0 * t0 + 4. * eta2 * k2).sqrt();
+    let t1 = a2_plus_b2 + cos2;
+    let a = (0.5 * (a2_plus_b2 + t0)).max(0.).sqrt();
+    let t2 = 2. * cos_i * a;
+    let rs = (t1 - t2) / (t1 + t2);
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+    0.5 * (rp + rs)
+}
+
+/// Per-channel conductor Fresnel reflectance.
+pub fn fresnel_conductor(cos_i: f64, eta: Vec3, k: Vec3) -> Vec3 {
+    let cos_i = cos_i.clamp(0., 1.);
+    Vec3(
+        fresnel_conductor_channel(cos_i, eta.r(), k.r()),
+        fresnel_conductor_channel(cos_i, eta.g(), k.g()),
+        fresnel_conductor_channel(cos_i, eta.b(), k.b()),
+    )
+}
+
+/// Reflections off a GGX surface drawn by sampling the microfacet normals
+/// visible from `wo`, the direction back along the ray in `frame`.
+pub struct GgxPdf {
+    pub ggx: Ggx,
+    pub frame: Onb,
+    pub wo: Vec3,
+}
+
+impl Pdf for GgxPdf {
+    fn value(&self, direction: &Vec3) -> f64 {
+        let wi = self.frame.to_local(&direction.unit());
+        if wi.z() <= 0. {
+            return 0.;
+        }
+        // the density of visible normals, G1 * (wo . h) * D / wo.z, over
+        // the 4 (wo . h) that reflecting about them stretches it by
+        let h = (self.wo + wi).unit();
+        self.ggx.g1(&self.wo) * self.ggx.d(&h) / (4. * self.wo.z())
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        let (u1, u2) = sampler.get_2d();
+        let h = self.ggx.sample_visible_normal(&self.wo, u1, u2);
+        self.frame.local(&(-self.wo).reflect(&h))
+    }
+}
+
+/// The density of reflections off a GGX conductor seen along `r`, where
+/// `frame` is the local shading frame at the hit; None from below.
+pub fn conductor_pdf(ggx: Ggx, frame: Onb, r: &Ray) -> Option<Box<dyn Pdf>> {
+    let wo = frame.to_local(&-r.direction().unit());
+    if wo.z() <= 0. {
+        return None;
+    }
+    Some(Box::new(GgxPdf { ggx, frame, wo }))
+}
+
+/// The BSDF times the cosine of a GGX conductor whose Fresnel reflectance
+/// is given by `fresnel`, for light from `direction` seen along `r`.
+pub fn conductor_eval(
+    ggx: &Ggx,
+    fresnel: impl Fn(f64) -> Vec3,
+    frame: &Onb,
+    r: &Ray,
+    direction: &Vec3,
+) -> Vec3 {
+    let wo = frame.to_local(&-r.direction().unit());
+    let wi = frame.to_local(&direction.unit());
+    if wo.z() <= 0. || wi.z() <= 0. {
+        return Vec3(0., 0., 0.);
+    }
+    // F * D * G2 / (4 wo.z wi.z), times wi.z
+    let h = (wo + wi).unit();
+    fresnel(wo.dot(h)) * (ggx.d(&h) * ggx.g2(&wo, &wi) / (4. * wo.z()))
+}
+
+/// Reflection and refraction through a rough GGX boundary between
+/// dielectrics, after Walter et al. 2007 ("Microfacet Models for Refraction
+/// through Rough Surfaces"), drawn by sampling the microfacet normals
+/// visible from `wo` and then reflecting or refracting by their Fresnel
+/// term. `eta` is the ratio of the refractive index on the far side to the
+/// near side.
+pub struct DielectricPdf {
+    pub ggx: Ggx,
+    pub frame: Onb,
+    pub wo: Vec3,
+    pub eta: f64,
+}
+
+impl DielectricPdf {
+    /// The microfacet normal taking `wo` to `wi`, facing up, or None if no
+    /// microfacet can.
+    fn half_vector(&self, wi: &Vec3) -> Option<Vec3> {
+        let h = if wi.z() > 0. {
+            self.wo + *wi
+        } else {
+            -(self.wo + self.eta * *wi)
+        };
+        if h.squared_length() == 0. {
+            return None;
+        }
+        let h = if h.z() < 0. { -h.unit() } else { h.unit() };
+        // refracting needs the two on opposite sides of the microfacet
+        if self.wo.dot(h) <= 0. || (wi.z() < 0.) != (wi.dot(h) < 0.) {
+            return None;
+        }
+        Some(h)
+    }
+
+    /// The BSDF times the cosine for light from `direction`. Refraction
+    /// isn't scaled by the change in solid angle across the boundary, as
+    /// with smooth `Dielectric`, so smooth and rough glass pass on the same
+    /// light.
+    pub fn eval(&self, direction: &Vec3) -> f64 {
+        let wi = self.frame.to_local(&direction.unit());
+        let h = match self.half_vector(&wi) {
+            Some(h) => h,
+            None => return 0.,
+        };
+        let (ggx, wo) = (&self.ggx, &self.wo);
+        let fresnel = fresnel_dielectric(wo.dot(h), self.eta);
+        let g2 = ggx.g2(wo, &wi);
+        if wi.z() > 0. {
+            fresnel * ggx.d(&h) * g2 / (4. * wo.z())
+        } else {
+            let denom = wo.dot(h) + self.eta * wi.dot(h);
+            let eta2 = self.eta * self.eta;
+            eta2 * (1. - fresnel) * ggx.d(&h) * g2 * (wi.dot(h) * wo.dot(h)).abs()
+                / (wo.z() * denom * denom)
+        }
+    }
+}
+
+impl Pdf for DielectricPdf {
+    fn value(&self, direction: &Vec3) -> f64 {
+        let wi = self.frame.to_local(&direction.unit());
+        let h = match self.half_vector(&wi) {
+            Some(h) => h,
+            None => return 0.,
+        };
+        let wo = &self.wo;
+        let fresnel = fresnel_dielectric(wo.dot(h), self.eta);
+        let visible = self.ggx.g1(wo) * wo.dot(h) * self.ggx.d(&h) / wo.z();
+        if wi.z() > 0. {
+            fresnel * visible / (4. * wo.dot(h))
+        } else {
+            let denom = wo.dot(h) + self.eta * wi.dot(h);
+            (1. - fresnel) * visible * self.eta * self.eta * wi.dot(h).abs() / (denom * denom)
+        }
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        let (u1, u2) = sampler.get_2d();
+        let h = self.ggx.sample_visible_normal(&self.wo, u1, u2);
+        let fresnel = fresnel_dielectric(self.wo.dot(h), self.eta);
+        let wi = match (-self.wo).refract(&h, 1. / self.eta) {
+            Some(refracted) if sampler.get_1d() >= fresnel => refracted,
+            _ => (-self.wo).reflect(&h),
+        };
+        self.frame.local(&wi)
+    }
+}
+
+/// A rough specular reflector using the GGX distribution, Smith
+/// shadowing-masking and Schlick Fresnel with reflectance `f0`.
+pub struct Microfacet {
+    pub f0: Box<dyn Texture>,
+    pub roughness: Box<dyn Texture>,
+}
+
+impl Microfacet {
+    fn ggx(&self, hit_record: &HitRecord) -> Ggx {
+        Ggx::from_roughness(hit_record.roughness(&*self.roughness))
+    }
+}
+
+impl Material for Microfacet {
+    fn scatter(
+        &self,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        Scatter::sample(self, r, hit_record, sampler)
+    }
+
+    fn pdf(&self, r: &Ray, hit_record: &HitRecord) -> Option<Box<dyn Pdf>> {
+        conductor_pdf(self.ggx(hit_record), Onb::from_w(&hit_record.normal), r)
+    }
+
+    fn eval(&self, r: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Vec3 {
+        let frame = Onb::from_w(&hit_record.normal);
+        let f0 = hit_record.texture(&*self.f0);
+        let fresnel = |c| fresnel_schlick(f0, c);
+        conductor_eval(&self.ggx(hit_record), fresnel, &frame, r, direction)
+    }
+
+    fn glossy(&self, _hit_record: &HitRecord) -> bool {
+        true
+    }
+}
+
+/// Brushed metal: a GGX reflector whose roughness differs along the surface
+/// tangent (`roughness_x`) and bitangent (`roughness_y`), stretching
+/// highlights across the brushing direction.
+pub struct AnisotropicMetal {
+    pub f0: Box<dyn Texture>,
+    pub roughness_x: Box<dyn Texture>,
+    pub roughness_y: Box<dyn Texture>,
+}
+
+impl AnisotropicMetal {
+    fn ggx(&self, hit_record: &HitRecord) -> Ggx {
+        Ggx::from_anisotropic_roughness(
+            hit_record.roughness(&*self.roughness_x),
+            hit_record.roughness(&*self.roughness_y),
+        )
+    }
+}
+
+impl Material for AnisotropicMetal {
+    fn scatter(
+        &self,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        Scatter::sample(self, r, hit_record, sampler)
+    }
+
+    fn pdf(&self, r: &Ray, hit_record: &HitRecord) -> Option<Box<dyn Pdf>> {
+        conductor_pdf(self.ggx(hit_record), hit_record.frame(), r)
+    }
+
+    fn eval(&self, r: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Vec3 {
+        let f0 = hit_record.texture(&*self.f0);
+        let fresnel = |c| fresnel_schlick(f0, c);
+        conductor_eval(
+            &self.ggx(hit_record),
+            fresnel,
+            &hit_record.frame(),
+            r,
+            direction,
+        )
+    }
+
+    fn glossy(&self, _hit_record: &HitRecord) -> bool {
+        true
+    }
+}
+
+/// A metal described by its measured complex refractive index `eta + i k`
+/// at red, green and blue wavelengths, with GGX roughness.
+pub struct Conductor {
+    pub eta: Vec3,
+    pub k: Vec3,
+    pub roughness: Box<dyn Texture>,
+}
+
+impl Conductor {
+    pub fn gold(roughness: f64) -> Conductor {
+        Conductor {
+            eta: Vec3(0.143, 0.374, 1.442),
+            k: Vec3(3.983, 2.385, 1.603),
+            roughness: Box::new(ConstantTexture::gray(roughness)),
+        }
+    }
+
+    pub fn silver(roughness: f64) -> Conductor {
+        Conductor {
+            eta: Vec3(0.155, 0.117, 0.138),
+            k: Vec3(4.828, 3.122, 2.147),
+            roughness: Box::new(ConstantTexture::gray(roughness)),
+        }
+    }
+
+    pub fn copper(roughness: f64) -> Conductor {
+        Conductor {
+            eta: Vec3(0.200, 0.924, 1.102),
+            k: Vec3(3.912, 2.452, 2.142),
+            roughness: Box::new(ConstantTexture::gray(roughness)),
+        }
+    }
+
+    pub fn aluminum(roughness: f64) -> Conductor {
+        Conductor {
+            eta: Vec3(1.657, 0.880, 0.521),
+            k: Vec3(9.224, 6.270, 4.837),
+            roughness: Box::new(ConstantTexture::gray(roughness)),
+        }
+    }
+
+    fn ggx(&self, hit_record: &HitRecord) -> Ggx {
+        Ggx::from_roughness(hit_record.roughness(&*self.roughness))
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(
+        &self,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        Scatter::sample(self, r, hit_record, sampler)
+    }
+
+    fn pdf(&self, r: &Ray, hit_record: &HitRecord) -> Option<Box<dyn Pdf>> {
+        conductor_pdf(self.ggx(hit_record), Onb::from_w(&hit_record.normal), r)
+    }
+
+    fn eval(&self, r: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Vec3 {
+        let frame = Onb::from_w(&hit_record.normal);
+        // measured at the red, green and blue wavelengths
+        let (eta, k) = match &hit_record.wavelengths {
+            Some(wavelengths) => (
+                wavelengths.interpolate(self.eta),
+                wavelengths.interpolate(self.k),
+            ),
+            None => (self.eta, self.k),
+        };
+        let fresnel = |c| fresnel_conductor(c, eta, k);
+        conductor_eval(&self.ggx(hit_record), fresnel, &frame, r, direction)
+    }
+
+    fn glossy(&self, _hit_record: &HitRecord) -> bool {
+        true
+    }
+}
@@ -0,0 +1,62 @@
+use crate::Material;
+
+/// What fills a closed transparent object, as far as nesting is concerned.
+#[derive(Debug, Copy, Clone)]
+pub struct Medium {
+    pub ior: f64,
+    pub priority: u32,
+}
+
+/// The transparent objects a path is currently inside, innermost last, for
+/// priority-based nested dielectrics (Schmidt and Budge 2002).
+///
+/// Overlapping objects, like the water in a glass modeled slightly into the
+/// glass's walls, are resolved by priority: the surface of an object is
+/// ignored while inside an object of higher priority, and refraction at the
+/// surfaces that remain uses the index of the medium on the other side rather
+/// than assuming air. Objects are told apart by their material, so each
+/// object's material should be its own, though the surfaces of one object
+/// (like the inside and outside of a hollow sphere) may share it.
+#[derive(Default)]
+pub struct MediumStack {
+    entries: Vec<(*const u8, Medium)>,
+}
+
+fn id(material: &dyn Material) -> *const u8 {
+    material as *const dyn Material as *const u8
+}
+
+impl MediumStack {
+    /// Whether a surface of `material` is a real boundary, rather than one
+    /// inside an object of higher priority.
+    pub fn is_visible(&self, material: &dyn Material, medium: Medium) -> bool {
+        let this = id(material);
+        self.entries
+            .iter()
+            .all(|&(other, m)| other == this || m.priority <= medium.priority)
+    }
+
+    /// Index of refraction on the far side of a surface of `material`: the
+    /// highest priority medium the path is in other than its own, with ties
+    /// going to the innermost, or air.
+    pub fn exterior_ior(&self, material: &dyn Material) -> f64 {
+        let this = id(material);
+        self.entries
+            .iter()
+            .filter(|&&(other, _)| other != this)
+            // the last of equal maxima, i.e. the innermost
+            .max_by_key(|&&(_, m)| m.priority)
+            .map_or(1., |&(_, m)| m.ior)
+    }
+
+    /// Record the path passing through a surface of `material`, into the
+    /// object if `entering` and out of it otherwise.
+    pub fn cross(&mut self, material: &dyn Material, medium: Medium, entering: bool) {
+        let this = id(material);
+        if entering {
+            self.entries.push((this, medium));
+        } else if let Some(i) = self.entries.iter().rposition(|&(other, _)| other == this) {
+            self.entries.remove(i);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::bitmap::Bitmap;
+use crate::pdf::Pdf;
+use crate::sampler::Sampler;
+use crate::spectrum::Wavelengths;
+use crate::vec3::Vec3;
+use crate::{HitRecord, Material, Ray, Scatter};
+
+/// Wraps a material, bending its shading normal by a tangent-space normal
+/// map: each texel's RGB encodes a unit normal as `2 * rgb - 1`, in a frame
+/// of the surface tangent, bitangent and normal.
+pub struct NormalMapped {
+    pub map: Bitmap,
+    pub material: Box<dyn Material>,
+}
+
+impl NormalMapped {
+    fn shading_record<'a>(&self, hit_record: &HitRecord<'a>) -> HitRecord<'a> {
+        let texel = self.map.sample(hit_record.u, hit_record.v);
+        let local = 2. * texel - Vec3(1., 1., 1.);
+        let mut shading = *hit_record;
+        shading.normal = hit_record.frame().local(&local).unit();
+        shading
+    }
+}
+
+impl Material for NormalMapped {
+    fn scatter(
+        &self,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        self.material
+            .scatter(r, &self.shading_record(hit_record), sampler)
+    }
+
+    fn pdf(&self, r: &Ray, hit_record: &HitRecord) -> Option<Box<dyn Pdf>> {
+        self.material.pdf(r, &self.shading_record(hit_record))
+    }
+
+    fn eval(&self, r: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Vec3 {
+        self.material
+            .eval(r, &self.shading_record(hit_record), direction)
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Vec3) -> Vec3 {
+        self.material.emitted(u, v, p)
+    }
+
+    fn emitted_spectral(&self, u: f64, v: f64, p: &Vec3, wavelengths: &Wavelengths) -> Vec3 {
+        self.material.emitted_spectral(u, v, p, wavelengths)
+    }
+
+    fn alpha(&self, u: f64, v: f64, p: &Vec3) -> f64 {
+        self.material.alpha(u, v, p)
+    }
+
+    fn two_sided(&self) -> bool {
+        self.material.two_sided()
+    }
+
+    fn glossy(&self, hit_record: &HitRecord) -> bool {
+        self.material.glossy(hit_record)
+    }
+}
+
+/// Wraps a material, bending its shading normal along the slope of a
+/// grayscale height map (the luminance of `map`), as a cheap stand-in for
+/// real displacement. Slopes are taken by finite differences one texel apart
+/// and multiplied by `scale`, the height change per unit of u or v.
+pub struct BumpMapped {
+    pub map: Bitmap,
+    pub scale: f64,
+    pub material: Box<dyn Material>,
+}
+
+impl BumpMapped {
+    fn height(&self, u: f64, v: f64) -> f64 {
+        self.map.sample(u, v).luminance()
+    }
+
+    fn shading_record<'a>(&self, hit_record: &HitRecord<'a>) -> HitRecord<'a> {
+        let (u, v) = (hit_record.u, hit_record.v);
+        let du = 1. / self.map.width as f64;
+        let dv = 1. / self.map.height as f64;
+        let h = self.height(u, v);
+        let dh_du = self.scale * (self.height(u + du, v) - h) / du;
+        let dh_dv = self.scale * (self.height(u, v + dv) - h) / dv;
+        let mut shading = *hit_record;
+        shading.normal = hit_record.frame().local(&Vec3(-dh_du, -dh_dv, 1.)).unit();
+        shading
+    }
+}
+
+impl Material for BumpMapped {
+    fn scatter(
+        &self,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        self.material
+            .scatter(r, &self.shading_record(hit_record), sampler)
+    }
+
+    fn pdf(&self, r: &Ray, hit_record: &HitRecord) -> Option<Box<dyn Pdf>> {
+        self.material.pdf(r, &self.shading_record(hit_record))
+    }
+
+    fn eval(&self, r: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Vec3 {
+        self.material
+            .eval(r, &self.shading_record(hit_record), direction)
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Vec3) -> Vec3 {
+        self.material.emitted(u, v, p)
+    }
+
+    fn emitted_spectral(&self, u: f64, v: f64, p: &Vec3, wavelengths: &Wavelengths) -> Vec3 {
+        self.material.emitted_spectral(u, v, p, wavelengths)
+    }
+
+    fn alpha(&self, u: f64, v: f64, p: &Vec3) -> f64 {
+        self.material.alpha(u, v, p)
+    }
+
+    fn two_sided(&self) -> bool {
+        self.material.two_sided()
+    }
+
+    fn glossy(&self, hit_record: &HitRecord) -> bool {
+        self.material.glossy(hit_record)
+    }
+}
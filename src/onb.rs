@@ -0,0 +1,47 @@
+use crate::vec3::Vec3;
+
+/// An orthonormal basis, used to move directions between world space and a
+/// local shading frame where `w` is the surface normal.
+#[derive(Debug, Copy, Clone)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    pub fn from_w(n: &Vec3) -> Onb {
+        let w = n.unit();
+        let a = if w.x().abs() > 0.9 {
+            Vec3(0., 1., 0.)
+        } else {
+            Vec3(1., 0., 0.)
+        };
+        let v = w.cross(a).unit();
+        let u = w.cross(v);
+        Onb { u, v, w }
+    }
+
+    /// A basis with `w` along `n` and `u` along the part of `tangent`
+    /// perpendicular to it.
+    pub fn from_normal_tangent(n: &Vec3, tangent: &Vec3) -> Onb {
+        let w = n.unit();
+        let t = *tangent - tangent.dot(w) * w;
+        if t.squared_length() < 1e-12 {
+            return Onb::from_w(n);
+        }
+        let u = t.unit();
+        let v = w.cross(u);
+        Onb { u, v, w }
+    }
+
+    /// Local coordinates to world space.
+    pub fn local(&self, a: &Vec3) -> Vec3 {
+        a.x() * self.u + a.y() * self.v + a.z() * self.w
+    }
+
+    /// World space to local coordinates.
+    pub fn to_local(&self, a: &Vec3) -> Vec3 {
+        Vec3(a.dot(self.u), a.dot(self.v), a.dot(self.w))
+    }
+}
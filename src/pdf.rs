@@ -0,0 +1,115 @@
+use std::f64::consts::PI;
+
+use crate::onb::Onb;
+use crate::sampler::{cosine_direction, Sampler};
+use crate::vec3::Vec3;
+
+/// A probability density over directions, which can both be sampled and
+/// say how likely any direction is, so samples can be weighted by it and
+/// densities mixed.
+pub trait Pdf {
+    /// Density, per unit solid angle, of `direction`.
+    fn value(&self, direction: &Vec3) -> f64;
+
+    /// A direction drawn from the density with the next dimensions of
+    /// `sampler`.
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3;
+}
+
+/// Directions about `frame.w` proportional to the cosine of their angle to
+/// it, the density diffuse surfaces scatter with.
+pub struct CosinePdf {
+    pub frame: Onb,
+}
+
+impl CosinePdf {
+    pub fn new(normal: &Vec3) -> CosinePdf {
+        CosinePdf {
+            frame: Onb::from_w(normal),
+        }
+    }
+}
+
+impl Pdf for CosinePdf {
+    fn value(&self, direction: &Vec3) -> f64 {
+        (direction.unit().dot(self.frame.w) / PI).max(0.)
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        self.frame.local(&cosine_direction(sampler.get_2d()))
+    }
+}
+
+/// Directions evenly over the whole sphere, the way fog scatters light.
+pub struct SpherePdf;
+
+impl Pdf for SpherePdf {
+    fn value(&self, _direction: &Vec3) -> f64 {
+        1. / (4. * PI)
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        let (u, v) = sampler.get_2d();
+        let z = 1. - 2. * v;
+        let r = (1. - z * z).max(0.).sqrt();
+        let phi = 2. * PI * u;
+        Vec3(r * phi.cos(), r * phi.sin(), z)
+    }
+}
+
+/// Henyey and Greenstein's phase function: directions about the way light
+/// was going, `forward`, more or less spread by how far `g`, the average
+/// cosine of the angle turned, is from 0, the density fog scatters light
+/// with.
+pub struct HenyeyGreenstein {
+    pub g: f64,
+    pub forward: Vec3,
+}
+
+impl Pdf for HenyeyGreenstein {
+    fn value(&self, direction: &Vec3) -> f64 {
+        let g = self.g;
+        let cos_theta = direction.unit().dot(self.forward.unit());
+        let denom = 1. + g * g - 2. * g * cos_theta;
+        (1. - g * g) / (4. * PI * denom * denom.sqrt())
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        let (u, v) = sampler.get_2d();
+        let g = self.g;
+        let cos_theta = if g.abs() < 1e-3 {
+            1. - 2. * v
+        } else {
+            let s = (1. - g * g) / (1. - g + 2. * g * v);
+            (1. + g * g - s * s) / (2. * g)
+        };
+        let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+        let phi = 2. * PI * u;
+        Onb::from_w(&self.forward).local(&Vec3(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta.clamp(-1., 1.),
+        ))
+    }
+}
+
+/// Draws from `b` with probability `weight` and from `a` otherwise.
+pub struct MixturePdf {
+    pub a: Box<dyn Pdf>,
+    pub b: Box<dyn Pdf>,
+    pub weight: f64,
+}
+
+impl Pdf for MixturePdf {
+    fn value(&self, direction: &Vec3) -> f64 {
+        (1. - self.weight) * self.a.value(direction) + self.weight * self.b.value(direction)
+    }
+
+    fn generate(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        if sampler.get_1d() < self.weight {
+            self.b.generate(sampler)
+        } else {
+            self.a.generate(sampler)
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use crate::sampler::{RandomSampler, Sampler};
+use crate::vec3::Vec3;
+
+const POINT_COUNT: usize = 256;
+
+/// Ken Perlin's gradient noise: random unit vectors at the lattice points,
+/// blended with Hermite smoothing, giving a smooth pseudo-random value in
+/// roughly [-1, 1] that varies on the scale of one unit.
+pub struct Perlin {
+    ranvec: Vec<Vec3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+fn generate_perm(random: &mut dyn Sampler) -> Vec<usize> {
+    let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+    for i in (1..POINT_COUNT).rev() {
+        let target = (random.get_1d() * (i + 1) as f64) as usize;
+        p.swap(i, target);
+    }
+    p
+}
+
+impl Perlin {
+    /// Noise made from the random numbers of `random`, the same for the
+    /// same numbers.
+    pub fn new(random: &mut dyn Sampler) -> Perlin {
+        let ranvec = (0..POINT_COUNT)
+            .map(|_| {
+                (2.0 * Vec3(random.get_1d(), random.get_1d(), random.get_1d()) - Vec3(1., 1., 1.))
+                    .unit()
+            })
+            .collect();
+        Perlin {
+            ranvec,
+            perm_x: generate_perm(random),
+            perm_y: generate_perm(random),
+            perm_z: generate_perm(random),
+        }
+    }
+
+    pub fn noise(&self, p: &Vec3) -> f64 {
+        let u = p.x() - p.x().floor();
+        let v = p.y() - p.y().floor();
+        let w = p.z() - p.z().floor();
+        let i = p.x().floor() as i64;
+        let j = p.y().floor() as i64;
+        let k = p.z().floor() as i64;
+        let wrap = |n: i64| (n & (POINT_COUNT as i64 - 1)) as usize;
+
+        let uu = u * u * (3. - 2. * u);
+        let vv = v * v * (3. - 2. * v);
+        let ww = w * w * (3. - 2. * w);
+        let mut accum = 0.;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let c = self.ranvec[self.perm_x[wrap(i + di)]
+                        ^ self.perm_y[wrap(j + dj)]
+                        ^ self.perm_z[wrap(k + dk)]];
+                    let (fi, fj, fk) = (di as f64, dj as f64, dk as f64);
+                    let weight = Vec3(u - fi, v - fj, w - fk);
+                    accum += (fi * uu + (1. - fi) * (1. - uu))
+                        * (fj * vv + (1. - fj) * (1. - vv))
+                        * (fk * ww + (1. - fk) * (1. - ww))
+                        * c.dot(weight);
+                }
+            }
+        }
+        accum
+    }
+
+    /// Sum of `depth` octaves of noise, each at double the frequency and half
+    /// the amplitude of the last.
+    pub fn turb(&self, p: &Vec3, depth: usize) -> f64 {
+        let mut accum = 0.;
+        let mut temp_p = *p;
+        let mut weight = 1.;
+        for _ in 0..depth {
+            accum += weight * self.noise(&temp_p);
+            weight *= 0.5;
+            temp_p = temp_p * 2.;
+        }
+        accum.abs()
+    }
+}
+
+impl Default for Perlin {
+    fn default() -> Perlin {
+        Perlin::new(&mut RandomSampler::new(0, 0))
+    }
+}
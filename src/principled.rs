@@ -0,0 +1,224 @@
+use std::f64::consts::PI;
+
+use crate::microfacet::{fresnel_dielectric, fresnel_schlick, Ggx};
+use crate::onb::Onb;
+use crate::sampler::{cosine_direction, Sampler};
+use crate::sheen::charlie;
+use crate::spectrum::Wavelengths;
+use crate::texture::{ConstantTexture, Texture};
+use crate::vec3::Vec3;
+use crate::{HitRecord, Material, Ray, Scatter};
+
+/// A "principled" material after Burley's Disney BRDF: one set of intuitive
+/// parameters, all in [0, 1] except `ior`, covering diffuse, plastic, metal,
+/// fabric, lacquer and glass looks. Metalness, roughness and emission are
+/// textures so they can vary across a single object.
+pub struct Principled {
+    pub base_color: Box<dyn Texture>,
+    pub metallic: Box<dyn Texture>,
+    pub roughness: Box<dyn Texture>,
+    /// Specular reflectance of the dielectric base, where 0.5 is the usual 4%.
+    pub specular: f64,
+    /// Grazing-angle brightening for cloth.
+    pub sheen: f64,
+    pub sheen_roughness: f64,
+    /// Strength of a second, glossy, uncolored specular layer.
+    pub clearcoat: f64,
+    pub clearcoat_gloss: f64,
+    /// Fraction of the dielectric base that refracts rather than diffuses.
+    pub transmission: f64,
+    pub ior: f64,
+    /// Radiance given off by the surface, black unless it glows.
+    pub emission: Box<dyn Texture>,
+}
+
+impl Principled {
+    /// A rough plastic of the given color; override the other parameters with
+    /// struct update syntax.
+    pub fn new(base_color: Box<dyn Texture>) -> Principled {
+        Principled {
+            base_color,
+            metallic: Box::new(ConstantTexture::gray(0.)),
+            roughness: Box::new(ConstantTexture::gray(0.5)),
+            specular: 0.5,
+            sheen: 0.,
+            sheen_roughness: 0.5,
+            clearcoat: 0.,
+            clearcoat_gloss: 1.,
+            transmission: 0.,
+            ior: 1.5,
+            emission: Box::new(ConstantTexture::gray(0.)),
+        }
+    }
+
+    fn specular_f0(&self, base_color: Vec3, metallic: f64) -> Vec3 {
+        let dielectric = 0.08 * self.specular * Vec3(1., 1., 1.);
+        (1. - metallic) * dielectric + metallic * base_color
+    }
+
+    fn sample_diffuse(
+        &self,
+        wo: &Vec3,
+        s: &SurfaceParams,
+        sampler: &mut dyn Sampler,
+    ) -> (Vec3, Vec3) {
+        let wi = cosine_direction(sampler.get_2d());
+        let h = (*wo + wi).unit();
+        let cos_d = wi.dot(h);
+        let fd90 = 0.5 + 2. * s.roughness * cos_d * cos_d;
+        let fd = (1. + (fd90 - 1.) * schlick_weight(wi.z()))
+            * (1. + (fd90 - 1.) * schlick_weight(wo.z()));
+        // cosine sampling turns the diffuse term into fd * base color, and
+        // the sheen lobe's f * cos / pdf into pi * f
+        let sheen = PI * self.sheen * charlie(wo, &wi, self.sheen_roughness) * Vec3(1., 1., 1.);
+        let weight = (1. - s.metallic) * (1. - self.transmission);
+        (wi, weight * (fd * s.base_color + sheen))
+    }
+
+    fn sample_specular(
+        &self,
+        wo: &Vec3,
+        ggx: &Ggx,
+        f0: Vec3,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Vec3, Vec3)> {
+        let (u1, u2) = sampler.get_2d();
+        let h = ggx.sample_visible_normal(wo, u1, u2);
+        let wi = (-*wo).reflect(&h);
+        if wi.z() <= 0. {
+            return None;
+        }
+        let weight = ggx.g2(wo, &wi) / ggx.g1(wo);
+        Some((wi, fresnel_schlick(f0, wo.dot(h)) * weight))
+    }
+
+    /// Rough dielectric transmission; `entering` is false for rays leaving
+    /// the object, which refract without picking up the base color again.
+    fn sample_transmission(
+        &self,
+        wo: &Vec3,
+        s: &SurfaceParams,
+        entering: bool,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Vec3, Vec3)> {
+        let ggx = Ggx::from_roughness(s.roughness);
+        let eta = if entering { self.ior } else { 1. / self.ior };
+        let (u1, u2) = sampler.get_2d();
+        let h = ggx.sample_visible_normal(wo, u1, u2);
+        let fresnel = fresnel_dielectric(wo.dot(h), eta);
+        let (wi, tint) = if sampler.get_1d() < fresnel {
+            let wi = (-*wo).reflect(&h);
+            if wi.z() <= 0. {
+                return None;
+            }
+            (wi, Vec3(1., 1., 1.))
+        } else {
+            let wi = (-*wo).refract(&h, 1. / eta)?.unit();
+            if wi.z() >= 0. {
+                return None;
+            }
+            let tint = if entering {
+                s.base_color
+            } else {
+                Vec3(1., 1., 1.)
+            };
+            (wi, tint)
+        };
+        // choosing reflection or refraction by the Fresnel term cancels it
+        Some((wi, tint * (ggx.g2(wo, &wi) / ggx.g1(wo))))
+    }
+}
+
+/// The textured parameters looked up at one hit point.
+struct SurfaceParams {
+    base_color: Vec3,
+    metallic: f64,
+    roughness: f64,
+}
+
+fn schlick_weight(cos_theta: f64) -> f64 {
+    (1. - cos_theta).clamp(0., 1.).powi(5)
+}
+
+impl Material for Principled {
+    fn scatter(
+        &self,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        let d = r.direction().unit();
+        let entering = hit_record.front_face;
+        let frame = Onb::from_w(&hit_record.normal);
+        let wo = frame.to_local(&-d);
+        let s = SurfaceParams {
+            base_color: hit_record.texture(&*self.base_color),
+            metallic: hit_record.texture_scalar(&*self.metallic),
+            roughness: hit_record.roughness(&*self.roughness),
+        };
+
+        let sampled = if !entering {
+            // only the transmissive lobe can be hit from inside
+            self.sample_transmission(&wo, &s, false, sampler)
+        } else {
+            // pick one lobe with probability roughly proportional to its
+            // contribution and divide by that probability
+            let f0 = self.specular_f0(s.base_color, s.metallic);
+            let diffuse_w = (1. - s.metallic) * (1. - self.transmission) * s.base_color.luminance();
+            let specular_w = fresnel_schlick(f0, wo.z()).luminance();
+            let clearcoat_w = 0.25 * self.clearcoat * (0.04 + 0.96 * schlick_weight(wo.z()));
+            let transmission_w = (1. - s.metallic) * self.transmission;
+            let total = diffuse_w + specular_w + clearcoat_w + transmission_w;
+            if total <= 0. {
+                return None;
+            }
+
+            let x = sampler.get_1d() * total;
+            if x < diffuse_w {
+                let (wi, w) = self.sample_diffuse(&wo, &s, sampler);
+                Some((wi, w * (total / diffuse_w)))
+            } else if x < diffuse_w + specular_w {
+                let ggx = Ggx::from_roughness(s.roughness);
+                self.sample_specular(&wo, &ggx, f0, sampler)
+                    .map(|(wi, w)| (wi, w * (total / specular_w)))
+            } else if x < diffuse_w + specular_w + clearcoat_w {
+                let ggx = Ggx::isotropic(
+                    0.1 * (1. - self.clearcoat_gloss) + 0.001 * self.clearcoat_gloss,
+                );
+                self.sample_specular(&wo, &ggx, 0.04 * Vec3(1., 1., 1.), sampler)
+                    .map(|(wi, w)| (wi, 0.25 * self.clearcoat * w * (total / clearcoat_w)))
+            } else {
+                let weight = (1. - s.metallic) * self.transmission;
+                self.sample_transmission(&wo, &s, true, sampler)
+                    .map(|(wi, w)| (wi, weight * w * (total / transmission_w)))
+            }
+        };
+
+        sampled.map(|(wi, attenuation)| Scatter {
+            attenuation,
+            scattered: Ray {
+                a: hit_record.p,
+                b: frame.local(&wi),
+                time: r.time(),
+                wavelengths: r.wavelengths,
+            },
+            pdf: None,
+        })
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Vec3) -> Vec3 {
+        self.emission.value(u, v, p)
+    }
+
+    fn emitted_spectral(&self, u: f64, v: f64, p: &Vec3, wavelengths: &Wavelengths) -> Vec3 {
+        match self.emission.spectrum() {
+            Some(spectrum) => wavelengths.of(spectrum),
+            None => wavelengths.from_rgb(self.emission.value(u, v, p)),
+        }
+    }
+
+    /// Mostly, for metals.
+    fn glossy(&self, hit_record: &HitRecord) -> bool {
+        hit_record.texture_scalar(&*self.metallic) >= 0.5
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+use crate::{face_normal, HitRecord, Hittable, Material, Ray, PIXEL_SPREAD};
+
+/// A parallelogram with one corner at `corner` and sides along the edge
+/// vectors `u` and `v`, which (u, v) coordinates follow from 0 to 1. The front
+/// face is the side `u × v` points to. Rectangles in the XY, XZ or YZ planes
+/// are just quads with axis-aligned edges; walls, floors and area lights are
+/// the main uses.
+pub struct Quad {
+    pub corner: Vec3,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub material: Arc<dyn Material>,
+}
+
+impl Hittable for Quad {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let n = self.u.cross(self.v);
+        let denom = n.dot(*r.direction());
+        if denom.abs() < 1e-12 {
+            return None;
+        }
+        let t = n.dot(self.corner - *r.origin()) / denom;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+        let p = r.point_at_parameter(t);
+        // coordinates of p along the two edges, by Cramer's rule
+        let w = n / n.dot(n);
+        let planar = p - self.corner;
+        let alpha = w.dot(planar.cross(self.v));
+        let beta = w.dot(self.u.cross(planar));
+        if !(0. ..=1.).contains(&alpha) || !(0. ..=1.).contains(&beta) {
+            return None;
+        }
+        let (normal, front_face) = face_normal(r, n.unit());
+        let hit_record = HitRecord {
+            t,
+            p,
+            normal,
+            geometric_normal: normal,
+            front_face,
+            tangent: self.u,
+            u: alpha,
+            v: beta,
+            uv_width: t * r.direction().length() * PIXEL_SPREAD / self.u.length(),
+            exterior_ior: 1.,
+            min_roughness: 0.,
+            wavelengths: r.wavelengths,
+            material: &*self.material,
+        };
+        Some(hit_record).filter(HitRecord::opaque)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let c = self.corner;
+        Some(Aabb::from_points(&[
+            c,
+            c + self.u,
+            c + self.v,
+            c + self.u + self.v,
+        ]))
+    }
+
+    /// Points are picked evenly over the area.
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let ray = Ray {
+            a: *origin,
+            b: *direction,
+            time: 0.,
+            wavelengths: None,
+        };
+        match self.hit(&ray, 0.0001, f64::MAX) {
+            Some(hit_record) => {
+                let area = self.u.cross(self.v).length();
+                let distance_squared = hit_record.t * hit_record.t * direction.squared_length();
+                let cosine = (direction.dot(hit_record.normal) / direction.length()).abs();
+                distance_squared / (cosine * area)
+            }
+            None => 0.,
+        }
+    }
+
+    fn random(&self, origin: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let (a, b) = sampler.get_2d();
+        self.corner + a * self.u + b * self.v - *origin
+    }
+
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(HitRecord<'_>, f64)> {
+        let (a, b) = sampler.get_2d();
+        let n = self.u.cross(self.v);
+        let hit_record = HitRecord {
+            t: 0.,
+            p: self.corner + a * self.u + b * self.v,
+            normal: n.unit(),
+            geometric_normal: n.unit(),
+            front_face: true,
+            tangent: self.u,
+            u: a,
+            v: b,
+            uv_width: 0.,
+            exterior_ior: 1.,
+            min_roughness: 0.,
+            wavelengths: None,
+            material: &*self.material,
+        };
+        Some((hit_record, 1. / n.length()))
+    }
+
+    fn surface_pdf(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let ray = Ray {
+            a: *origin,
+            b: *direction,
+            time: 0.,
+            wavelengths: None,
+        };
+        match self.hit(&ray, 0.0001, f64::MAX) {
+            Some(_) => 1. / self.u.cross(self.v).length(),
+            None => 0.,
+        }
+    }
+}
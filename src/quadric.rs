@@ -0,0 +1,178 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::transform::Matrix4;
+use crate::vec3::Vec3;
+use crate::{face_normal, sphere_uv, HitRecord, Hittable, Material, Ray, PIXEL_SPREAD};
+
+/// A second-degree surface: the points p where x^T Q x = 0 for the
+/// homogeneous x = (p, 1) and a symmetric matrix Q, which covers ellipsoids,
+/// paraboloids, hyperboloids, cones and cylinders. Points where x^T Q x < 0
+/// are inside. Unbounded surfaces can be clipped to the box `bounds`.
+///
+/// (u, v) come from the direction of the normal, as on a sphere.
+pub struct Quadric {
+    pub coefficients: Matrix4,
+    pub bounds: Option<(Vec3, Vec3)>,
+    pub material: Arc<dyn Material>,
+}
+
+impl Quadric {
+    /// An ellipsoid about `center` with semi-axes `radii` along x, y and z.
+    pub fn ellipsoid(center: Vec3, radii: Vec3, material: Arc<dyn Material>) -> Quadric {
+        let mut quadric = Quadric::centered(center, radii, [1., 1., 1.], -1., material);
+        quadric.bounds = Some((center - radii, center + radii));
+        quadric
+    }
+
+    /// The bowl y = (x / a)^2 + (z / b)^2 opening upward from its lowest
+    /// point at `vertex`, for (a, b) = `radii`, cut off `height` above it.
+    pub fn paraboloid(
+        vertex: Vec3,
+        radii: (f64, f64),
+        height: f64,
+        material: Arc<dyn Material>,
+    ) -> Quadric {
+        let (a, b) = radii;
+        let mut q = [[0.; 4]; 4];
+        q[0][0] = 1. / (a * a);
+        q[2][2] = 1. / (b * b);
+        // -y, split between the symmetric entries
+        q[1][3] = -0.5;
+        q[3][1] = -0.5;
+        let translate = Matrix4::translation(-vertex);
+        let reach = Vec3(a, 0., b) * height.sqrt();
+        Quadric {
+            coefficients: translate.transpose() * Matrix4(q) * translate,
+            bounds: Some((vertex - reach, vertex + reach + Vec3(0., height, 0.))),
+            material,
+        }
+    }
+
+    /// A hyperboloid about `center` around the y axis, (x / rx)^2 +
+    /// (z / rz)^2 - (y / ry)^2 = 1 for `radii` (rx, ry, rz), of one sheet
+    /// like a cooling tower, cut off `half_height` above and below the
+    /// center.
+    pub fn hyperboloid(
+        center: Vec3,
+        radii: Vec3,
+        half_height: f64,
+        material: Arc<dyn Material>,
+    ) -> Quadric {
+        let mut quadric = Quadric::centered(center, radii, [1., -1., 1.], -1., material);
+        let y = half_height / radii.y();
+        let reach =
+            (1. + y * y).sqrt() * Vec3(radii.x(), 0., radii.z()) + Vec3(0., half_height, 0.);
+        quadric.bounds = Some((center - reach, center + reach));
+        quadric
+    }
+
+    /// sum of sign_i (x_i / radius_i)^2 + constant = 0, moved to `center`.
+    fn centered(
+        center: Vec3,
+        radii: Vec3,
+        signs: [f64; 3],
+        constant: f64,
+        material: Arc<dyn Material>,
+    ) -> Quadric {
+        let r = [radii.x(), radii.y(), radii.z()];
+        let mut q = [[0.; 4]; 4];
+        for i in 0..3 {
+            q[i][i] = signs[i] / (r[i] * r[i]);
+        }
+        q[3][3] = constant;
+        let translate = Matrix4::translation(-center);
+        Quadric {
+            coefficients: translate.transpose() * Matrix4(q) * translate,
+            bounds: None,
+            material,
+        }
+    }
+
+    /// Q x for x = (p, w).
+    fn apply(&self, p: &Vec3, w: f64) -> [f64; 4] {
+        let x = [p.x(), p.y(), p.z(), w];
+        let mut y = [0.; 4];
+        for (i, row) in self.coefficients.0.iter().enumerate() {
+            y[i] = row.iter().zip(&x).map(|(a, b)| a * b).sum();
+        }
+        y
+    }
+
+    fn inside_bounds(&self, p: &Vec3) -> bool {
+        match self.bounds {
+            Some((min, max)) => {
+                p.x() >= min.x()
+                    && p.y() >= min.y()
+                    && p.z() >= min.z()
+                    && p.x() <= max.x()
+                    && p.y() <= max.y()
+                    && p.z() <= max.z()
+            }
+            None => true,
+        }
+    }
+}
+
+fn dot4(a: [f64; 4], p: &Vec3, w: f64) -> f64 {
+    a[0] * p.x() + a[1] * p.y() + a[2] * p.z() + a[3] * w
+}
+
+impl Hittable for Quadric {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let (o, d) = (*r.origin(), *r.direction());
+        // (o + t d, 1)^T Q (o + t d, 1) = a t^2 + b t + c
+        let qd = self.apply(&d, 0.);
+        let a = dot4(qd, &d, 0.);
+        let b = 2. * dot4(qd, &o, 1.);
+        let c = dot4(self.apply(&o, 1.), &o, 1.);
+        let roots = if a.abs() < 1e-12 {
+            // the ray runs parallel to an asymptote and meets the surface once
+            if b == 0. {
+                return None;
+            }
+            [-c / b, f64::INFINITY]
+        } else {
+            let discriminant = b * b - 4. * a * c;
+            if discriminant < 0. {
+                return None;
+            }
+            // the numerically stable pair of quadratic roots
+            let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+            let (t0, t1) = (q / a, c / q);
+            [t0.min(t1), t0.max(t1)]
+        };
+
+        roots
+            .iter()
+            .filter(|&&t| t > t_min && t < t_max)
+            .map(|&t| (t, r.point_at_parameter(t)))
+            .filter(|(_, p)| self.inside_bounds(p))
+            .map(|(t, p)| {
+                let gradient = self.apply(&p, 1.);
+                let outward = Vec3(gradient[0], gradient[1], gradient[2]).unit();
+                let (normal, front_face) = face_normal(r, outward);
+                let (u, v) = sphere_uv(&outward);
+                HitRecord {
+                    t,
+                    p,
+                    normal,
+                    geometric_normal: normal,
+                    front_face,
+                    tangent: Vec3(outward.z(), 0., -outward.x()),
+                    u,
+                    v,
+                    uv_width: t * d.length() * PIXEL_SPREAD,
+                    exterior_ior: 1.,
+                    min_roughness: 0.,
+                    wavelengths: r.wavelengths,
+                    material: &*self.material,
+                }
+            })
+            .find(HitRecord::opaque)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.bounds.map(|(min, max)| Aabb { min, max })
+    }
+}
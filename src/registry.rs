@@ -0,0 +1,29 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::Material;
+
+/// Materials by name, so a scene can define each one once and hand the same
+/// instance to any number of objects.
+#[derive(Default)]
+pub struct MaterialRegistry {
+    materials: HashMap<String, Arc<dyn Material>>,
+}
+
+impl MaterialRegistry {
+    pub fn new() -> MaterialRegistry {
+        MaterialRegistry::default()
+    }
+
+    /// Register `material` under `name`, replacing any material already
+    /// registered with that name, and return a handle to it.
+    pub fn add<M: Material + 'static>(&mut self, name: &str, material: M) -> Arc<dyn Material> {
+        let material: Arc<dyn Material> = Arc::new(material);
+        self.materials.insert(name.to_string(), material.clone());
+        material
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Material>> {
+        self.materials.get(name).cloned()
+    }
+}
@@ -0,0 +1,338 @@
+use crate::camera::Camera;
+use crate::environment::Environment;
+use crate::sampler::{sample_disk, RandomSampler, Sampler};
+use crate::vec3::Vec3;
+use crate::{HitRecord, Hittable, Ray};
+
+/// Light samples a pixel's reservoir may stand for from the last frame,
+/// as a multiple of the candidates drawn this frame, so old picks can't
+/// crowd out new ones.
+const HISTORY: f64 = 20.;
+
+/// Direct lighting by reservoir-based spatiotemporal importance resampling
+/// (ReSTIR), after Bitterli et al. 2020 ("Spatiotemporal reservoir
+/// resampling for real-time ray tracing with dynamic direct lighting").
+/// Each pixel draws several points on the lights and keeps one, picked in
+/// proportion to how much light it would bring if nothing were in the way,
+/// in a reservoir that remembers how many it was picked from. Pixels then
+/// pick again among their own pick, their pick from the last frame and
+/// those of a few neighbours whose surfaces look alike, so each one ends up
+/// choosing among hundreds of points for the price of a few shadow rays;
+/// with many lights, the ones that matter are found far more often.
+///
+/// Only light reaching the first surface that spreads light over all
+/// directions is found, through mirrors and glass: from the lights by
+/// resampling, and from the sky by following the material. Emitters and
+/// the sky are seen directly too.
+pub struct Restir<'a> {
+    pub world: &'a dyn Hittable,
+    /// The emitters to pick points on, also in `world`.
+    pub lights: &'a dyn Hittable,
+    /// The light arriving from far away, seen by rays that miss everything.
+    pub environment: &'a dyn Environment,
+    /// Points each pixel draws on the lights every frame.
+    pub candidates: usize,
+    /// Neighbouring pixels each pixel picks among every frame, and how many
+    /// pixels away they may be.
+    pub neighbours: usize,
+    pub radius: f64,
+    /// Frames rendered and averaged, each picking up the picks of the last.
+    pub frames: usize,
+    /// Picks all the random numbers, so the same seed renders the same
+    /// image.
+    pub seed: u32,
+}
+
+/// A point on a light that a pixel picked.
+#[derive(Copy, Clone)]
+struct LightSample {
+    p: Vec3,
+    normal: Vec3,
+    emitted: Vec3,
+    two_sided: bool,
+}
+
+/// The first surface a pixel's ray meets that spreads light over all
+/// directions, `r` being the ray that hit it and `throughput` what light
+/// leaving it is multiplied by on its way to the camera.
+#[derive(Copy, Clone)]
+struct Surface<'a> {
+    hit: HitRecord<'a>,
+    r: Ray,
+    throughput: Vec3,
+    /// How far the ray went to get here, for telling which neighbours
+    /// look alike.
+    distance: f64,
+}
+
+/// One light sample picked from a stream of them, with the total weight of
+/// those seen and how many there were.
+#[derive(Copy, Clone, Default)]
+struct Reservoir {
+    sample: Option<LightSample>,
+    weight_sum: f64,
+    m: f64,
+    /// What the picked sample's light is multiplied by for an unbiased
+    /// estimate, standing in for one over its density.
+    weight: f64,
+}
+
+impl Reservoir {
+    /// Sees `m` samples of total weight `w` represented by `sample`,
+    /// keeping it with probability `w` over the weight seen so far.
+    fn update(&mut self, sample: LightSample, w: f64, m: f64, sampler: &mut dyn Sampler) {
+        self.weight_sum += w;
+        self.m += m;
+        if w > 0. && sampler.get_1d() * self.weight_sum < w {
+            self.sample = Some(sample);
+        }
+    }
+}
+
+impl<'a> Restir<'a> {
+    /// The radiance of each of `nx` by `ny` pixels, top row first, of what
+    /// `cam` sees.
+    pub fn render(&self, cam: &dyn Camera, nx: usize, ny: usize) -> Vec<Vec3> {
+        let sampler = &mut RandomSampler::new(self.seed as u64, 0);
+        let mut image = vec![Vec3(0., 0., 0.); nx * ny];
+        let mut previous: Vec<(Option<Surface>, Reservoir)> = Vec::new();
+        for _ in 0..self.frames.max(1) {
+            // where each pixel's ray lands and what it sees on the way
+            let mut surfaces = Vec::with_capacity(nx * ny);
+            for j in (0..ny).rev() {
+                for i in 0..nx {
+                    let u = (i as f64 + sampler.get_1d()) / nx as f64;
+                    let v = (j as f64 + sampler.get_1d()) / ny as f64;
+                    let (radiance, surface) = match cam.generate_ray(u, v, sampler) {
+                        Some(r) => self.primary(r, sampler),
+                        None => (Vec3(0., 0., 0.), None),
+                    };
+                    image[surfaces.len()] = image[surfaces.len()] + radiance;
+                    surfaces.push(surface);
+                }
+            }
+
+            // each pixel's own pick, joined with last frame's
+            let mut reservoirs: Vec<Reservoir> = surfaces
+                .iter()
+                .enumerate()
+                .map(|(k, surface)| {
+                    let surface = surface.as_ref()?;
+                    let reservoir = self.candidates(surface, sampler);
+                    match previous.get(k) {
+                        Some((Some(last), old)) if similar(surface, last) => {
+                            let old = Reservoir {
+                                m: old.m.min(HISTORY * reservoir.m),
+                                ..*old
+                            };
+                            let inputs = [(reservoir, *surface), (old, *last)];
+                            Some(self.combine(surface, &inputs, sampler))
+                        }
+                        _ => Some(reservoir),
+                    }
+                })
+                .map(Option::unwrap_or_default)
+                .collect();
+
+            // then with a few neighbours'
+            reservoirs = (0..nx * ny)
+                .map(|k| {
+                    let surface = match &surfaces[k] {
+                        Some(surface) => surface,
+                        None => return Reservoir::default(),
+                    };
+                    let mut inputs = vec![(reservoirs[k], *surface)];
+                    for _ in 0..self.neighbours {
+                        let (dx, dy) = sample_disk(sampler.get_2d());
+                        let x = (k % nx) as f64 + (dx * self.radius).round();
+                        let y = (k / nx) as f64 + (dy * self.radius).round();
+                        if x < 0. || y < 0. || x >= nx as f64 || y >= ny as f64 {
+                            continue;
+                        }
+                        let n = y as usize * nx + x as usize;
+                        match &surfaces[n] {
+                            Some(other) if n != k && similar(surface, other) => {
+                                inputs.push((reservoirs[n], *other));
+                            }
+                            _ => {}
+                        }
+                    }
+                    self.combine(surface, &inputs, sampler)
+                })
+                .collect();
+
+            // the light the picks bring
+            for (k, (surface, reservoir)) in surfaces.iter().zip(&reservoirs).enumerate() {
+                if let (Some(surface), Some(sample)) = (surface, reservoir.sample) {
+                    let light = self.contribution(surface, &sample) * reservoir.weight;
+                    image[k] = image[k] + surface.throughput * light;
+                }
+            }
+            previous = surfaces.into_iter().zip(reservoirs).collect();
+        }
+        let frames = self.frames.max(1) as f64;
+        image.into_iter().map(|c| c / frames).collect()
+    }
+
+    /// The light seen along camera ray `r` straight from emitters and the
+    /// sky, through mirrors and glass and from the sky onto the surface
+    /// they lead to, and that surface if it spreads light over all
+    /// directions.
+    fn primary(&self, mut r: Ray, sampler: &mut dyn Sampler) -> (Vec3, Option<Surface<'a>>) {
+        let mut radiance = Vec3(0., 0., 0.);
+        let mut throughput = Vec3(1., 1., 1.);
+        let mut distance = 0.;
+        for _ in 0..50 {
+            let hit = match self.world.hit(&r, 0.0001, f64::MAX) {
+                Some(hit) => hit,
+                None => return (radiance + throughput * self.environment.along(&r), None),
+            };
+            distance += hit.t * r.direction().length();
+            if !hit.front_face && !hit.material.two_sided() {
+                break;
+            }
+            let emitted = hit.material.emitted(hit.u, hit.v, &hit.p);
+            radiance = radiance + throughput * emitted;
+            let scatter = match hit.material.scatter(&r, &hit, sampler) {
+                Some(scatter) if hit.consistent(scatter.scattered.direction()) => scatter,
+                _ => break,
+            };
+            if scatter.pdf.is_some() {
+                // the sky's light, found by following the material
+                let weight = scatter.weight();
+                if self
+                    .world
+                    .hit(&scatter.scattered, 0.0001, f64::MAX)
+                    .is_none()
+                {
+                    let sky = self.environment.along(&scatter.scattered);
+                    radiance = radiance + throughput * weight * sky;
+                }
+                let surface = Surface {
+                    hit,
+                    r,
+                    throughput,
+                    distance,
+                };
+                return (radiance, Some(surface));
+            }
+            throughput = throughput * scatter.weight();
+            r = scatter.scattered;
+        }
+        (radiance, None)
+    }
+
+    /// A reservoir of `candidates` points drawn evenly over the lights,
+    /// one picked by how much light it would bring to `surface`.
+    fn candidates(&self, surface: &Surface, sampler: &mut dyn Sampler) -> Reservoir {
+        let mut reservoir = Reservoir::default();
+        for _ in 0..self.candidates {
+            let (hit, density) = match self.lights.sample_surface(sampler) {
+                Some(sample) => sample,
+                None => break,
+            };
+            let sample = LightSample {
+                p: hit.p,
+                normal: hit.normal,
+                emitted: hit.material.emitted(hit.u, hit.v, &hit.p),
+                two_sided: hit.material.two_sided(),
+            };
+            let w = self.target(surface, &sample) / density;
+            reservoir.update(sample, w, 1., sampler);
+        }
+        self.finish(surface, reservoir, reservoir.m)
+    }
+
+    /// One pick for `surface` among the picks of `inputs`, each made for
+    /// its own surface and weighted by how much light it brings to this
+    /// one. Only the inputs whose surface could have picked the winner,
+    /// with nothing in the way, count towards how many samples it was
+    /// picked from, so light one surface sees but another can't isn't
+    /// lost from shadow edges.
+    fn combine(
+        &self,
+        surface: &Surface,
+        inputs: &[(Reservoir, Surface)],
+        sampler: &mut dyn Sampler,
+    ) -> Reservoir {
+        let mut combined = Reservoir::default();
+        for (reservoir, _) in inputs {
+            if let Some(sample) = reservoir.sample {
+                let w = self.target(surface, &sample) * reservoir.weight * reservoir.m;
+                combined.update(sample, w, reservoir.m, sampler);
+            } else {
+                combined.m += reservoir.m;
+            }
+        }
+        let z = match &combined.sample {
+            Some(sample) => inputs
+                .iter()
+                .filter(|(_, other)| self.target(other, sample) > 0. && self.visible(other, sample))
+                .map(|(reservoir, _)| reservoir.m)
+                .sum(),
+            None => 0.,
+        };
+        self.finish(surface, combined, z)
+    }
+
+    /// `reservoir` with its weight for `surface` worked out, out of `z`
+    /// samples that could have been picked. Picks something blocks weigh
+    /// nothing, so no reservoir passes on light its surface can't see.
+    fn finish(&self, surface: &Surface, mut reservoir: Reservoir, z: f64) -> Reservoir {
+        reservoir.weight = match &reservoir.sample {
+            Some(sample) if z > 0. && self.visible(surface, sample) => {
+                let target = self.target(surface, sample);
+                if target > 0. {
+                    reservoir.weight_sum / (z * target)
+                } else {
+                    0.
+                }
+            }
+            _ => 0.,
+        };
+        reservoir
+    }
+
+    /// The light `sample` brings to `surface` if nothing is in the way,
+    /// per unit area of the light.
+    fn contribution(&self, surface: &Surface, sample: &LightSample) -> Vec3 {
+        let to_light = sample.p - surface.hit.p;
+        let distance_squared = to_light.squared_length();
+        let direction = to_light / distance_squared.sqrt();
+        let cosine = -direction.dot(sample.normal);
+        if distance_squared == 0. || (cosine <= 0. && !sample.two_sided) {
+            return Vec3(0., 0., 0.);
+        }
+        if !surface.hit.consistent(&direction) {
+            return Vec3(0., 0., 0.);
+        }
+        let bsdf = surface
+            .hit
+            .material
+            .eval(&surface.r, &surface.hit, &direction);
+        bsdf * sample.emitted * cosine.abs() / distance_squared
+    }
+
+    /// How much `sample` is worth picking for `surface`: the brightness of
+    /// the light it brings.
+    fn target(&self, surface: &Surface, sample: &LightSample) -> f64 {
+        self.contribution(surface, sample).luminance().max(0.)
+    }
+
+    /// Whether nothing blocks the way from `surface` to `sample`.
+    fn visible(&self, surface: &Surface, sample: &LightSample) -> bool {
+        let shadow_ray = Ray {
+            a: surface.hit.p,
+            b: sample.p - surface.hit.p,
+            time: surface.r.time(),
+            wavelengths: surface.r.wavelengths,
+        };
+        self.world.hit(&shadow_ray, 0.0001, 0.9999).is_none()
+    }
+}
+
+/// Whether two surfaces face about the same way and are about as far
+/// away, so the light one picks likely suits the other.
+fn similar(a: &Surface, b: &Surface) -> bool {
+    a.hit.normal.dot(b.hit.normal) > 0.9 && (a.distance - b.distance).abs() < 0.1 * a.distance
+}
@@ -0,0 +1,365 @@
+use crate::blue_noise;
+use crate::vec3::Vec3;
+
+/// A source of the numbers a sample is made from, one dimension at a time:
+/// where in the pixel, where on the lens, when in the shutter interval.
+pub trait Sampler {
+    /// A number in [0, 1) for the next dimension of the sample.
+    fn get_1d(&mut self) -> f64;
+
+    /// Numbers in [0, 1) for the next two dimensions of the sample.
+    fn get_2d(&mut self) -> (f64, f64) {
+        let u = self.get_1d();
+        (u, self.get_1d())
+    }
+}
+
+/// Independent uniform random numbers from O'Neill's PCG32 generator, the
+/// same numbers every time for the same seed, so renders repeat exactly.
+/// Generators on different streams give unrelated numbers from the same
+/// seed.
+#[derive(Copy, Clone, Debug)]
+pub struct RandomSampler {
+    state: u64,
+    increment: u64,
+}
+
+impl RandomSampler {
+    pub fn new(seed: u64, stream: u64) -> RandomSampler {
+        let mut random = RandomSampler {
+            state: 0,
+            increment: (stream << 1) | 1,
+        };
+        random.next_u32();
+        random.state = random.state.wrapping_add(seed);
+        random.next_u32();
+        random
+    }
+
+    /// The next 32 random bits.
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(self.increment);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+}
+
+impl Sampler for RandomSampler {
+    fn get_1d(&mut self) -> f64 {
+        unit(self.next_u32())
+    }
+}
+
+/// A low discrepancy sampler: the samples of a pixel come from the Sobol
+/// sequence, spread far more evenly than independent random numbers, so
+/// images converge faster. Each pair of dimensions draws the first two
+/// dimensions of the sequence with the points shuffled and Owen scrambled
+/// by a seed of its own and the pixel's, after Burley's "Practical
+/// Hash-based Owen Scrambling", so dimensions don't correlate with one
+/// another and neighbouring pixels' patterns don't line up.
+#[derive(Copy, Clone, Debug)]
+pub struct SobolSampler {
+    seed: u32,
+    index: u32,
+    dimension: u32,
+}
+
+impl SobolSampler {
+    /// The sampler for sample `index` of pixel (x, y), scrambled by `seed`.
+    pub fn new(seed: u32, (x, y): (u32, u32), index: u32) -> SobolSampler {
+        SobolSampler {
+            seed: hash(x ^ hash(y ^ hash(seed))),
+            index,
+            dimension: 0,
+        }
+    }
+
+    /// The seed for the next dimension, or pair of dimensions, and the
+    /// index of the point of the sequence it uses.
+    fn next(&mut self) -> (u32, u32) {
+        let seed = hash(self.seed ^ hash(self.dimension));
+        self.dimension += 1;
+        (seed, owen_scramble(self.index, seed))
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn get_1d(&mut self) -> f64 {
+        let (seed, index) = self.next();
+        unit(owen_scramble(index.reverse_bits(), hash(seed)))
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let (seed, index) = self.next();
+        let x = owen_scramble(index.reverse_bits(), hash(seed));
+        let y = owen_scramble(sobol_second(index), hash(seed ^ 1));
+        (unit(x), unit(y))
+    }
+}
+
+/// Which kind of sampler to render with.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SamplerKind {
+    Random,
+    Sobol,
+    Halton,
+    BlueNoise,
+}
+
+impl SamplerKind {
+    /// A sampler of this kind for sample `index` of `pixel`, its numbers
+    /// picked by `seed`.
+    pub fn sampler(self, seed: u32, pixel: (u32, u32), index: u32) -> Box<dyn Sampler> {
+        match self {
+            SamplerKind::Random => Box::new(RandomSampler::new(
+                seed as u64,
+                (hash(pixel.0 ^ hash(pixel.1)) as u64) << 32 | index as u64,
+            )),
+            SamplerKind::Sobol => Box::new(SobolSampler::new(seed, pixel, index)),
+            SamplerKind::Halton => Box::new(HaltonSampler::new(seed, pixel, index)),
+            SamplerKind::BlueNoise => Box::new(BlueNoiseSampler::new(seed, pixel, index)),
+        }
+    }
+}
+
+impl<S: Sampler + ?Sized> Sampler for Box<S> {
+    fn get_1d(&mut self) -> f64 {
+        (**self).get_1d()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (**self).get_2d()
+    }
+}
+
+/// Another low discrepancy sampler: dimension d of the samples of a pixel
+/// is the radical inverse of the sample's index in the d-th prime base, the
+/// Halton sequence. Every pixel would see the same points, so each shifts
+/// them by an offset of its own per dimension, wrapping around, which is
+/// Cranley-Patterson rotation.
+///
+/// In a big base the first few points all land near zero, so the digits
+/// are scrambled too, each multiplied by a number of its own modulo the
+/// base, after Atanassov. Dimensions past the table of primes, deep into
+/// long paths, are independent random numbers.
+#[derive(Copy, Clone, Debug)]
+pub struct HaltonSampler {
+    seed: u32,
+    index: u32,
+    dimension: usize,
+    random: RandomSampler,
+}
+
+impl HaltonSampler {
+    /// The sampler for sample `index` of pixel (x, y), shifted by `seed`.
+    pub fn new(seed: u32, (x, y): (u32, u32), index: u32) -> HaltonSampler {
+        let seed = hash(x ^ hash(y ^ hash(seed)));
+        HaltonSampler {
+            seed,
+            index,
+            dimension: 0,
+            random: RandomSampler::new(seed as u64, index as u64),
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.dimension;
+        self.dimension += 1;
+        match PRIMES.get(dimension) {
+            Some(&base) => {
+                let offset = unit(hash(self.seed ^ hash(dimension as u32)));
+                (radical_inverse(base, self.index, hash(dimension as u32)) + offset).fract()
+            }
+            None => self.random.get_1d(),
+        }
+    }
+}
+
+/// Sobol points shared by every pixel, each pixel shifting them by the
+/// value of a blue noise mask there, wrapping around, with the mask itself
+/// shifted by a different amount for each dimension. Neighbouring pixels'
+/// errors then differ as much as they can, so what noise is left at low
+/// sample counts is fine grained, easy on the eye and easy to filter out.
+#[derive(Copy, Clone, Debug)]
+pub struct BlueNoiseSampler {
+    sobol: SobolSampler,
+    pixel: (u32, u32),
+    seed: u32,
+    dimension: u32,
+}
+
+impl BlueNoiseSampler {
+    /// The sampler for sample `index` of `pixel`, the mask moved about by
+    /// `seed`.
+    pub fn new(seed: u32, pixel: (u32, u32), index: u32) -> BlueNoiseSampler {
+        BlueNoiseSampler {
+            sobol: SobolSampler {
+                seed: 0,
+                index,
+                dimension: 0,
+            },
+            pixel,
+            seed: hash(seed),
+            dimension: 0,
+        }
+    }
+
+    /// `x` shifted by the mask, moved about by `seed`.
+    fn shift(&self, x: f64, seed: u32) -> f64 {
+        let (mx, my) = (hash(seed), hash(seed ^ 1));
+        let offset =
+            blue_noise::value(self.pixel.0.wrapping_add(mx), self.pixel.1.wrapping_add(my));
+        (x + offset).fract()
+    }
+}
+
+impl Sampler for BlueNoiseSampler {
+    fn get_1d(&mut self) -> f64 {
+        let seed = hash(self.dimension ^ self.seed);
+        self.dimension += 1;
+        let x = self.sobol.get_1d();
+        self.shift(x, seed)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let seed = hash(self.dimension ^ self.seed);
+        self.dimension += 1;
+        let (x, y) = self.sobol.get_2d();
+        (self.shift(x, seed), self.shift(y, hash(seed)))
+    }
+}
+
+/// The digits of `index` in `base` mirrored about the point, as a
+/// fraction, each multiplied by a nonzero number modulo the base that
+/// `seed` and its place pick, which keeps zeros zero and the others
+/// distinct.
+fn radical_inverse(base: u32, mut index: u32, seed: u32) -> f64 {
+    let inverse = 1. / base as f64;
+    let mut place = inverse;
+    let mut x = 0.;
+    let mut i = 0;
+    while index > 0 {
+        let multiplier = 1 + hash(seed ^ hash(i)) % (base - 1);
+        let digit = index % base * multiplier % base;
+        x += digit as f64 * place;
+        index /= base;
+        place *= inverse;
+        i += 1;
+    }
+    x
+}
+
+/// The bases of the Halton sequence's dimensions.
+const PRIMES: [u32; 128] = primes();
+
+const fn primes<const N: usize>() -> [u32; N] {
+    let mut primes = [0; N];
+    let mut found = 0;
+    let mut n = 2;
+    while found < N {
+        let mut i = 0;
+        while i < found && n % primes[i] != 0 {
+            i += 1;
+        }
+        if i == found {
+            primes[found] = n;
+            found += 1;
+        }
+        n += 1;
+    }
+    primes
+}
+
+/// The second dimension of the Sobol sequence at `index`, as 32 bits of
+/// fraction; the first is just `index` with its bits reversed.
+fn sobol_second(index: u32) -> u32 {
+    let mut direction = 1 << 31;
+    let mut x = 0;
+    for bit in 0..32 {
+        if index & (1 << bit) != 0 {
+            x ^= direction;
+        }
+        direction ^= direction >> 1;
+    }
+    x
+}
+
+/// A random permutation of the 32 bit fractions in [0, 1) that keeps
+/// points in the same halves, quarters, eighths and so on of the interval
+/// apart as they were, so a well spread sequence stays well spread.
+fn owen_scramble(x: u32, seed: u32) -> u32 {
+    // Laine and Karras's hash, as improved by Burley, mixes each bit only
+    // with the bits below it: applied to the bits reversed it scrambles
+    // each bit by the ones above it, as Owen scrambling does
+    let mut x = x.reverse_bits();
+    x ^= x.wrapping_mul(0x3d20adea);
+    x = x.wrapping_add(seed);
+    x = x.wrapping_mul((seed >> 16) | 1);
+    x ^= x.wrapping_mul(0x05526c56);
+    x ^= x.wrapping_mul(0x53a22864);
+    x.reverse_bits()
+}
+
+/// A well mixed hash of `x`.
+fn hash(mut x: u32) -> u32 {
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846ca68b);
+    x ^ (x >> 16)
+}
+
+/// A 32 bit fraction as a number in [0, 1).
+fn unit(x: u32) -> f64 {
+    x as f64 / (1u64 << 32) as f64
+}
+
+/// A number in [0, 1) that looks random but is the same every time for
+/// point `p`, for choices made where there is no sampler to hand.
+pub fn random_at(p: &Vec3) -> f64 {
+    let bits = [p.x(), p.y(), p.z()].map(f64::to_bits);
+    unit(
+        bits.iter()
+            .fold(0, |h, &b| hash(hash(h ^ b as u32) ^ (b >> 32) as u32)),
+    )
+}
+
+/// A point in the unit disk from a point `(u, v)` in the unit square,
+/// spread evenly by the concentric mapping, which keeps nearby points
+/// nearby.
+pub fn sample_disk((u, v): (f64, f64)) -> (f64, f64) {
+    let (x, y) = (2. * u - 1., 2. * v - 1.);
+    if x == 0. && y == 0. {
+        return (0., 0.);
+    }
+    let quarter = std::f64::consts::FRAC_PI_4;
+    let (r, theta) = if x.abs() > y.abs() {
+        (x, quarter * (y / x))
+    } else {
+        (y, 2. * quarter - quarter * (x / y))
+    };
+    (r * theta.cos(), r * theta.sin())
+}
+
+/// A direction about +z from a point `(u, v)` in the unit square,
+/// distributed proportionally to the cosine of its angle to the axis.
+pub fn cosine_direction((u, v): (f64, f64)) -> Vec3 {
+    let phi = 2. * std::f64::consts::PI * u;
+    let r = v.sqrt();
+    Vec3(phi.cos() * r, phi.sin() * r, (1. - v).sqrt())
+}
+
+/// A point in the unit ball from a point `(u, v)` in the unit square,
+/// which picks the direction, and `w` in [0, 1), which picks the distance
+/// from the center, spread evenly through the ball.
+pub fn sample_ball((u, v): (f64, f64), w: f64) -> Vec3 {
+    let z = 1. - 2. * u;
+    let r = (1. - z * z).max(0.).sqrt();
+    let phi = 2. * std::f64::consts::PI * v;
+    w.cbrt() * Vec3(r * phi.cos(), r * phi.sin(), z)
+}
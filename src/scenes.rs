@@ -0,0 +1,709 @@
+use std::io;
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::camera::{Camera, Exposure, Perspective};
+use crate::cuboid::Cuboid;
+use crate::environment::{Environment, Gradient, Portal, Solid};
+use crate::fog::Fog;
+use crate::grid::Grid;
+use crate::light::Light;
+use crate::perlin::Perlin;
+use crate::quad::Quad;
+use crate::sampler::{RandomSampler, Sampler};
+use crate::sky::{PhysicalSky, SkyModelData};
+use crate::texture::{CheckerTexture, CloudTexture, ConstantTexture, MarbleTexture, NoiseTexture};
+use crate::transform::{Moving, RotateY, Translate};
+use crate::vec3::Vec3;
+use crate::volume::{ConstantMedium, Isotropic};
+use crate::{Dielectric, DiffuseLight, Hittable, Lambertian, Material, Metal, Sphere, World};
+
+/// Everything needed to render a picture: what to look at and from where.
+pub struct Scene {
+    pub world: World,
+    /// The emitters in `world` worth aiming rays at directly.
+    pub lights: World,
+    pub camera: Box<dyn Camera>,
+    pub accelerator: Accelerator,
+    /// How the camera is exposed, for scenes lit in physical units. None
+    /// takes radiance as the pixel value, for scenes lit in relative units.
+    pub exposure: Option<Exposure>,
+    /// Lights with no size, which light `world` without being in it.
+    pub delta_lights: Vec<Box<dyn Light>>,
+    /// The light arriving from far away, seen by rays that miss everything.
+    pub environment: Box<dyn Environment>,
+    /// Openings the environment lights `world` through, if it is lit from
+    /// inside a room.
+    pub portals: Vec<Portal>,
+    /// Fog filling the air, if any; only the path tracer sees it.
+    pub fog: Option<Fog>,
+}
+
+/// How a scene's objects are organized for finding what a ray hits.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Accelerator {
+    /// Test every object; fine for a handful.
+    None,
+    Bvh,
+    /// A BVH allowed to split space through big objects.
+    SpatialBvh,
+    KdTree,
+    Grid,
+}
+
+impl Accelerator {
+    /// `world` with its objects organized this way.
+    pub fn apply(self, world: World) -> World {
+        match self {
+            Accelerator::None => world,
+            Accelerator::Bvh => world.into_bvh(),
+            Accelerator::SpatialBvh => world.into_spatial_bvh(),
+            Accelerator::KdTree => world.into_kd_tree(),
+            Accelerator::Grid => world.into_grid(),
+        }
+    }
+}
+
+/// The names `by_name` knows, the first being the default.
+pub const NAMES: [&str; 12] = [
+    "spheres",
+    "daylight",
+    "cloud",
+    "cover",
+    "cornell",
+    "smoke",
+    "textures",
+    "particles",
+    "motion",
+    "lanterns",
+    "interior",
+    "hazy",
+];
+
+/// The scene called `name`, framed for images `aspect` times wider than
+/// they are high.
+pub fn by_name(name: &str, aspect: f64) -> Option<Scene> {
+    match name {
+        "spheres" => Some(spheres(aspect)),
+        "daylight" => Some(daylight(aspect)),
+        "cloud" => Some(cloud(aspect)),
+        "cover" => Some(cover(aspect)),
+        "cornell" => Some(cornell_box(aspect)),
+        "smoke" => Some(cornell_smoke(aspect)),
+        "textures" => Some(textures(aspect)),
+        "particles" => Some(particles(aspect)),
+        "motion" => Some(motion(aspect)),
+        "lanterns" => Some(lanterns(aspect)),
+        "interior" => Some(interior(aspect)),
+        "hazy" => Some(hazy(aspect)),
+        _ => None,
+    }
+}
+
+fn lambertian(color: Vec3) -> Arc<dyn Material> {
+    Arc::new(Lambertian {
+        albedo: Box::new(ConstantTexture { color }),
+    })
+}
+
+fn metal(color: Vec3, fuzz: f64) -> Arc<dyn Material> {
+    Arc::new(Metal {
+        albedo: Box::new(ConstantTexture { color }),
+        fuzz: Box::new(ConstantTexture::gray(fuzz)),
+        film: None,
+    })
+}
+
+fn glass() -> Arc<dyn Material> {
+    Arc::new(Dielectric {
+        ref_idx: 1.5,
+        dispersion: 0.,
+        film: None,
+        priority: 0,
+    })
+}
+
+fn light(color: Vec3) -> Arc<dyn Material> {
+    Arc::new(DiffuseLight {
+        emit: Box::new(ConstantTexture { color }),
+    })
+}
+
+/// Where the Hosek-Wilkie sky's coefficient tables are looked for.
+const SKY_DATA: &str = "ArHosekSkyModelData_RGB.h";
+
+/// The Hosek-Wilkie sky if its tables are in `SKY_DATA`, or else, if
+/// there is no such file, Preetham's, with the sun and air as for
+/// `PhysicalSky::hosek_wilkie`. Exits if the file is there but can't be
+/// read.
+fn sky(elevation: f64, azimuth: f64, turbidity: f64, albedo: f64) -> PhysicalSky {
+    match SkyModelData::load(SKY_DATA) {
+        Ok(data) => PhysicalSky::hosek_wilkie(&data, elevation, azimuth, turbidity, albedo),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            PhysicalSky::new(elevation, azimuth, turbidity)
+        }
+        Err(e) => {
+            eprintln!("couldn't load sky model tables {}: {}", SKY_DATA, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn sphere(center: Vec3, radius: f64, material: Arc<dyn Material>) -> Box<dyn Hittable> {
+    Box::new(Sphere {
+        center,
+        radius,
+        material,
+    })
+}
+
+/// Diffuse, metal and hollow glass spheres side by side on a big yellow one.
+pub fn spheres(aspect: f64) -> Scene {
+    let glass = glass();
+    Scene {
+        world: World {
+            hittables: vec![
+                sphere(Vec3(0., 0., -1.), 0.5, lambertian(Vec3(0.8, 0.3, 0.3))),
+                sphere(Vec3(0., -100.5, -1.), 100., lambertian(Vec3(0.8, 0.8, 0.))),
+                sphere(Vec3(1., 0., -1.), 0.5, metal(Vec3(0.8, 0.6, 0.2), 0.)),
+                // hollow glass sphere: the negative radius flips the inner
+                // normals
+                sphere(Vec3(-1., 0., -1.), 0.5, glass.clone()),
+                sphere(Vec3(-1., 0., -1.), -0.45, glass),
+            ],
+        },
+        accelerator: Accelerator::Bvh,
+        exposure: None,
+        delta_lights: Vec::new(),
+        environment: Box::new(Gradient::sky()),
+        portals: Vec::new(),
+        fog: None,
+        lights: World::default(),
+        camera: Box::new(Perspective::new(
+            Vec3(0., 0., 0.),
+            Vec3(0., 0., -1.),
+            Vec3(0., 1., 0.),
+            90.,
+            aspect,
+            0.,
+            1.,
+        )),
+    }
+}
+
+/// The same spheres outdoors in the afternoon sun, exposed as a photograph
+/// would be.
+pub fn daylight(aspect: f64) -> Scene {
+    Scene {
+        exposure: Some(Exposure::sunny_16(100.)),
+        environment: Box::new(sky(0.6, 2., 3., 0.5)),
+        ..spheres(aspect)
+    }
+}
+
+/// The daylit spheres with a cloud drifting over them, a ball of fog
+/// billowing out at its edges.
+pub fn cloud(aspect: f64) -> Scene {
+    let center = Vec3(0.3, 1.4, -2.5);
+    let radius = 1.2;
+    Scene {
+        fog: Some(Fog {
+            absorption: Vec3(0., 0., 0.),
+            scattering: Vec3(8., 8., 8.),
+            density: Some(Box::new(CloudTexture {
+                noise: Perlin::new(&mut RandomSampler::new(0, 2)),
+                scale: 1.5,
+                center,
+                radius,
+            })),
+            g: 0.5,
+            bounds: Some(Aabb {
+                min: center - radius * Vec3(1., 1., 1.),
+                max: center + radius * Vec3(1., 1., 1.),
+            }),
+        }),
+        ..daylight(aspect)
+    }
+}
+
+/// The cover of "Ray Tracing in One Weekend": hundreds of small random
+/// spheres around three big ones, focused about the big ones.
+pub fn cover(aspect: f64) -> Scene {
+    let mut hittables = vec![sphere(
+        Vec3(0., -1000., 0.),
+        1000.,
+        lambertian(Vec3(0.5, 0.5, 0.5)),
+    )];
+    let glass = glass();
+    // the same spheres every time
+    let mut rng = RandomSampler::new(0, 0);
+    let mut random = || rng.get_1d();
+    for a in -11..11 {
+        for b in -11..11 {
+            let center = Vec3(a as f64 + 0.9 * random(), 0.2, b as f64 + 0.9 * random());
+            if (center - Vec3(4., 0.2, 0.)).length() <= 0.9 {
+                continue;
+            }
+            let choose = random();
+            let material = if choose < 0.8 {
+                lambertian(Vec3(
+                    random() * random(),
+                    random() * random(),
+                    random() * random(),
+                ))
+            } else if choose < 0.95 {
+                metal(
+                    Vec3(
+                        0.5 * (1. + random()),
+                        0.5 * (1. + random()),
+                        0.5 * (1. + random()),
+                    ),
+                    0.5 * random(),
+                )
+            } else {
+                glass.clone()
+            };
+            hittables.push(sphere(center, 0.2, material));
+        }
+    }
+    hittables.push(sphere(Vec3(0., 1., 0.), 1., glass));
+    hittables.push(sphere(
+        Vec3(-4., 1., 0.),
+        1.,
+        lambertian(Vec3(0.4, 0.2, 0.1)),
+    ));
+    hittables.push(sphere(Vec3(4., 1., 0.), 1., metal(Vec3(0.7, 0.6, 0.5), 0.)));
+    Scene {
+        world: World { hittables },
+        accelerator: Accelerator::Bvh,
+        exposure: None,
+        delta_lights: Vec::new(),
+        environment: Box::new(Gradient::sky()),
+        portals: Vec::new(),
+        fog: None,
+        lights: World::default(),
+        camera: Box::new(Perspective::new(
+            Vec3(13., 2., 3.),
+            Vec3(0., 0., 0.),
+            Vec3(0., 1., 0.),
+            20.,
+            aspect,
+            0.1,
+            10.,
+        )),
+    }
+}
+
+/// The Cornell box: a 555 unit room with a green left wall, a red right wall
+/// and a square light in the ceiling, holding two white blocks.
+///
+/// The room is closed behind the camera so the sky doesn't light it.
+pub fn cornell_box(aspect: f64) -> Scene {
+    let red = lambertian(Vec3(0.65, 0.05, 0.05));
+    let white = lambertian(Vec3(0.73, 0.73, 0.73));
+    let green = lambertian(Vec3(0.12, 0.45, 0.15));
+    let (front, depth) = (-801., 555. + 801.);
+    let wall = |corner, u, v, material: &Arc<dyn Material>| -> Box<dyn Hittable> {
+        Box::new(Quad {
+            corner,
+            u,
+            v,
+            material: material.clone(),
+        })
+    };
+    let block = |size: Vec3, degrees, offset| -> Box<dyn Hittable> {
+        Box::new(Translate {
+            object: Box::new(RotateY {
+                object: Box::new(Cuboid {
+                    min: Vec3(0., 0., 0.),
+                    max: size,
+                    material: white.clone(),
+                }),
+                degrees,
+            }),
+            offset,
+        })
+    };
+    let lamp = Arc::new(Quad {
+        corner: Vec3(343., 554., 332.),
+        u: Vec3(-130., 0., 0.),
+        v: Vec3(0., 0., -105.),
+        material: light(Vec3(15., 15., 15.)),
+    });
+    let hittables = vec![
+        wall(
+            Vec3(555., 0., front),
+            Vec3(0., 555., 0.),
+            Vec3(0., 0., depth),
+            &green,
+        ),
+        wall(
+            Vec3(0., 0., front),
+            Vec3(0., 555., 0.),
+            Vec3(0., 0., depth),
+            &red,
+        ),
+        Box::new(lamp.clone()),
+        wall(
+            Vec3(0., 0., front),
+            Vec3(555., 0., 0.),
+            Vec3(0., 0., depth),
+            &white,
+        ),
+        wall(
+            Vec3(0., 555., front),
+            Vec3(555., 0., 0.),
+            Vec3(0., 0., depth),
+            &white,
+        ),
+        wall(
+            Vec3(0., 0., 555.),
+            Vec3(555., 0., 0.),
+            Vec3(0., 555., 0.),
+            &white,
+        ),
+        wall(
+            Vec3(0., 0., front),
+            Vec3(555., 0., 0.),
+            Vec3(0., 555., 0.),
+            &white,
+        ),
+        block(Vec3(165., 330., 165.), 15., Vec3(265., 0., 295.)),
+        block(Vec3(165., 165., 165.), -18., Vec3(130., 0., 65.)),
+    ];
+    Scene {
+        world: World { hittables },
+        accelerator: Accelerator::Bvh,
+        exposure: None,
+        delta_lights: Vec::new(),
+        environment: Box::new(Gradient::sky()),
+        portals: Vec::new(),
+        fog: None,
+        lights: World {
+            hittables: vec![Box::new(lamp)],
+        },
+        camera: Box::new(Perspective::new(
+            Vec3(278., 278., -800.),
+            Vec3(278., 278., 0.),
+            Vec3(0., 1., 0.),
+            40.,
+            aspect,
+            0.,
+            1.,
+        )),
+    }
+}
+
+/// The Cornell box with its blocks made of smoke, one dark and one light,
+/// as in "Ray Tracing: The Next Week".
+pub fn cornell_smoke(aspect: f64) -> Scene {
+    let mut scene = cornell_box(aspect);
+    let smoke = |size: Vec3, degrees, offset, color| -> Box<dyn Hittable> {
+        Box::new(ConstantMedium {
+            boundary: Box::new(Translate {
+                object: Box::new(RotateY {
+                    object: Box::new(Cuboid {
+                        min: Vec3(0., 0., 0.),
+                        max: size,
+                        material: lambertian(color),
+                    }),
+                    degrees,
+                }),
+                offset,
+            }),
+            density: 0.01,
+            phase_function: Arc::new(Isotropic {
+                albedo: Box::new(ConstantTexture { color }),
+            }),
+        })
+    };
+    // in place of the two blocks
+    let hittables = &mut scene.world.hittables;
+    hittables.truncate(hittables.len() - 2);
+    hittables.push(smoke(
+        Vec3(165., 330., 165.),
+        15.,
+        Vec3(265., 0., 295.),
+        Vec3(0., 0., 0.),
+    ));
+    hittables.push(smoke(
+        Vec3(165., 165., 165.),
+        -18.,
+        Vec3(130., 0., 65.),
+        Vec3(1., 1., 1.),
+    ));
+    scene
+}
+
+/// Procedural textures: a checkered ground under a marble and a noise
+/// sphere.
+pub fn textures(aspect: f64) -> Scene {
+    let checker = Arc::new(Lambertian {
+        albedo: Box::new(CheckerTexture {
+            odd: Box::new(ConstantTexture {
+                color: Vec3(0.2, 0.3, 0.1),
+            }),
+            even: Box::new(ConstantTexture {
+                color: Vec3(0.9, 0.9, 0.9),
+            }),
+            scale: 1.,
+        }),
+    });
+    let marble = Arc::new(Lambertian {
+        albedo: Box::new(MarbleTexture {
+            noise: Perlin::new(&mut RandomSampler::new(0, 0)),
+            scale: 4.,
+            color: Vec3(1., 1., 1.),
+        }),
+    });
+    let noise = Arc::new(Lambertian {
+        albedo: Box::new(NoiseTexture {
+            noise: Perlin::new(&mut RandomSampler::new(0, 1)),
+            scale: 4.,
+        }),
+    });
+    Scene {
+        world: World {
+            hittables: vec![
+                sphere(Vec3(0., -1000., 0.), 1000., checker),
+                sphere(Vec3(-1.1, 1., 0.), 1., marble),
+                sphere(Vec3(1.1, 1., 0.), 1., noise),
+            ],
+        },
+        accelerator: Accelerator::Bvh,
+        exposure: None,
+        delta_lights: Vec::new(),
+        environment: Box::new(Gradient::sky()),
+        portals: Vec::new(),
+        fog: None,
+        lights: World::default(),
+        camera: Box::new(Perspective::new(
+            Vec3(0., 2., 8.),
+            Vec3(0., 1., 0.),
+            Vec3(0., 1., 0.),
+            30.,
+            aspect,
+            0.,
+            1.,
+        )),
+    }
+}
+
+/// A cloud of thousands of tiny spheres hanging over the ground, spread
+/// evenly enough for a uniform grid to suit them. The grid covers just the
+/// cloud, since the huge ground sphere would stretch its cells.
+pub fn particles(aspect: f64) -> Scene {
+    let mut rng = RandomSampler::new(0, 0);
+    let mut random = || rng.get_1d();
+    let cloud = (0..5000)
+        .map(|_| {
+            let center = Vec3(4. * random() - 2., 0.5 + 3. * random(), 4. * random() - 2.);
+            let color = Vec3(
+                0.3 + 0.7 * random(),
+                0.3 + 0.7 * random(),
+                0.3 + 0.7 * random(),
+            );
+            sphere(center, 0.04, lambertian(color))
+        })
+        .collect();
+    Scene {
+        world: World {
+            hittables: vec![
+                sphere(Vec3(0., -1000., 0.), 1000., lambertian(Vec3(0.5, 0.5, 0.5))),
+                Box::new(Grid::new(cloud)),
+            ],
+        },
+        accelerator: Accelerator::None,
+        exposure: None,
+        delta_lights: Vec::new(),
+        environment: Box::new(Gradient::sky()),
+        portals: Vec::new(),
+        fog: None,
+        lights: World::default(),
+        camera: Box::new(Perspective::new(
+            Vec3(0., 3., 9.),
+            Vec3(0., 2., 0.),
+            Vec3(0., 1., 0.),
+            35.,
+            aspect,
+            0.,
+            1.,
+        )),
+    }
+}
+
+/// A row of spheres rising faster from left to right, caught mid-flight by
+/// a shutter open for one unit of time.
+pub fn motion(aspect: f64) -> Scene {
+    let mut hittables = vec![sphere(
+        Vec3(0., -1000., 0.),
+        1000.,
+        lambertian(Vec3(0.5, 0.5, 0.5)),
+    )];
+    for i in 0..5 {
+        let x = i as f64 - 2.;
+        hittables.push(Box::new(Moving {
+            object: sphere(Vec3(x, 0.4, 0.), 0.4, lambertian(Vec3(0.8, 0.3, 0.2))),
+            velocity: Vec3(0., 0.15 * i as f64, 0.),
+        }));
+    }
+    let mut camera = Perspective::new(
+        Vec3(0., 1., 6.),
+        Vec3(0., 0.6, 0.),
+        Vec3(0., 1., 0.),
+        40.,
+        aspect,
+        0.,
+        1.,
+    );
+    camera.pose.shutter_close = 1.;
+    Scene {
+        world: World { hittables },
+        accelerator: Accelerator::Bvh,
+        exposure: None,
+        delta_lights: Vec::new(),
+        environment: Box::new(Gradient::sky()),
+        portals: Vec::new(),
+        fog: None,
+        lights: World::default(),
+        camera: Box::new(camera),
+    }
+}
+
+/// Hundreds of small colored lanterns of all brightnesses floating over a
+/// field of spheres at night, for sampling many lights.
+pub fn lanterns(aspect: f64) -> Scene {
+    let mut rng = RandomSampler::new(0, 0);
+    let mut random = || rng.get_1d();
+    let mut hittables = vec![sphere(
+        Vec3(0., -1000., 0.),
+        1000.,
+        lambertian(Vec3(0.5, 0.5, 0.5)),
+    )];
+    for i in -4..=4 {
+        for j in -4..=1 {
+            let center = Vec3(2. * i as f64, 0.5, 2. * j as f64);
+            hittables.push(sphere(center, 0.5, lambertian(Vec3(0.7, 0.7, 0.7))));
+        }
+    }
+    let mut lights = Vec::new();
+    for _ in 0..500 {
+        let center = Vec3(
+            24. * random() - 12.,
+            1.2 + 2. * random(),
+            14. * random() - 11.,
+        );
+        let color = Vec3(1., 0.4 + 0.4 * random(), 0.1 + 0.3 * random());
+        let brightness = 20. * random() * random();
+        let material = light(brightness * color);
+        hittables.push(sphere(center, 0.05, material.clone()));
+        lights.push(sphere(center, 0.05, material));
+    }
+    Scene {
+        world: World { hittables },
+        accelerator: Accelerator::Bvh,
+        exposure: None,
+        delta_lights: Vec::new(),
+        environment: Box::new(Solid {
+            color: Vec3(0., 0., 0.),
+        }),
+        portals: Vec::new(),
+        fog: None,
+        lights: World { hittables: lights },
+        camera: Box::new(Perspective::new(
+            Vec3(0., 3., 9.),
+            Vec3(0., 1., -2.),
+            Vec3(0., 1., 0.),
+            50.,
+            aspect,
+            0.,
+            1.,
+        )),
+    }
+}
+
+/// A room lit only through a window in its left wall, by the sky and the
+/// afternoon sun shining in across the floor, exposed as a photograph
+/// indoors would be.
+pub fn interior(aspect: f64) -> Scene {
+    let white = lambertian(Vec3(0.75, 0.75, 0.75));
+    let wall = |corner, u, v| -> Box<dyn Hittable> {
+        Box::new(Quad {
+            corner,
+            u,
+            v,
+            material: white.clone(),
+        })
+    };
+    // the room runs from x = -2 to 2, y = 0 to 2.5 and z = -5 to 0
+    let (width, height, depth) = (4., 2.5, 5.);
+    let window = Portal {
+        corner: Vec3(-2., 0.8, -1.5),
+        u: Vec3(0., 1.2, 0.),
+        v: Vec3(0., 0., -2.),
+    };
+    let hittables = vec![
+        // floor, ceiling, right wall, back and front
+        wall(Vec3(-2., 0., 0.), Vec3(width, 0., 0.), Vec3(0., 0., -depth)),
+        wall(
+            Vec3(-2., height, 0.),
+            Vec3(width, 0., 0.),
+            Vec3(0., 0., -depth),
+        ),
+        wall(Vec3(2., 0., 0.), Vec3(0., height, 0.), Vec3(0., 0., -depth)),
+        wall(
+            Vec3(-2., 0., -depth),
+            Vec3(width, 0., 0.),
+            Vec3(0., height, 0.),
+        ),
+        wall(Vec3(-2., 0., 0.), Vec3(width, 0., 0.), Vec3(0., height, 0.)),
+        // the left wall, around the window
+        wall(Vec3(-2., 0., 0.), Vec3(0., 0.8, 0.), Vec3(0., 0., -depth)),
+        wall(Vec3(-2., 2., 0.), Vec3(0., 0.5, 0.), Vec3(0., 0., -depth)),
+        wall(Vec3(-2., 0.8, 0.), Vec3(0., 1.2, 0.), Vec3(0., 0., -1.5)),
+        wall(Vec3(-2., 0.8, -3.5), Vec3(0., 1.2, 0.), Vec3(0., 0., -1.5)),
+        Box::new(Cuboid {
+            min: Vec3(0.6, 0., -4.2),
+            max: Vec3(1.6, 0.8, -3.2),
+            material: lambertian(Vec3(0.6, 0.3, 0.2)),
+        }),
+        sphere(Vec3(-0.6, 0.4, -3.5), 0.4, metal(Vec3(0.8, 0.8, 0.8), 0.1)),
+    ];
+    Scene {
+        world: World { hittables },
+        accelerator: Accelerator::Bvh,
+        exposure: Some(Exposure {
+            iso: 100.,
+            shutter: 1. / 125.,
+            f_number: 5.6,
+        }),
+        delta_lights: Vec::new(),
+        environment: Box::new(sky(0.5, -1.4, 3., 0.3)),
+        portals: vec![window],
+        fog: None,
+        lights: World::default(),
+        camera: Box::new(Perspective::new(
+            Vec3(1.5, 1.4, -0.3),
+            Vec3(-0.6, 0.9, -3.5),
+            Vec3(0., 1., 0.),
+            60.,
+            aspect,
+            0.,
+            1.,
+        )),
+    }
+}
+
+/// The room of `interior` filled with haze, which the sunlight coming in
+/// through the window lights up in a beam.
+pub fn hazy(aspect: f64) -> Scene {
+    let mut scene = interior(aspect);
+    scene.fog = Some(Fog {
+        absorption: Vec3(0.005, 0.005, 0.005),
+        scattering: Vec3(0.05, 0.05, 0.05),
+        density: None,
+        g: 0.,
+        bounds: scene.world.bounding_box(),
+    });
+    scene
+}
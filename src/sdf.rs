@@ -0,0 +1,194 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::vec3::Vec3;
+use crate::{face_normal, sphere_uv, HitRecord, Hittable, Material, Ray, PIXEL_SPREAD};
+
+/// A shape given by its signed distance function: the distance from a point
+/// to the nearest surface, negative inside. The value must never overestimate
+/// the true distance, or sphere tracing can step through the surface.
+pub trait Sdf: Send + Sync {
+    fn distance(&self, p: &Vec3) -> f64;
+}
+
+pub struct SdfSphere {
+    pub center: Vec3,
+    pub radius: f64,
+}
+
+impl Sdf for SdfSphere {
+    fn distance(&self, p: &Vec3) -> f64 {
+        (*p - self.center).length() - self.radius
+    }
+}
+
+/// An axis-aligned box extending `half_size` either side of `center`, with
+/// edges rounded off by `rounding`.
+pub struct SdfBox {
+    pub center: Vec3,
+    pub half_size: Vec3,
+    pub rounding: f64,
+}
+
+impl Sdf for SdfBox {
+    fn distance(&self, p: &Vec3) -> f64 {
+        let q = *p - self.center;
+        let d = Vec3(
+            q.x().abs() - self.half_size.x() + self.rounding,
+            q.y().abs() - self.half_size.y() + self.rounding,
+            q.z().abs() - self.half_size.z() + self.rounding,
+        );
+        let outside = Vec3(d.x().max(0.), d.y().max(0.), d.z().max(0.)).length();
+        let inside = d.x().max(d.y()).max(d.z()).min(0.);
+        outside + inside - self.rounding
+    }
+}
+
+/// A torus around the y axis through `center`.
+pub struct SdfTorus {
+    pub center: Vec3,
+    pub major_radius: f64,
+    pub minor_radius: f64,
+}
+
+impl Sdf for SdfTorus {
+    fn distance(&self, p: &Vec3) -> f64 {
+        let q = *p - self.center;
+        let ring = (q.x() * q.x() + q.z() * q.z()).sqrt() - self.major_radius;
+        (ring * ring + q.y() * q.y()).sqrt() - self.minor_radius
+    }
+}
+
+/// A line segment from `a` to `b` thickened to `radius`.
+pub struct SdfCapsule {
+    pub a: Vec3,
+    pub b: Vec3,
+    pub radius: f64,
+}
+
+impl Sdf for SdfCapsule {
+    fn distance(&self, p: &Vec3) -> f64 {
+        let pa = *p - self.a;
+        let ba = self.b - self.a;
+        let h = (pa.dot(ba) / ba.dot(ba)).clamp(0., 1.);
+        (pa - h * ba).length() - self.radius
+    }
+}
+
+/// Polynomial smooth minimum of Quilez: like `a.min(b)`, but blended over
+/// differences smaller than `k`.
+fn smooth_min(a: f64, b: f64, k: f64) -> f64 {
+    if k <= 0. {
+        return a.min(b);
+    }
+    let h = (0.5 + 0.5 * (b - a) / k).clamp(0., 1.);
+    b + (a - b) * h - k * h * (1. - h)
+}
+
+/// The two shapes merged, with a fillet of about `smoothness` where they
+/// meet, for blobby organic forms.
+pub struct SmoothUnion {
+    pub a: Box<dyn Sdf>,
+    pub b: Box<dyn Sdf>,
+    pub smoothness: f64,
+}
+
+impl Sdf for SmoothUnion {
+    fn distance(&self, p: &Vec3) -> f64 {
+        smooth_min(self.a.distance(p), self.b.distance(p), self.smoothness)
+    }
+}
+
+/// `a` with `b` carved out of it, the edges rounded by `smoothness`.
+pub struct SmoothDifference {
+    pub a: Box<dyn Sdf>,
+    pub b: Box<dyn Sdf>,
+    pub smoothness: f64,
+}
+
+impl Sdf for SmoothDifference {
+    fn distance(&self, p: &Vec3) -> f64 {
+        -smooth_min(-self.a.distance(p), self.b.distance(p), self.smoothness)
+    }
+}
+
+/// Give up marching after this many steps, e.g. for rays grazing a surface.
+const MAX_STEPS: usize = 512;
+
+/// Renders a signed distance function by sphere tracing: stepping along the
+/// ray by the distance to the nearest surface, which can't overshoot it,
+/// until that distance is negligible. Normals come from the gradient by
+/// central differences; surface coordinates from the normal's direction.
+pub struct SdfSurface {
+    pub sdf: Box<dyn Sdf>,
+    /// Marching stops within this distance of the surface.
+    pub epsilon: f64,
+    /// Rays give up after marching this far from their origin without a hit,
+    /// so misses stay cheap; it should reach from the camera past the far side
+    /// of the shape.
+    pub max_distance: f64,
+    pub material: Arc<dyn Material>,
+}
+
+impl SdfSurface {
+    fn normal(&self, p: &Vec3) -> Vec3 {
+        let h = self.epsilon;
+        let d =
+            |offset: Vec3| self.sdf.distance(&(*p + offset)) - self.sdf.distance(&(*p - offset));
+        Vec3(d(Vec3(h, 0., 0.)), d(Vec3(0., h, 0.)), d(Vec3(0., 0., h))).unit()
+    }
+}
+
+impl Hittable for SdfSurface {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let speed = r.direction().length();
+        // march in units of distance, starting well clear of any surface the
+        // ray may have just left
+        let mut s = t_min * speed + 10. * self.epsilon;
+        let end = self.max_distance.min(t_max * speed);
+        // rays starting inside march out to the surface the same way
+        let mut side = self.sdf.distance(&r.point_at_parameter(s / speed)).signum();
+        for _ in 0..MAX_STEPS {
+            if s >= end {
+                return None;
+            }
+            let distance = side * self.sdf.distance(&r.point_at_parameter(s / speed));
+            if distance < self.epsilon {
+                let t = s / speed;
+                let p = r.point_at_parameter(t);
+                let outward = self.normal(&p);
+                let (normal, front_face) = face_normal(r, outward);
+                let (u, v) = sphere_uv(&outward);
+                let hit_record = HitRecord {
+                    t,
+                    p,
+                    normal,
+                    geometric_normal: normal,
+                    front_face,
+                    tangent: Vec3(outward.z(), 0., -outward.x()),
+                    u,
+                    v,
+                    uv_width: s * PIXEL_SPREAD,
+                    exterior_ior: 1.,
+                    min_roughness: 0.,
+                    wavelengths: r.wavelengths,
+                    material: &*self.material,
+                };
+                if hit_record.opaque() {
+                    return Some(hit_record);
+                }
+                // cut out: carry on from the other side
+                side = -side;
+                s += 10. * self.epsilon;
+                continue;
+            }
+            s += distance;
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        // distance fields don't say how far they reach
+        None
+    }
+}
@@ -0,0 +1,64 @@
+use std::f64::consts::PI;
+
+use crate::pdf::{CosinePdf, Pdf};
+use crate::sampler::Sampler;
+use crate::texture::Texture;
+use crate::vec3::Vec3;
+use crate::{HitRecord, Material, Ray, Scatter};
+
+/// The "Charlie" sheen BRDF of Estevez and Kulla 2017 with Neubelt and
+/// Pettineo's visibility term, for local directions `wo` and `wi` around +z.
+///
+/// Its microfacet distribution favors normals lying flat against the surface,
+/// like fibers standing up from cloth, so it brightens toward grazing angles.
+pub fn charlie(wo: &Vec3, wi: &Vec3, roughness: f64) -> f64 {
+    if wo.z() <= 0. || wi.z() <= 0. {
+        return 0.;
+    }
+    let h = (*wo + *wi).unit();
+    let inv_alpha = 1. / (roughness * roughness).max(1e-3);
+    let sin2_h = (1. - h.z() * h.z()).max(0.);
+    let d = (2. + inv_alpha) * sin2_h.powf(0.5 * inv_alpha) / (2. * PI);
+    let v = 1. / (4. * (wi.z() + wo.z() - wi.z() * wo.z()));
+    d * v
+}
+
+/// Fabric: a diffuse base under a Charlie sheen layer. Use a black `albedo`
+/// for a sheen-only velvet.
+pub struct Cloth {
+    pub albedo: Box<dyn Texture>,
+    pub sheen_color: Box<dyn Texture>,
+    /// Sheen roughness in (0, 1]; lower values confine the sheen to grazing
+    /// angles.
+    pub roughness: f64,
+}
+
+impl Material for Cloth {
+    fn scatter(
+        &self,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        Scatter::sample(self, r, hit_record, sampler)
+    }
+
+    fn pdf(&self, r: &Ray, hit_record: &HitRecord) -> Option<Box<dyn Pdf>> {
+        if r.direction().dot(hit_record.normal) >= 0. {
+            return None;
+        }
+        Some(Box::new(CosinePdf::new(&hit_record.normal)))
+    }
+
+    fn eval(&self, r: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Vec3 {
+        let frame = crate::onb::Onb::from_w(&hit_record.normal);
+        let wo = frame.to_local(&-r.direction().unit());
+        let wi = frame.to_local(&direction.unit());
+        if wo.z() <= 0. || wi.z() <= 0. {
+            return Vec3(0., 0., 0.);
+        }
+        let sheen = charlie(&wo, &wi, self.roughness);
+        (hit_record.texture(&*self.albedo) / PI + sheen * hit_record.texture(&*self.sheen_color))
+            * wi.z()
+    }
+}
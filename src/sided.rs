@@ -0,0 +1,96 @@
+use crate::aabb::Aabb;
+use crate::pdf::Pdf;
+use crate::sampler::Sampler;
+use crate::spectrum::Wavelengths;
+use crate::vec3::Vec3;
+use crate::{HitRecord, Hittable, Material, Ray, Scatter};
+
+/// Turns an object inside out: hits on it report the other side as the
+/// front, so a one-sided light or wall that faces the wrong way can be
+/// turned around without rebuilding its geometry.
+pub struct FlipFace {
+    pub object: Box<dyn Hittable>,
+}
+
+impl Hittable for FlipFace {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.object.hit(r, t_min, t_max).map(|mut hit_record| {
+            hit_record.front_face = !hit_record.front_face;
+            hit_record
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.object.bounding_box()
+    }
+
+    fn clipped_box(&self, clip: &Aabb) -> Option<Aabb> {
+        self.object.clipped_box(clip)
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        self.object.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        self.object.random(origin, sampler)
+    }
+
+    /// The object's point, seen from what is now its front.
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(HitRecord<'_>, f64)> {
+        let (mut hit_record, density) = self.object.sample_surface(sampler)?;
+        hit_record.normal = -hit_record.normal;
+        hit_record.geometric_normal = -hit_record.geometric_normal;
+        Some((hit_record, density))
+    }
+
+    fn surface_pdf(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        self.object.surface_pdf(origin, direction)
+    }
+}
+
+/// Wraps a material so only the front of surfaces made of it, the side their
+/// outward normal points to, emits or reflects light; from behind they are
+/// black. Area lights in particular then shine one way only.
+pub struct OneSided {
+    pub material: Box<dyn Material>,
+}
+
+impl Material for OneSided {
+    fn scatter(
+        &self,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        self.material.scatter(r, hit_record, sampler)
+    }
+
+    fn pdf(&self, r: &Ray, hit_record: &HitRecord) -> Option<Box<dyn Pdf>> {
+        self.material.pdf(r, hit_record)
+    }
+
+    fn eval(&self, r: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Vec3 {
+        self.material.eval(r, hit_record, direction)
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Vec3) -> Vec3 {
+        self.material.emitted(u, v, p)
+    }
+
+    fn emitted_spectral(&self, u: f64, v: f64, p: &Vec3, wavelengths: &Wavelengths) -> Vec3 {
+        self.material.emitted_spectral(u, v, p, wavelengths)
+    }
+
+    fn alpha(&self, u: f64, v: f64, p: &Vec3) -> f64 {
+        self.material.alpha(u, v, p)
+    }
+
+    fn two_sided(&self) -> bool {
+        false
+    }
+
+    fn glossy(&self, hit_record: &HitRecord) -> bool {
+        self.material.glossy(hit_record)
+    }
+}
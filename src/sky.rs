@@ -0,0 +1,362 @@
+use std::f64::consts::PI;
+use std::io;
+use std::path::Path;
+
+use crate::environment::Environment;
+use crate::onb::Onb;
+use crate::sampler::Sampler;
+use crate::spectrum::xyz_to_rgb;
+use crate::thin_film;
+use crate::vec3::Vec3;
+
+/// Angle the sun's disk spans from its middle to its edge, in radians.
+const SUN_RADIUS: f64 = 0.00465;
+
+/// Luminance of the sun's disk above the atmosphere, in nits.
+const SUN_LUMINANCE: f64 = 2e9;
+
+/// Luminous efficacy of the Hosek-Wilkie model's radiometric units, in
+/// lumens per watt, turning its radiance into nits.
+const EFFICACY: f64 = 683.;
+
+/// A clear sky and the sun in it, in nits, for outdoor scenes exposed
+/// like photographs, as with `Exposure::sunny_16`. The sky follows the
+/// analytic model of Hosek and Wilkie 2012 ("An Analytic Model for Full
+/// Spectral Sky-Dome Radiance"): its brightness and color from the zenith
+/// to the horizon and around the sun are fitted to simulations of light
+/// scattering in the atmosphere, for how hazy the air is and how much
+/// light the ground throws back up, with a glow around the sun and a
+/// brightening towards the horizon truer to life than earlier models.
+/// Without the model's published coefficient tables it falls back on the
+/// older model of Preetham, Shirley and Smits 1999 ("A Practical Analytic
+/// Model for Daylight"). The sun is a disk of its real size, dimmed and
+/// reddened by the air it shines through, so it casts shadows with soft
+/// edges; directions are drawn towards it half the time, so every surface
+/// finds it.
+///
+/// Below the horizon the sky stays as it is at the horizon, for scenes
+/// without a ground.
+pub struct PhysicalSky {
+    /// The unit direction towards the middle of the sun.
+    sun: Vec3,
+    sun_radiance: Vec3,
+    model: SkyModel,
+}
+
+enum SkyModel {
+    HosekWilkie {
+        /// The nine coefficients of the sky's shape for red, green and
+        /// blue.
+        coefficients: [[f64; 9]; 3],
+        /// The radiance each shape is scaled by, in nits.
+        radiances: [f64; 3],
+    },
+    Preetham {
+        /// Angle of the sun from the zenith, no lower than the horizon.
+        sun_zenith: f64,
+        /// Perez coefficients of luminance and the x and y chromaticities.
+        perez: [[f64; 5]; 3],
+        /// Luminance and chromaticities at the zenith.
+        zenith: [f64; 3],
+    },
+}
+
+/// The coefficient tables of the Hosek-Wilkie sky for red, green and blue,
+/// as published with the model's reference implementation in
+/// `ArHosekSkyModelData_RGB.h`.
+pub struct SkyModelData {
+    /// Per color, nine coefficients at each of six elevations, for each of
+    /// ten turbidities, for ground albedos 0 and 1.
+    coefficients: [Vec<f64>; 3],
+    /// Per color, the radiance at each of six elevations, for each of ten
+    /// turbidities, for ground albedos 0 and 1.
+    radiances: [Vec<f64>; 3],
+}
+
+impl SkyModelData {
+    /// Read the tables from `ArHosekSkyModelData_RGB.h`, the C header they
+    /// are published in: the arrays `datasetRGB1` to `datasetRGB3` and
+    /// `datasetRGBRad1` to `datasetRGBRad3`.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<SkyModelData> {
+        let text = std::fs::read_to_string(path)?;
+        let table = |name: &str, len: usize| {
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("bad sky model table: {}", name),
+                )
+            };
+            // the definition, not the list of tables naming it
+            let (start, _) = text
+                .match_indices(name)
+                .find(|&(i, _)| text[i + name.len()..].trim_start().starts_with('['))
+                .ok_or_else(invalid)?;
+            let body = &text[start..];
+            let open = body.find('{').ok_or_else(invalid)?;
+            let close = body.find('}').ok_or_else(invalid)?;
+            let values = body[open + 1..close.max(open + 1)]
+                .lines()
+                .map(|line| line.split("//").next().unwrap_or(""))
+                .flat_map(|line| line.split(','))
+                .map(str::trim)
+                .filter(|word| !word.is_empty())
+                .map(str::parse::<f64>)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| invalid())?;
+            if values.len() == len {
+                Ok(values)
+            } else {
+                Err(invalid())
+            }
+        };
+        Ok(SkyModelData {
+            coefficients: [
+                table("datasetRGB1", 2 * 10 * 6 * 9)?,
+                table("datasetRGB2", 2 * 10 * 6 * 9)?,
+                table("datasetRGB3", 2 * 10 * 6 * 9)?,
+            ],
+            radiances: [
+                table("datasetRGBRad1", 2 * 10 * 6)?,
+                table("datasetRGBRad2", 2 * 10 * 6)?,
+                table("datasetRGBRad3", 2 * 10 * 6)?,
+            ],
+        })
+    }
+}
+
+/// The unit direction towards the sun `elevation` radians above the
+/// horizon and `azimuth` radians round from -z towards +x.
+fn sun_direction(elevation: f64, azimuth: f64) -> Vec3 {
+    Vec3(
+        elevation.cos() * azimuth.sin(),
+        elevation.sin(),
+        -elevation.cos() * azimuth.cos(),
+    )
+}
+
+/// `n` values interpolated from a Hosek-Wilkie table holding them at six
+/// elevations, for ten turbidities and two albedos: along a quintic Bézier
+/// curve through the elevations, taken by the cube root of the elevation
+/// over a right angle, then linearly between the turbidities and albedos
+/// either side.
+fn interpolate(table: &[f64], n: usize, elevation: f64, turbidity: f64, albedo: f64) -> Vec<f64> {
+    let x = (elevation.clamp(0., 0.5 * PI) / (0.5 * PI)).cbrt();
+    let bernstein: Vec<f64> = [1., 5., 10., 10., 5., 1.]
+        .iter()
+        .enumerate()
+        .map(|(j, binomial)| binomial * (1. - x).powi(5 - j as i32) * x.powi(j as i32))
+        .collect();
+    let turbidity = turbidity.clamp(1., 10.);
+    let lower = turbidity.floor() as usize;
+    let remainder = turbidity - lower as f64;
+    let mut values = vec![0.; n];
+    for (a, albedo_weight) in [(0, 1. - albedo), (1, albedo)] {
+        for (t, turbidity_weight) in [(lower - 1, 1. - remainder), (lower, remainder)] {
+            if t >= 10 {
+                continue;
+            }
+            let block = &table[n * 6 * (10 * a + t)..];
+            for (i, value) in values.iter_mut().enumerate() {
+                let curve: f64 = (0..6).map(|j| bernstein[j] * block[i + n * j]).sum();
+                *value += albedo_weight * turbidity_weight * curve;
+            }
+        }
+    }
+    values
+}
+
+/// The Hosek-Wilkie sky's shape with coefficients `c`, at `cos_theta`
+/// from the zenith and `gamma` radians from the sun.
+fn hosek_wilkie(c: &[f64; 9], cos_theta: f64, gamma: f64) -> f64 {
+    let cos_gamma = gamma.cos();
+    let glow = (c[4] * gamma).exp();
+    let rayleigh = cos_gamma * cos_gamma;
+    let mie = (1. + cos_gamma * cos_gamma) / (1. + c[8] * c[8] - 2. * c[8] * cos_gamma).powf(1.5);
+    (1. + c[0] * (c[1] / (cos_theta + 0.01)).exp())
+        * (c[2] + c[3] * glow + c[5] * rayleigh + c[6] * mie + c[7] * cos_theta.sqrt())
+}
+
+impl PhysicalSky {
+    /// The Hosek-Wilkie sky, from `data`, with the sun `elevation` radians
+    /// above the horizon and `azimuth` radians round from -z towards +x,
+    /// seen from above, through air of `turbidity`: 2 for a very clear
+    /// day, 3 for a clear one, and up to 10 for a hazy one; and over
+    /// ground of `albedo`, from 0 to 1, like 0.1 to 0.3 for grass and soil
+    /// and more for snow.
+    pub fn hosek_wilkie(
+        data: &SkyModelData,
+        elevation: f64,
+        azimuth: f64,
+        turbidity: f64,
+        albedo: f64,
+    ) -> PhysicalSky {
+        let albedo = albedo.clamp(0., 1.);
+        let mut coefficients = [[0.; 9]; 3];
+        let mut radiances = [0.; 3];
+        for channel in 0..3 {
+            let c = interpolate(&data.coefficients[channel], 9, elevation, turbidity, albedo);
+            coefficients[channel].copy_from_slice(&c);
+            let radiance = interpolate(&data.radiances[channel], 1, elevation, turbidity, albedo);
+            radiances[channel] = EFFICACY * radiance[0];
+        }
+        PhysicalSky {
+            sun: sun_direction(elevation, azimuth),
+            sun_radiance: sun_radiance(elevation, turbidity),
+            model: SkyModel::HosekWilkie {
+                coefficients,
+                radiances,
+            },
+        }
+    }
+
+    /// The sky by Preetham et al.'s model, with the sun `elevation`
+    /// radians above the horizon and `azimuth` radians round from -z
+    /// towards +x, seen from above, through air of `turbidity`: 2 for a
+    /// very clear day, 3 for a clear one, and up to 10 for a hazy one.
+    pub fn new(elevation: f64, azimuth: f64, turbidity: f64) -> PhysicalSky {
+        let t = turbidity;
+        let sun_zenith = (0.5 * PI - elevation).clamp(0., 0.5 * PI);
+        let perez = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+        let chi = (4. / 9. - t / 120.) * (PI - 2. * sun_zenith);
+        // in thousands of nits
+        let luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let s = sun_zenith;
+        let (s2, s3, t2) = (s * s, s * s * s, t * t);
+        let x = t2 * (0.00166 * s3 - 0.00375 * s2 + 0.00209 * s)
+            + t * (-0.02903 * s3 + 0.06377 * s2 - 0.03202 * s + 0.00394)
+            + (0.11693 * s3 - 0.21196 * s2 + 0.06052 * s + 0.25886);
+        let y = t2 * (0.00275 * s3 - 0.00610 * s2 + 0.00317 * s)
+            + t * (-0.04214 * s3 + 0.08970 * s2 - 0.04153 * s + 0.00516)
+            + (0.15346 * s3 - 0.26756 * s2 + 0.06670 * s + 0.26688);
+        PhysicalSky {
+            sun: sun_direction(elevation, azimuth),
+            sun_radiance: sun_radiance(elevation, turbidity),
+            model: SkyModel::Preetham {
+                sun_zenith,
+                perez,
+                zenith: [1000. * luminance.max(0.), x, y],
+            },
+        }
+    }
+
+    /// The sky without the sun.
+    fn sky(&self, direction: &Vec3) -> Vec3 {
+        let gamma = direction.dot(self.sun).clamp(-1., 1.).acos();
+        let rgb = match &self.model {
+            SkyModel::HosekWilkie {
+                coefficients,
+                radiances,
+            } => {
+                let cos_theta = direction.y().max(0.);
+                let [r, g, b] = [0, 1, 2]
+                    .map(|i| radiances[i] * hosek_wilkie(&coefficients[i], cos_theta, gamma));
+                Vec3(r, g, b)
+            }
+            SkyModel::Preetham {
+                sun_zenith,
+                perez,
+                zenith,
+            } => {
+                let cos_theta = direction.y().max(0.001);
+                let [luminance, x, y] = [0, 1, 2].map(|i| {
+                    let perez = |cos_theta: f64, gamma: f64| {
+                        let [a, b, c, d, e] = perez[i];
+                        (1. + a * (b / cos_theta).exp())
+                            * (1. + c * (d * gamma).exp() + e * gamma.cos() * gamma.cos())
+                    };
+                    zenith[i] * perez(cos_theta, gamma) / perez(1., *sun_zenith)
+                });
+                xyz_to_rgb(Vec3(
+                    x / y * luminance,
+                    luminance,
+                    (1. - x - y) / y * luminance,
+                ))
+            }
+        };
+        Vec3(rgb.r().max(0.), rgb.g().max(0.), rgb.b().max(0.))
+    }
+}
+
+impl Environment for PhysicalSky {
+    fn radiance(&self, direction: &Vec3) -> Vec3 {
+        let d = direction.unit();
+        if d.dot(self.sun) >= SUN_RADIUS.cos() {
+            self.sky(&d) + self.sun_radiance
+        } else {
+            self.sky(&d)
+        }
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<Vec3> {
+        let (u, v) = sampler.get_2d();
+        // towards the sun half the time, otherwise anywhere
+        let z = if self.sun_radiance.luminance() <= 0. {
+            1. - 2. * v
+        } else if v < 0.5 {
+            1. + 2. * v * (SUN_RADIUS.cos() - 1.)
+        } else {
+            1. - 2. * (2. * v - 1.)
+        };
+        let r = (1. - z * z).max(0.).sqrt();
+        let phi = 2. * PI * u;
+        Some(Onb::from_w(&self.sun).local(&Vec3(r * phi.cos(), r * phi.sin(), z)))
+    }
+
+    fn pdf(&self, direction: &Vec3) -> f64 {
+        let anywhere = 1. / (4. * PI);
+        if self.sun_radiance.luminance() <= 0. {
+            return anywhere;
+        }
+        let cos_radius = SUN_RADIUS.cos();
+        let sun = if direction.unit().dot(self.sun) >= cos_radius {
+            1. / (2. * PI * (1. - cos_radius))
+        } else {
+            0.
+        };
+        0.5 * sun + 0.5 * anywhere
+    }
+}
+
+/// Radiance of the sun's disk seen from the ground with the sun
+/// `elevation` radians up, through air of `turbidity`: what is left of it
+/// at the red, green and blue wavelengths once scattered by molecules and
+/// haze, after Preetham et al.'s appendix.
+fn sun_radiance(elevation: f64, turbidity: f64) -> Vec3 {
+    if elevation <= 0. {
+        return Vec3(0., 0., 0.);
+    }
+    // relative optical mass of air, by Kasten and Young 1989
+    let degrees = elevation.to_degrees();
+    let air_mass = 1. / (elevation.sin() + 0.50572 * (degrees + 6.07995).powf(-1.6364));
+    // Ångström's turbidity coefficient
+    let beta = 0.04608 * turbidity - 0.04586;
+    let [red, green, blue] = thin_film::WAVELENGTHS.map(|wavelength| {
+        let micrometres = wavelength / 1000.;
+        let rayleigh = -0.008735 * micrometres.powf(-4.08);
+        let haze = -beta * micrometres.powf(-1.3);
+        SUN_LUMINANCE * ((rayleigh + haze) * air_mass).exp()
+    });
+    Vec3(red, green, blue)
+}
@@ -0,0 +1,191 @@
+use std::sync::OnceLock;
+
+use crate::thin_film;
+use crate::vec3::Vec3;
+
+/// The range of visible wavelengths spectral paths sample, in nanometres.
+pub const MIN_WAVELENGTH: f64 = 360.;
+pub const MAX_WAVELENGTH: f64 = 830.;
+
+/// The wavelengths, in nanometres, a path carries in spectral rendering,
+/// one per channel of the colors along it in place of red, green and blue:
+/// a hero wavelength picked at random first, and two companions a third
+/// and two thirds of the visible range on from it, wrapping around, after
+/// Wilkie et al. 2014 ("Hero Wavelength Spectral Sampling"). Together they
+/// cover the spectrum evenly, so a path finds color as well as three
+/// channels would, and where light can only go on at one wavelength, as
+/// through a prism, the hero carries on alone.
+#[derive(Copy, Clone, Debug)]
+pub struct Wavelengths(pub [f64; 3]);
+
+/// A spectrum specified by its values over wavelength.
+#[derive(Clone, Debug)]
+pub enum Spectrum {
+    /// Measured values at wavelengths in nanometres, in increasing order,
+    /// linear between them and constant beyond.
+    Sampled(Vec<(f64, f64)>),
+    /// The light given off by a black body at a temperature in kelvin, by
+    /// Planck's law, scaled to 1 at its peak.
+    Blackbody(f64),
+}
+
+impl Wavelengths {
+    /// A hero wavelength spread evenly over the visible range by `u` in
+    /// [0, 1), with its companions.
+    pub fn sample(u: f64) -> Wavelengths {
+        let at = |u: f64| MIN_WAVELENGTH + u.fract() * (MAX_WAVELENGTH - MIN_WAVELENGTH);
+        Wavelengths([at(u), at(u + 1. / 3.), at(u + 2. / 3.)])
+    }
+
+    pub fn hero(&self) -> f64 {
+        self.0[0]
+    }
+
+    /// The hero wavelength alone, for where light only goes on at one
+    /// wavelength, with what the values at each wavelength are multiplied
+    /// by from then on: three times the hero's, to make up for the
+    /// companions dropped, unless they already were.
+    pub fn hero_only(&self) -> (Wavelengths, Vec3) {
+        let hero = self.hero();
+        if self.0[1] == hero {
+            (*self, Vec3(1., 1., 1.))
+        } else {
+            (Wavelengths([hero; 3]), Vec3(3., 0., 0.))
+        }
+    }
+
+    /// The values of `spectrum` at each wavelength.
+    pub fn of(&self, spectrum: &Spectrum) -> Vec3 {
+        Vec3(
+            spectrum.at(self.0[0]),
+            spectrum.at(self.0[1]),
+            spectrum.at(self.0[2]),
+        )
+    }
+
+    /// The values at each wavelength of a smooth spectrum with linear sRGB
+    /// color `rgb`: blue at short wavelengths, green in the middle, red at
+    /// long ones, blending into each other. White stays white, everywhere
+    /// one, and reflectances stay between zero and one.
+    pub fn from_rgb(&self, rgb: Vec3) -> Vec3 {
+        let smoothstep = |from: f64, to: f64, x: f64| {
+            let t = ((x - from) / (to - from)).clamp(0., 1.);
+            t * t * (3. - 2. * t)
+        };
+        let at = |wavelength: f64| {
+            let blue = 1. - smoothstep(465., 505., wavelength);
+            let red = smoothstep(570., 610., wavelength);
+            rgb.b() * blue + rgb.g() * (1. - blue - red) + rgb.r() * red
+        };
+        Vec3(at(self.0[0]), at(self.0[1]), at(self.0[2]))
+    }
+
+    /// The values at each wavelength of a quantity known at the red, green
+    /// and blue wavelengths of `thin_film::WAVELENGTHS`, as `rgb`: linear
+    /// between them and constant beyond.
+    pub fn interpolate(&self, rgb: Vec3) -> Vec3 {
+        let [red, green, blue] = thin_film::WAVELENGTHS;
+        let spectrum = Spectrum::Sampled(vec![(blue, rgb.b()), (green, rgb.g()), (red, rgb.r())]);
+        self.of(&spectrum)
+    }
+
+    /// The linear sRGB color of radiance `values` arriving at each
+    /// wavelength: an estimate of the color of the light at all of them,
+    /// weighed by how the eye sees each. A flat spectrum comes out white.
+    pub fn to_rgb(&self, values: Vec3) -> Vec3 {
+        let range = MAX_WAVELENGTH - MIN_WAVELENGTH;
+        let xyz =
+            (xyz(self.0[0]) * values.0 + xyz(self.0[1]) * values.1 + xyz(self.0[2]) * values.2)
+                * (range / 3.);
+        xyz_to_white_balanced_rgb(xyz)
+    }
+}
+
+impl Spectrum {
+    /// The value at `wavelength` nanometres.
+    pub fn at(&self, wavelength: f64) -> f64 {
+        match self {
+            Spectrum::Sampled(samples) => {
+                let i = samples.partition_point(|&(w, _)| w < wavelength);
+                match (samples.get(i.wrapping_sub(1)), samples.get(i)) {
+                    (Some(&(w0, v0)), Some(&(w1, v1))) => {
+                        v0 + (v1 - v0) * (wavelength - w0) / (w1 - w0)
+                    }
+                    (Some(&(_, v)), None) | (None, Some(&(_, v))) => v,
+                    (None, None) => 0.,
+                }
+            }
+            &Spectrum::Blackbody(temperature) => {
+                // by Wien's displacement law
+                let peak = 2.897771955e6 / temperature;
+                planck(wavelength, temperature) / planck(peak, temperature)
+            }
+        }
+    }
+
+    /// The linear sRGB color of the spectrum, as light; the flat spectrum
+    /// of ones is white.
+    pub fn to_rgb(&self) -> Vec3 {
+        let mut xyz = Vec3(0., 0., 0.);
+        let mut wavelength = MIN_WAVELENGTH;
+        while wavelength <= MAX_WAVELENGTH {
+            xyz = xyz + self.at(wavelength) * self::xyz(wavelength);
+            wavelength += 1.;
+        }
+        xyz_to_white_balanced_rgb(xyz)
+    }
+}
+
+/// Spectral radiance of a black body at `temperature` kelvin, up to a
+/// constant factor.
+fn planck(wavelength: f64, temperature: f64) -> f64 {
+    let metres = wavelength * 1e-9;
+    // second radiation constant, h c / k, in metre kelvins
+    let c2 = 1.4387769e-2;
+    1. / (metres.powi(5) * ((c2 / (metres * temperature)).exp() - 1.))
+}
+
+/// The CIE 1931 color matching functions at `wavelength` nanometres, by
+/// the multi-lobe Gaussian fit of Wyman, Sloan and Shirley 2013.
+fn xyz(wavelength: f64) -> Vec3 {
+    let g = |mu: f64, below: f64, above: f64| {
+        let t = (wavelength - mu) / if wavelength < mu { below } else { above };
+        (-0.5 * t * t).exp()
+    };
+    Vec3(
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    )
+}
+
+/// Linear sRGB from CIE XYZ, scaled so the flat spectrum that white in
+/// the scene stands for comes out white rather than pink.
+fn xyz_to_white_balanced_rgb(xyz: Vec3) -> Vec3 {
+    static WHITE: OnceLock<Vec3> = OnceLock::new();
+    let white = WHITE.get_or_init(|| {
+        let mut sum = Vec3(0., 0., 0.);
+        let mut wavelength = MIN_WAVELENGTH;
+        while wavelength <= MAX_WAVELENGTH {
+            sum = sum + self::xyz(wavelength);
+            wavelength += 1.;
+        }
+        xyz_to_rgb(sum)
+    });
+    let rgb = xyz_to_rgb(xyz);
+    Vec3(
+        rgb.r() / white.r(),
+        rgb.g() / white.g(),
+        rgb.b() / white.b(),
+    )
+}
+
+/// Linear sRGB from CIE XYZ.
+pub fn xyz_to_rgb(xyz: Vec3) -> Vec3 {
+    let Vec3(x, y, z) = xyz;
+    Vec3(
+        3.2406 * x - 1.5372 * y - 0.4986 * z,
+        -0.9689 * x + 1.8758 * y + 0.0415 * z,
+        0.0557 * x - 0.2040 * y + 1.0570 * z,
+    )
+}
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::mesh::TriangleMesh;
+use crate::vec3::Vec3;
+use crate::Material;
+
+/// A coarse polygon cage, usually of quads, to be smoothed by Catmull-Clark
+/// subdivision. Each face lists indices into `positions` in order around it.
+pub struct ControlMesh {
+    pub positions: Vec<Vec3>,
+    pub faces: Vec<Vec<u32>>,
+}
+
+/// The faces on either side of an edge, and the index of its new point.
+struct Edge {
+    faces: Vec<usize>,
+    point: u32,
+}
+
+fn edge_key(a: u32, b: u32) -> (u32, u32) {
+    (a.min(b), a.max(b))
+}
+
+impl ControlMesh {
+    /// Read the vertices and faces of a Wavefront OBJ file, ignoring
+    /// everything else in it.
+    pub fn load_obj<P: AsRef<Path>>(path: P) -> io::Result<ControlMesh> {
+        let invalid = |line: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("bad OBJ line: {}", line),
+            )
+        };
+        let mut positions = Vec::new();
+        let mut faces = Vec::new();
+        for line in std::fs::read_to_string(path)?.lines() {
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("v") => {
+                    let xyz = words
+                        .take(3)
+                        .map(str::parse::<f64>)
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|_| invalid(line))?;
+                    if xyz.len() != 3 {
+                        return Err(invalid(line));
+                    }
+                    positions.push(Vec3(xyz[0], xyz[1], xyz[2]));
+                }
+                Some("f") => {
+                    // a vertex is "position/uv/normal" with the last two
+                    // optional, counting from 1, or back from the newest
+                    // position if negative
+                    let face = words
+                        .map(|word| {
+                            let index = word.split('/').next()?.parse::<i64>().ok()?;
+                            let index = if index < 0 {
+                                positions.len() as i64 + index
+                            } else {
+                                index - 1
+                            };
+                            if index < 0 || index >= positions.len() as i64 {
+                                None
+                            } else {
+                                Some(index as u32)
+                            }
+                        })
+                        .collect::<Option<Vec<_>>>()
+                        .filter(|face| face.len() >= 3)
+                        .ok_or_else(|| invalid(line))?;
+                    faces.push(face);
+                }
+                _ => {}
+            }
+        }
+        Ok(ControlMesh { positions, faces })
+    }
+
+    /// One level of Catmull-Clark subdivision, splitting each n-sided face
+    /// into n quads and moving the old vertices toward the limit surface.
+    /// Open edges are kept as B-spline boundary curves and corners, vertices
+    /// on just one boundary face, stay put.
+    pub fn subdivide(&self) -> ControlMesh {
+        let vertex_count = self.positions.len();
+        let face_points: Vec<Vec3> = self
+            .faces
+            .iter()
+            .map(|face| {
+                let sum = face
+                    .iter()
+                    .fold(Vec3(0., 0., 0.), |sum, &i| sum + self.positions[i as usize]);
+                sum / face.len() as f64
+            })
+            .collect();
+
+        let mut edges: HashMap<(u32, u32), Edge> = HashMap::new();
+        let mut edge_order = Vec::new();
+        for (f, face) in self.faces.iter().enumerate() {
+            for i in 0..face.len() {
+                let key = edge_key(face[i], face[(i + 1) % face.len()]);
+                let point = (vertex_count + self.faces.len() + edge_order.len()) as u32;
+                let edge = edges.entry(key).or_insert_with(|| {
+                    edge_order.push(key);
+                    Edge {
+                        faces: Vec::new(),
+                        point,
+                    }
+                });
+                edge.faces.push(f);
+            }
+        }
+
+        let edge_points = edge_order.iter().map(|key| {
+            let edge = &edges[key];
+            let midpoint = (self.positions[key.0 as usize] + self.positions[key.1 as usize]) / 2.;
+            if edge.faces.len() == 2 {
+                (midpoint + (face_points[edge.faces[0]] + face_points[edge.faces[1]]) / 2.) / 2.
+            } else {
+                midpoint
+            }
+        });
+
+        // gather what touches each old vertex
+        let mut vertex_faces = vec![Vec::new(); vertex_count];
+        for (f, face) in self.faces.iter().enumerate() {
+            for &i in face {
+                vertex_faces[i as usize].push(f);
+            }
+        }
+        let mut vertex_edges = vec![Vec::new(); vertex_count];
+        for key in &edge_order {
+            vertex_edges[key.0 as usize].push(*key);
+            vertex_edges[key.1 as usize].push(*key);
+        }
+        let other_end = |key: &(u32, u32), i: usize| {
+            let j = if key.0 as usize == i { key.1 } else { key.0 };
+            self.positions[j as usize]
+        };
+        let vertex_points = (0..vertex_count).map(|i| {
+            let p = self.positions[i];
+            let boundary: Vec<_> = vertex_edges[i]
+                .iter()
+                .filter(|key| edges[*key].faces.len() != 2)
+                .collect();
+            let n = vertex_faces[i].len();
+            if boundary.len() == 2 {
+                0.75 * p + 0.125 * (other_end(boundary[0], i) + other_end(boundary[1], i))
+            } else if !boundary.is_empty() || n < 3 {
+                p
+            } else {
+                let n = n as f64;
+                let f = vertex_faces[i]
+                    .iter()
+                    .fold(Vec3(0., 0., 0.), |sum, &f| sum + face_points[f])
+                    / n;
+                let r = vertex_edges[i].iter().fold(Vec3(0., 0., 0.), |sum, key| {
+                    sum + (p + other_end(key, i)) / 2.
+                }) / vertex_edges[i].len() as f64;
+                (f + 2. * r + (n - 3.) * p) / n
+            }
+        });
+
+        let positions = vertex_points
+            .chain(face_points.iter().cloned())
+            .chain(edge_points)
+            .collect();
+        let mut faces = Vec::new();
+        for (f, face) in self.faces.iter().enumerate() {
+            let center = (vertex_count + f) as u32;
+            let k = face.len();
+            for i in 0..k {
+                let next = edges[&edge_key(face[i], face[(i + 1) % k])].point;
+                let previous = edges[&edge_key(face[(i + k - 1) % k], face[i])].point;
+                faces.push(vec![face[i], next, center, previous]);
+            }
+        }
+        ControlMesh { positions, faces }
+    }
+
+    /// Subdivide `levels` times and split each face into a fan of triangles
+    /// for rendering, smoothly shaded.
+    pub fn to_mesh(&self, levels: usize, material: Arc<dyn Material>) -> TriangleMesh {
+        let mut mesh = ControlMesh {
+            positions: self.positions.clone(),
+            faces: self.faces.clone(),
+        };
+        for _ in 0..levels {
+            mesh = mesh.subdivide();
+        }
+        let indices = mesh
+            .faces
+            .iter()
+            .flat_map(|face| (1..face.len() - 1).map(move |i| [face[0], face[i], face[i + 1]]))
+            .collect();
+        let mut mesh = TriangleMesh {
+            positions: mesh.positions,
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            indices,
+            materials: vec![material],
+            face_materials: Vec::new(),
+        };
+        mesh.compute_normals();
+        mesh
+    }
+}
@@ -0,0 +1,306 @@
+use crate::bitmap::{Bitmap, MipMap};
+use crate::perlin::Perlin;
+use crate::spectrum::Spectrum;
+use crate::vec3::Vec3;
+use crate::HitRecord;
+
+/// A color that can vary over a surface, looked up by surface coordinates
+/// (u, v) and/or the hit point `p`.
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f64, v: f64, p: &Vec3) -> Vec3;
+
+    /// The texture read as a single number, for maps of roughness, metalness
+    /// and the like: the luminance of its color, so a gray texture gives its
+    /// gray level.
+    fn scalar(&self, u: f64, v: f64, p: &Vec3) -> f64 {
+        self.value(u, v, p).luminance()
+    }
+
+    /// The average value over a region about `width` across in (u, v), for
+    /// textures that would otherwise alias when seen from far away.
+    fn filtered(&self, u: f64, v: f64, p: &Vec3, _width: f64) -> Vec3 {
+        self.value(u, v, p)
+    }
+
+    /// The value seen by a ray hit, filtered over its footprint. Textures
+    /// that depend on more of the surface than (u, v) and the point, like the
+    /// normal, override this.
+    fn at_hit(&self, hit_record: &HitRecord) -> Vec3 {
+        self.filtered(
+            hit_record.u,
+            hit_record.v,
+            &hit_record.p,
+            hit_record.uv_width,
+        )
+    }
+
+    /// For textures given as a spectrum, the spectrum, which spectral
+    /// rendering uses in place of the color; the rest have their color
+    /// turned into a spectrum.
+    fn spectrum(&self) -> Option<&Spectrum> {
+        None
+    }
+}
+
+pub struct ConstantTexture {
+    pub color: Vec3,
+}
+
+impl ConstantTexture {
+    /// A constant gray, e.g. a uniform roughness.
+    pub fn gray(value: f64) -> ConstantTexture {
+        ConstantTexture {
+            color: Vec3(value, value, value),
+        }
+    }
+}
+
+impl Texture for ConstantTexture {
+    fn value(&self, _u: f64, _v: f64, _p: &Vec3) -> Vec3 {
+        self.color
+    }
+}
+
+/// A uniform spectrum, like a measured reflectance or a light's emission;
+/// with RGB rendering it shows as the spectrum's color.
+pub struct SpectrumTexture {
+    spectrum: Spectrum,
+    color: Vec3,
+}
+
+impl SpectrumTexture {
+    pub fn new(spectrum: Spectrum) -> SpectrumTexture {
+        let color = spectrum.to_rgb();
+        SpectrumTexture { spectrum, color }
+    }
+}
+
+impl Texture for SpectrumTexture {
+    fn value(&self, _u: f64, _v: f64, _p: &Vec3) -> Vec3 {
+        self.color
+    }
+
+    fn spectrum(&self) -> Option<&Spectrum> {
+        Some(&self.spectrum)
+    }
+}
+
+/// Shows the surface coordinates themselves as color, u in red and v in
+/// green, for checking a primitive's parameterization.
+pub struct UvTexture;
+
+impl Texture for UvTexture {
+    fn value(&self, u: f64, v: f64, _p: &Vec3) -> Vec3 {
+        Vec3(u, v, 0.)
+    }
+}
+
+/// A 3D checkerboard of alternating textures, with `scale` checks per unit
+/// length along each axis.
+pub struct CheckerTexture {
+    pub odd: Box<dyn Texture>,
+    pub even: Box<dyn Texture>,
+    pub scale: f64,
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f64, v: f64, p: &Vec3) -> Vec3 {
+        let k = self.scale * std::f64::consts::PI;
+        let sines = (k * p.x()).sin() * (k * p.y()).sin() * (k * p.z()).sin();
+        if sines < 0. {
+            self.odd.value(u, v, p)
+        } else {
+            self.even.value(u, v, p)
+        }
+    }
+}
+
+/// Smooth gray noise, `scale` controlling its frequency.
+pub struct NoiseTexture {
+    pub noise: Perlin,
+    pub scale: f64,
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Vec3) -> Vec3 {
+        0.5 * (1. + self.noise.noise(&(self.scale * *p))) * Vec3(1., 1., 1.)
+    }
+}
+
+/// Gray turbulence, like a camouflage net.
+pub struct TurbulenceTexture {
+    pub noise: Perlin,
+    pub scale: f64,
+}
+
+impl Texture for TurbulenceTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Vec3) -> Vec3 {
+        self.noise.turb(&(self.scale * *p), 7) * Vec3(1., 1., 1.)
+    }
+}
+
+/// A puff of cloud, for how thick fog is: white in the middle of the ball
+/// of `radius` about `center`, thinning out to black towards its edge in
+/// billows of turbulence.
+pub struct CloudTexture {
+    pub noise: Perlin,
+    pub scale: f64,
+    pub center: Vec3,
+    pub radius: f64,
+}
+
+impl Texture for CloudTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Vec3) -> Vec3 {
+        let falloff = 1. - (*p - self.center).length() / self.radius;
+        let billows = self.noise.turb(&(self.scale * *p), 5);
+        (3. * (falloff + billows) - 1.).clamp(0., 1.) * Vec3(1., 1., 1.)
+    }
+}
+
+/// Marble: bands along z whose phase is distorted by turbulence, blended
+/// from `color` toward black.
+pub struct MarbleTexture {
+    pub noise: Perlin,
+    pub scale: f64,
+    pub color: Vec3,
+}
+
+impl Texture for MarbleTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Vec3) -> Vec3 {
+        let phase = self.scale * p.z() + 10. * self.noise.turb(p, 7);
+        0.5 * (1. + phase.sin()) * self.color
+    }
+}
+
+/// An image wrapped over the surface by its (u, v) coordinates, filtered
+/// bilinearly and, given a footprint, through its mip pyramid.
+pub struct ImageTexture {
+    pub image: MipMap,
+}
+
+impl ImageTexture {
+    /// Load a color image, decoding it with the same gamma of 2 the renderer
+    /// writes its output with.
+    pub fn load<P: AsRef<std::path::Path>>(path: P) -> Result<ImageTexture, image::ImageError> {
+        let mut image = Bitmap::load(path)?;
+        for texel in &mut image.pixels {
+            *texel = *texel * *texel;
+        }
+        Ok(ImageTexture {
+            image: MipMap::new(image),
+        })
+    }
+
+    /// Load an image that holds data rather than color, like a roughness map
+    /// or emission mask, using its values as they are.
+    pub fn load_linear<P: AsRef<std::path::Path>>(
+        path: P,
+    ) -> Result<ImageTexture, image::ImageError> {
+        Ok(ImageTexture {
+            image: MipMap::new(Bitmap::load(path)?),
+        })
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: &Vec3) -> Vec3 {
+        self.image.levels[0].bilinear(u, v)
+    }
+
+    fn filtered(&self, u: f64, v: f64, _p: &Vec3, width: f64) -> Vec3 {
+        self.image.sample(u, v, width)
+    }
+}
+
+/// Projects `texture` along the x, y and z axes and blends the three by how
+/// squarely the surface faces each axis, so surfaces without usable (u, v)
+/// coordinates can still be textured. `scale` is the number of texture
+/// repeats per unit length; a higher `sharpness` narrows the blend regions.
+pub struct TriplanarTexture {
+    pub texture: Box<dyn Texture>,
+    pub scale: f64,
+    pub sharpness: f64,
+}
+
+impl TriplanarTexture {
+    fn blend(&self, p: &Vec3, weights: Vec3) -> Vec3 {
+        let q = self.scale * *p;
+        let total = weights.x() + weights.y() + weights.z();
+        (weights.x() * self.texture.value(q.z(), q.y(), p)
+            + weights.y() * self.texture.value(q.x(), q.z(), p)
+            + weights.z() * self.texture.value(q.x(), q.y(), p))
+            / total
+    }
+}
+
+impl Texture for TriplanarTexture {
+    /// Without a normal to go by, the three projections are averaged.
+    fn value(&self, _u: f64, _v: f64, p: &Vec3) -> Vec3 {
+        self.blend(p, Vec3(1., 1., 1.))
+    }
+
+    fn at_hit(&self, hit_record: &HitRecord) -> Vec3 {
+        let n = hit_record.geometric_normal;
+        let weight = |c: f64| c.abs().powf(self.sharpness);
+        self.blend(
+            &hit_record.p,
+            Vec3(weight(n.x()), weight(n.y()), weight(n.z())),
+        )
+    }
+}
+
+/// Colors at positions along [0, 1], sorted by position, interpolated
+/// linearly in between and held constant beyond the first and last.
+pub struct ColorRamp {
+    pub stops: Vec<(f64, Vec3)>,
+}
+
+impl ColorRamp {
+    pub fn at(&self, t: f64) -> Vec3 {
+        match self.stops.iter().position(|&(position, _)| position > t) {
+            Some(0) => self.stops[0].1,
+            Some(i) => {
+                let (t0, c0) = self.stops[i - 1];
+                let (t1, c1) = self.stops[i];
+                let f = (t - t0) / (t1 - t0);
+                (1. - f) * c0 + f * c1
+            }
+            None => self.stops.last().map_or(Vec3(0., 0., 0.), |&(_, c)| c),
+        }
+    }
+}
+
+/// What a gradient runs along.
+pub enum GradientKind {
+    /// From 0 at `from` to 1 at `to`, constant across planes perpendicular to
+    /// the line between them.
+    Linear { from: Vec3, to: Vec3 },
+    /// From 0 at `center` to 1 at `radius` from it.
+    Radial { center: Vec3, radius: f64 },
+    /// Along the surface's u coordinate.
+    U,
+    /// Along the surface's v coordinate.
+    V,
+}
+
+/// A ramp of colors laid out in space or over the surface, for skies,
+/// stylized shading and blend masks.
+pub struct GradientTexture {
+    pub ramp: ColorRamp,
+    pub kind: GradientKind,
+}
+
+impl Texture for GradientTexture {
+    fn value(&self, u: f64, v: f64, p: &Vec3) -> Vec3 {
+        let t = match self.kind {
+            GradientKind::Linear { from, to } => {
+                let axis = to - from;
+                (*p - from).dot(axis) / axis.squared_length()
+            }
+            GradientKind::Radial { center, radius } => (*p - center).length() / radius,
+            GradientKind::U => u,
+            GradientKind::V => v,
+        };
+        self.ramp.at(t)
+    }
+}
@@ -0,0 +1,79 @@
+use std::f64::consts::PI;
+
+use crate::vec3::Vec3;
+
+/// Representative wavelengths, in nanometres, for the red, green and blue
+/// channels.
+pub const WAVELENGTHS: [f64; 3] = [650., 532., 450.];
+
+/// What a thin film sits on top of.
+#[derive(Debug, Copy, Clone)]
+pub enum Substrate {
+    /// A transparent base with the given refractive index.
+    Dielectric(f64),
+    /// A metal, described only by its reflectance at normal incidence; its
+    /// reflection is treated as a half-wave phase flip.
+    Conductor(Vec3),
+}
+
+/// A thin transparent coating (soap film, oil, oxide layer) whose reflections
+/// interfere with those of the surface below, giving angle and
+/// thickness-dependent iridescent colors.
+#[derive(Debug, Copy, Clone)]
+pub struct ThinFilm {
+    /// Film thickness in nanometres.
+    pub thickness: f64,
+    pub ior: f64,
+}
+
+/// Fresnel amplitude coefficients (s, p) going from index `n_i` into `n_t`,
+/// plus the cosine of the transmitted angle, or None on total internal
+/// reflection.
+fn amplitudes(n_i: f64, cos_i: f64, n_t: f64) -> Option<(f64, f64, f64)> {
+    let ratio = n_i / n_t;
+    let sin2_t = ratio * ratio * (1. - cos_i * cos_i);
+    if sin2_t >= 1. {
+        return None;
+    }
+    let cos_t = (1. - sin2_t).sqrt();
+    let rs = (n_i * cos_i - n_t * cos_t) / (n_i * cos_i + n_t * cos_t);
+    let rp = (n_t * cos_i - n_i * cos_t) / (n_t * cos_i + n_i * cos_t);
+    Some((rs, rp, cos_t))
+}
+
+/// Reflectance of a layer with real amplitude coefficients `r12` on top and
+/// `r23` below, summed over all internal bounces with phase difference
+/// `delta` (the Airy formula).
+fn airy(r12: f64, r23: f64, delta: f64) -> f64 {
+    let cross = 2. * r12 * r23 * delta.cos();
+    (r12 * r12 + r23 * r23 + cross) / (1. + r12 * r12 * r23 * r23 + cross)
+}
+
+impl ThinFilm {
+    /// Per-channel reflectance of light arriving from air at `cos_i` to the
+    /// film normal, each channel at its wavelength in `wavelengths`:
+    /// `WAVELENGTHS` for RGB.
+    pub fn reflectance(&self, cos_i: f64, substrate: Substrate, wavelengths: [f64; 3]) -> Vec3 {
+        let cos_i = cos_i.clamp(0., 1.);
+        let (rs12, rp12, cos_f) = match amplitudes(1., cos_i, self.ior) {
+            Some(a) => a,
+            None => return Vec3(1., 1., 1.),
+        };
+        let mut channels = [0.; 3];
+        for (i, wavelength) in wavelengths.iter().enumerate() {
+            let (rs23, rp23) = match substrate {
+                Substrate::Dielectric(n) => match amplitudes(self.ior, cos_f, n) {
+                    Some((rs, rp, _)) => (rs, rp),
+                    None => (1., 1.),
+                },
+                Substrate::Conductor(base) => {
+                    let r = -[base.r(), base.g(), base.b()][i].max(0.).sqrt();
+                    (r, r)
+                }
+            };
+            let delta = 4. * PI * self.ior * self.thickness * cos_f / wavelength;
+            channels[i] = (airy(rs12, rs23, delta) + airy(rp12, rp23, delta)) / 2.;
+        }
+        Vec3(channels[0], channels[1], channels[2])
+    }
+}
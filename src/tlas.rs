@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::bvh::Bvh;
+use crate::transform::{Instance, Matrix4};
+use crate::{HitRecord, Hittable, Ray};
+
+/// Two-level acceleration: a top-level BVH over placed instances, each of
+/// which refers to an object with its own bottom-level BVH, like a mesh's
+/// `into_bvh`. Moving, adding or removing an instance only rebuilds the
+/// small top-level tree over instance bounds, never the objects' own trees,
+/// so scenes can be edited or animated cheaply.
+///
+/// Instances must be bounded.
+#[derive(Default)]
+pub struct Tlas {
+    instances: Vec<Arc<Instance>>,
+    top: Option<Bvh>,
+}
+
+impl Tlas {
+    pub fn new() -> Tlas {
+        Tlas::default()
+    }
+
+    pub fn instances(&self) -> &[Arc<Instance>] {
+        &self.instances
+    }
+
+    /// Place another instance and return its index.
+    pub fn add(&mut self, instance: Instance) -> usize {
+        self.instances.push(Arc::new(instance));
+        self.rebuild();
+        self.instances.len() - 1
+    }
+
+    /// Remove the instance at `index`, moving the last one into its place.
+    pub fn remove(&mut self, index: usize) -> Arc<Instance> {
+        let removed = self.instances.swap_remove(index);
+        self.rebuild();
+        removed
+    }
+
+    /// Move the instance at `index` to `matrix`, keeping its object and
+    /// material. The top-level tree is refit rather than rebuilt, which suits
+    /// small moves between animation frames; call `rebuild` after large
+    /// ones.
+    ///
+    /// Panics if `matrix` can't be inverted.
+    pub fn set_transform(&mut self, index: usize, matrix: Matrix4) {
+        let old = &self.instances[index];
+        let mut moved = Instance::new(old.object.clone(), matrix);
+        moved.material = old.material.clone();
+        let moved = Arc::new(moved);
+        self.instances[index] = moved.clone();
+        if let Some(top) = &mut self.top {
+            top.replace(index, Box::new(moved));
+            top.refit();
+        }
+    }
+
+    /// Build the top-level tree afresh.
+    pub fn rebuild(&mut self) {
+        self.top = if self.instances.is_empty() {
+            None
+        } else {
+            let instances = self
+                .instances
+                .iter()
+                .map(|instance| -> Box<dyn Hittable> { Box::new(instance.clone()) })
+                .collect();
+            Some(Bvh::new(instances))
+        };
+    }
+}
+
+impl Hittable for Tlas {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.top.as_ref()?.hit(r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.top.as_ref()?.bounding_box()
+    }
+}
@@ -0,0 +1,143 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::onb::Onb;
+use crate::vec3::Vec3;
+use crate::{face_normal, HitRecord, Hittable, Material, Ray, PIXEL_SPREAD};
+
+/// A ring around `axis` through `center`: the surface swept by a circle of
+/// `minor_radius` whose center runs around a circle of `major_radius`.
+///
+/// u goes around the axis and v around the tube.
+pub struct Torus {
+    pub center: Vec3,
+    pub axis: Vec3,
+    pub major_radius: f64,
+    pub minor_radius: f64,
+    pub material: Arc<dyn Material>,
+}
+
+/// Evaluate a polynomial given its coefficients from the constant term up.
+fn evaluate(coefficients: &[f64], x: f64) -> f64 {
+    coefficients.iter().rev().fold(0., |acc, &c| acc * x + c)
+}
+
+/// Real roots of a polynomial within [lo, hi], in increasing order, given
+/// its coefficients from the constant term up.
+///
+/// The roots of the derivative, found the same way, split the range into
+/// pieces on which the polynomial is monotonic, so each piece holds at most
+/// one root, which bisection then closes in on. Unlike closed-form quartic
+/// formulas this doesn't lose precision to cancellation. Roots where the
+/// polynomial only touches zero, like grazing hits, are missed.
+pub fn real_roots(coefficients: &[f64], lo: f64, hi: f64) -> Vec<f64> {
+    let degree = coefficients.len() - 1;
+    if degree == 1 {
+        let root = -coefficients[0] / coefficients[1];
+        return if root >= lo && root <= hi {
+            vec![root]
+        } else {
+            vec![]
+        };
+    }
+    let derivative: Vec<f64> = coefficients
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, &c)| i as f64 * c)
+        .collect();
+    let mut bounds = vec![lo];
+    bounds.extend(real_roots(&derivative, lo, hi));
+    bounds.push(hi);
+
+    let mut roots = Vec::new();
+    for pair in bounds.windows(2) {
+        let (mut a, mut b) = (pair[0], pair[1]);
+        let fa = evaluate(coefficients, a);
+        if fa.signum() == evaluate(coefficients, b).signum() {
+            continue;
+        }
+        for _ in 0..64 {
+            let mid = 0.5 * (a + b);
+            if evaluate(coefficients, mid).signum() == fa.signum() {
+                a = mid;
+            } else {
+                b = mid;
+            }
+        }
+        roots.push(0.5 * (a + b));
+    }
+    roots
+}
+
+impl Hittable for Torus {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        // in a frame with the axis along z, and along a unit direction so the
+        // quartic stays well scaled
+        let frame = Onb::from_w(&self.axis);
+        let speed = r.direction().length();
+        let o = frame.to_local(&(*r.origin() - self.center));
+        let d = frame.to_local(r.direction()) / speed;
+        let (big, small) = (self.major_radius, self.minor_radius);
+
+        // (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + y^2) with p = o + t d
+        let m = o.dot(o) + big * big - small * small;
+        let n = o.dot(d);
+        let (a, b, c) = (
+            d.x() * d.x() + d.y() * d.y(),
+            o.x() * d.x() + o.y() * d.y(),
+            o.x() * o.x() + o.y() * o.y(),
+        );
+        let four_r2 = 4. * big * big;
+        let coefficients = [
+            m * m - four_r2 * c,
+            4. * n * m - 2. * four_r2 * b,
+            4. * n * n + 2. * m - four_r2 * a,
+            4. * n,
+            1.,
+        ];
+        // nothing lies farther than the far side of the bounding sphere
+        let reach = o.length() + big + small;
+        let lo = (t_min * speed).max(0.);
+        let hi = (t_max * speed).min(reach);
+        if lo >= hi {
+            return None;
+        }
+
+        real_roots(&coefficients, lo, hi)
+            .into_iter()
+            .filter(|&s| s > t_min * speed && s < t_max * speed)
+            .map(|s| {
+                let local = o + s * d;
+                let ring = Vec3(local.x(), local.y(), 0.).unit() * big;
+                let (normal, front_face) = face_normal(r, frame.local(&(local - ring)).unit());
+                let around_axis = local.y().atan2(local.x());
+                let around_tube = local.z().atan2((local - ring).dot(ring) / big);
+                HitRecord {
+                    t: s / speed,
+                    p: r.point_at_parameter(s / speed),
+                    normal,
+                    geometric_normal: normal,
+                    front_face,
+                    tangent: frame.local(&Vec3(-local.y(), local.x(), 0.)),
+                    u: around_axis / (2. * PI) + 0.5,
+                    v: around_tube / (2. * PI) + 0.5,
+                    uv_width: s * PIXEL_SPREAD / (2. * PI * (big + small)),
+                    exterior_ior: 1.,
+                    min_roughness: 0.,
+                    wavelengths: r.wavelengths,
+                    material: &*self.material,
+                }
+            })
+            .find(HitRecord::opaque)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let r = (self.major_radius + self.minor_radius) * Vec3(1., 1., 1.);
+        Some(Aabb {
+            min: self.center - r,
+            max: self.center + r,
+        })
+    }
+}
@@ -0,0 +1,581 @@
+use std::ops::Mul;
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+use crate::{HitRecord, Hittable, Material, Ray};
+
+/// A 4x4 matrix acting on points and directions in homogeneous coordinates,
+/// stored by rows.
+#[derive(Debug, Copy, Clone)]
+pub struct Matrix4(pub [[f64; 4]; 4]);
+
+impl Matrix4 {
+    pub fn identity() -> Matrix4 {
+        Matrix4::scaling(Vec3(1., 1., 1.))
+    }
+
+    pub fn translation(offset: Vec3) -> Matrix4 {
+        Matrix4([
+            [1., 0., 0., offset.x()],
+            [0., 1., 0., offset.y()],
+            [0., 0., 1., offset.z()],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn scaling(factors: Vec3) -> Matrix4 {
+        Matrix4([
+            [factors.x(), 0., 0., 0.],
+            [0., factors.y(), 0., 0.],
+            [0., 0., factors.z(), 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    /// Rotation by `degrees` counterclockwise about `axis`, looking down it
+    /// toward the origin.
+    pub fn rotation(axis: Vec3, degrees: f64) -> Matrix4 {
+        let a = axis.unit();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let k = 1. - cos;
+        let (x, y, z) = (a.x(), a.y(), a.z());
+        Matrix4([
+            [
+                cos + x * x * k,
+                x * y * k - z * sin,
+                x * z * k + y * sin,
+                0.,
+            ],
+            [
+                y * x * k + z * sin,
+                cos + y * y * k,
+                y * z * k - x * sin,
+                0.,
+            ],
+            [
+                z * x * k - y * sin,
+                z * y * k + x * sin,
+                cos + z * z * k,
+                0.,
+            ],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn transpose(&self) -> Matrix4 {
+        let mut m = [[0.; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.0[j][i];
+            }
+        }
+        Matrix4(m)
+    }
+
+    /// The inverse by Gauss-Jordan elimination with partial pivoting, or
+    /// None for a singular matrix.
+    pub fn inverse(&self) -> Option<Matrix4> {
+        let mut a = self.0;
+        let mut inv = Matrix4::identity().0;
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&i, &j| a[i][col].abs().partial_cmp(&a[j][col].abs()).unwrap())
+                .unwrap();
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+            let scale = 1. / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= scale;
+                inv[col][j] *= scale;
+            }
+            for row in 0..4 {
+                if row != col {
+                    let factor = a[row][col];
+                    for j in 0..4 {
+                        a[row][j] -= factor * a[col][j];
+                        inv[row][j] -= factor * inv[col][j];
+                    }
+                }
+            }
+        }
+        Some(Matrix4(inv))
+    }
+
+    /// The determinant of the part acting on directions, the factor by
+    /// which it scales volumes.
+    pub fn determinant(&self) -> f64 {
+        let m = &self.0;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    pub fn transform_point(&self, p: &Vec3) -> Vec3 {
+        let m = &self.0;
+        let w = m[3][0] * p.x() + m[3][1] * p.y() + m[3][2] * p.z() + m[3][3];
+        (self.transform_vector(p) + Vec3(m[0][3], m[1][3], m[2][3])) / w
+    }
+
+    /// Transform a direction, which translation doesn't affect.
+    pub fn transform_vector(&self, v: &Vec3) -> Vec3 {
+        let m = &self.0;
+        Vec3(
+            m[0][0] * v.x() + m[0][1] * v.y() + m[0][2] * v.z(),
+            m[1][0] * v.x() + m[1][1] * v.y() + m[1][2] * v.z(),
+            m[2][0] * v.x() + m[2][1] * v.y() + m[2][2] * v.z(),
+        )
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Matrix4;
+
+    /// The transform applying `other` first, then `self`.
+    fn mul(self, other: Matrix4) -> Matrix4 {
+        let mut m = [[0.; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.0[i][k] * other.0[k][j]).sum();
+            }
+        }
+        Matrix4(m)
+    }
+}
+
+/// Places an object with an arbitrary affine transform from its own space to
+/// the world: rays are taken into object space to be intersected and hits
+/// brought back out, normals by the inverse transpose. Sampled as a light,
+/// the object's densities are scaled by how the transform stretches solid
+/// angles and areas.
+pub struct Transformed {
+    pub object: Box<dyn Hittable>,
+    pub matrix: Matrix4,
+    pub inverse: Matrix4,
+}
+
+impl Transformed {
+    /// Panics if `matrix` can't be inverted.
+    pub fn new(object: Box<dyn Hittable>, matrix: Matrix4) -> Transformed {
+        Transformed {
+            object,
+            matrix,
+            inverse: matrix.inverse().expect("transform must be invertible"),
+        }
+    }
+}
+
+impl Hittable for Transformed {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        hit_transformed(&*self.object, &self.matrix, &self.inverse, r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.object.bounding_box()?.transformed(&self.matrix))
+    }
+
+    fn clipped_box(&self, clip: &Aabb) -> Option<Aabb> {
+        clipped_transformed(&*self.object, &self.matrix, &self.inverse, clip)
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        pdf_transformed(
+            &*self.object,
+            &self.matrix,
+            &self.inverse,
+            origin,
+            direction,
+        )
+    }
+
+    fn random(&self, origin: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let local = self
+            .object
+            .random(&self.inverse.transform_point(origin), sampler);
+        self.matrix.transform_vector(&local)
+    }
+
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(HitRecord<'_>, f64)> {
+        sample_transformed(&*self.object, &self.matrix, &self.inverse, sampler)
+    }
+
+    fn surface_pdf(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        surface_pdf_transformed(
+            &*self.object,
+            &self.matrix,
+            &self.inverse,
+            origin,
+            direction,
+        )
+    }
+}
+
+fn hit_transformed<'a>(
+    object: &'a dyn Hittable,
+    matrix: &Matrix4,
+    inverse: &Matrix4,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    // the direction keeps its scale, so t means the same in both spaces
+    let local = Ray {
+        a: inverse.transform_point(r.origin()),
+        b: inverse.transform_vector(r.direction()),
+        time: r.time(),
+        wavelengths: r.wavelengths,
+    };
+    Some(place(object.hit(&local, t_min, t_max)?, matrix, inverse))
+}
+
+/// `hit_record` brought out of object space into the world.
+fn place<'a>(mut hit_record: HitRecord<'a>, matrix: &Matrix4, inverse: &Matrix4) -> HitRecord<'a> {
+    let normal_matrix = inverse.transpose();
+    hit_record.p = matrix.transform_point(&hit_record.p);
+    hit_record.normal = normal_matrix.transform_vector(&hit_record.normal).unit();
+    hit_record.geometric_normal = normal_matrix
+        .transform_vector(&hit_record.geometric_normal)
+        .unit();
+    hit_record.tangent = matrix.transform_vector(&hit_record.tangent);
+    hit_record
+}
+
+/// The part of the object inside the box around `clip` taken into object
+/// space, brought back out and cut down to `clip`.
+fn clipped_transformed(
+    object: &dyn Hittable,
+    matrix: &Matrix4,
+    inverse: &Matrix4,
+    clip: &Aabb,
+) -> Option<Aabb> {
+    let local = object.clipped_box(&clip.transformed(inverse))?;
+    Aabb::intersection(&local.transformed(matrix), clip)
+}
+
+/// The object's density for the direction in object space, per unit of
+/// the solid angle there, times the solid angle there per unit of the
+/// solid angle in the world: the cube of how much the transform stretches
+/// the direction over how much it scales volumes.
+fn pdf_transformed(
+    object: &dyn Hittable,
+    matrix: &Matrix4,
+    inverse: &Matrix4,
+    origin: &Vec3,
+    direction: &Vec3,
+) -> f64 {
+    let local = inverse.transform_vector(direction).unit();
+    let stretch = matrix.transform_vector(&local).length();
+    object.pdf_value(&inverse.transform_point(origin), &local) * stretch.powi(3)
+        / matrix.determinant().abs()
+}
+
+/// A point picked on the object in object space and brought out, its
+/// density per unit area divided by how much the transform scales areas
+/// across the surface there.
+fn sample_transformed<'a>(
+    object: &'a dyn Hittable,
+    matrix: &Matrix4,
+    inverse: &Matrix4,
+    sampler: &mut dyn Sampler,
+) -> Option<(HitRecord<'a>, f64)> {
+    let (hit_record, density) = object.sample_surface(sampler)?;
+    let normal = inverse
+        .transpose()
+        .transform_vector(&hit_record.geometric_normal);
+    let scale = matrix.determinant().abs() * normal.length();
+    Some((place(hit_record, matrix, inverse), density / scale))
+}
+
+/// `surface_pdf` of the object for the ray brought into object space,
+/// divided by how much the transform scales areas across the surface where
+/// the ray meets it, as `sample_transformed` does.
+fn surface_pdf_transformed(
+    object: &dyn Hittable,
+    matrix: &Matrix4,
+    inverse: &Matrix4,
+    origin: &Vec3,
+    direction: &Vec3,
+) -> f64 {
+    let (origin, direction) = (
+        inverse.transform_point(origin),
+        inverse.transform_vector(direction),
+    );
+    let ray = Ray {
+        a: origin,
+        b: direction,
+        time: 0.,
+        wavelengths: None,
+    };
+    match object.hit(&ray, 0.0001, f64::MAX) {
+        Some(hit_record) => {
+            let normal = inverse
+                .transpose()
+                .transform_vector(&hit_record.geometric_normal.unit());
+            let scale = matrix.determinant().abs() * normal.length();
+            object.surface_pdf(&origin, &direction) / scale
+        }
+        None => 0.,
+    }
+}
+
+/// One placed copy of a shared object, such as a mesh or a whole hierarchy,
+/// so a forest needs only one tree in memory. Each copy has its own
+/// transform, and can be given its own `material` in place of the object's.
+/// Copies of lights are sampled as lights as `Transformed` ones are.
+pub struct Instance {
+    pub object: Arc<dyn Hittable>,
+    pub matrix: Matrix4,
+    pub inverse: Matrix4,
+    pub material: Option<Arc<dyn Material>>,
+}
+
+impl Instance {
+    /// Panics if `matrix` can't be inverted.
+    pub fn new(object: Arc<dyn Hittable>, matrix: Matrix4) -> Instance {
+        Instance {
+            object,
+            matrix,
+            inverse: matrix.inverse().expect("transform must be invertible"),
+            material: None,
+        }
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mut hit_record =
+            hit_transformed(&*self.object, &self.matrix, &self.inverse, r, t_min, t_max)?;
+        if let Some(material) = &self.material {
+            hit_record.material = &**material;
+        }
+        Some(hit_record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.object.bounding_box()?.transformed(&self.matrix))
+    }
+
+    fn clipped_box(&self, clip: &Aabb) -> Option<Aabb> {
+        clipped_transformed(&*self.object, &self.matrix, &self.inverse, clip)
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        pdf_transformed(
+            &*self.object,
+            &self.matrix,
+            &self.inverse,
+            origin,
+            direction,
+        )
+    }
+
+    fn random(&self, origin: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let local = self
+            .object
+            .random(&self.inverse.transform_point(origin), sampler);
+        self.matrix.transform_vector(&local)
+    }
+
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(HitRecord<'_>, f64)> {
+        let (mut hit_record, density) =
+            sample_transformed(&*self.object, &self.matrix, &self.inverse, sampler)?;
+        if let Some(material) = &self.material {
+            hit_record.material = &**material;
+        }
+        Some((hit_record, density))
+    }
+
+    fn surface_pdf(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        surface_pdf_transformed(
+            &*self.object,
+            &self.matrix,
+            &self.inverse,
+            origin,
+            direction,
+        )
+    }
+}
+
+/// `b` moved by `offset`.
+fn shifted(b: &Aabb, offset: Vec3) -> Aabb {
+    Aabb {
+        min: b.min + offset,
+        max: b.max + offset,
+    }
+}
+
+/// Moves an object by `offset`.
+pub struct Translate {
+    pub object: Box<dyn Hittable>,
+    pub offset: Vec3,
+}
+
+impl Hittable for Translate {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let moved = Ray {
+            a: *r.origin() - self.offset,
+            b: *r.direction(),
+            time: r.time(),
+            wavelengths: r.wavelengths,
+        };
+        let mut hit_record = self.object.hit(&moved, t_min, t_max)?;
+        hit_record.p = hit_record.p + self.offset;
+        Some(hit_record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(shifted(&self.object.bounding_box()?, self.offset))
+    }
+
+    fn clipped_box(&self, clip: &Aabb) -> Option<Aabb> {
+        let inner = self.object.clipped_box(&shifted(clip, -self.offset))?;
+        Some(shifted(&inner, self.offset))
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        self.object.pdf_value(&(*origin - self.offset), direction)
+    }
+
+    fn random(&self, origin: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        self.object.random(&(*origin - self.offset), sampler)
+    }
+
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(HitRecord<'_>, f64)> {
+        let (mut hit_record, density) = self.object.sample_surface(sampler)?;
+        hit_record.p = hit_record.p + self.offset;
+        Some((hit_record, density))
+    }
+
+    fn surface_pdf(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        self.object.surface_pdf(&(*origin - self.offset), direction)
+    }
+}
+
+/// Moves an object by `velocity` per unit of time, from where it is at time
+/// zero, so it blurs over a camera's shutter interval. Its box covers the
+/// times from zero to one, the interval shutters are expected to stay
+/// within. Sampled as a light, it is where it is at time zero, light
+/// sampling knowing nothing of time.
+pub struct Moving {
+    pub object: Box<dyn Hittable>,
+    pub velocity: Vec3,
+}
+
+impl Hittable for Moving {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let offset = r.time() * self.velocity;
+        let moved = Ray {
+            a: *r.origin() - offset,
+            b: *r.direction(),
+            time: r.time(),
+            wavelengths: r.wavelengths,
+        };
+        let mut hit_record = self.object.hit(&moved, t_min, t_max)?;
+        hit_record.p = hit_record.p + offset;
+        Some(hit_record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let start = self.object.bounding_box()?;
+        Some(Aabb::surrounding(&start, &shifted(&start, self.velocity)))
+    }
+
+    /// The parts of the object that pass through `clip` at some time, over
+    /// all the times they are there, cut down to `clip`.
+    fn clipped_box(&self, clip: &Aabb) -> Option<Aabb> {
+        let swept = Aabb::surrounding(clip, &shifted(clip, -self.velocity));
+        let start = self.object.clipped_box(&swept)?;
+        let moved = Aabb::surrounding(&start, &shifted(&start, self.velocity));
+        Aabb::intersection(&moved, clip)
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        self.object.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        self.object.random(origin, sampler)
+    }
+
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(HitRecord<'_>, f64)> {
+        self.object.sample_surface(sampler)
+    }
+
+    fn surface_pdf(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        self.object.surface_pdf(origin, direction)
+    }
+}
+
+/// Turns an object by `degrees` about the y axis, in the same sense as
+/// `Matrix4::rotation`.
+pub struct RotateY {
+    pub object: Box<dyn Hittable>,
+    pub degrees: f64,
+}
+
+impl RotateY {
+    /// Rotate `v` by `sign` times the angle.
+    fn rotate(&self, v: &Vec3, sign: f64) -> Vec3 {
+        let (sin, cos) = (sign * self.degrees).to_radians().sin_cos();
+        Vec3(cos * v.x() + sin * v.z(), v.y(), -sin * v.x() + cos * v.z())
+    }
+
+    /// The box around `b` turned by `sign` times the angle.
+    fn rotate_box(&self, b: &Aabb, sign: f64) -> Aabb {
+        let turned: Vec<Vec3> = b.corners().iter().map(|c| self.rotate(c, sign)).collect();
+        Aabb::from_points(&turned)
+    }
+
+    /// `hit_record` turned out of the object's space into the world.
+    fn turn<'a>(&self, mut hit_record: HitRecord<'a>) -> HitRecord<'a> {
+        hit_record.p = self.rotate(&hit_record.p, 1.);
+        hit_record.normal = self.rotate(&hit_record.normal, 1.);
+        hit_record.geometric_normal = self.rotate(&hit_record.geometric_normal, 1.);
+        hit_record.tangent = self.rotate(&hit_record.tangent, 1.);
+        hit_record
+    }
+}
+
+impl Hittable for RotateY {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let turned = Ray {
+            a: self.rotate(r.origin(), -1.),
+            b: self.rotate(r.direction(), -1.),
+            time: r.time(),
+            wavelengths: r.wavelengths,
+        };
+        Some(self.turn(self.object.hit(&turned, t_min, t_max)?))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.rotate_box(&self.object.bounding_box()?, 1.))
+    }
+
+    fn clipped_box(&self, clip: &Aabb) -> Option<Aabb> {
+        let inner = self.object.clipped_box(&self.rotate_box(clip, -1.))?;
+        Aabb::intersection(&self.rotate_box(&inner, 1.), clip)
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        self.object
+            .pdf_value(&self.rotate(origin, -1.), &self.rotate(direction, -1.))
+    }
+
+    fn random(&self, origin: &Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        self.rotate(&self.object.random(&self.rotate(origin, -1.), sampler), 1.)
+    }
+
+    fn sample_surface(&self, sampler: &mut dyn Sampler) -> Option<(HitRecord<'_>, f64)> {
+        let (hit_record, density) = self.object.sample_surface(sampler)?;
+        Some((self.turn(hit_record), density))
+    }
+
+    fn surface_pdf(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        self.object
+            .surface_pdf(&self.rotate(origin, -1.), &self.rotate(direction, -1.))
+    }
+}
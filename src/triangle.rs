@@ -0,0 +1,177 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::vec3::Vec3;
+use crate::{face_normal, HitRecord, Hittable, Material, Ray, PIXEL_SPREAD};
+
+/// A single triangle, intersected with the Möller-Trumbore algorithm.
+///
+/// Vertex normals, when given, are interpolated across the face for smooth
+/// shading while the flat face normal remains the geometric normal. Surface
+/// coordinates are interpolated from `uvs` the same way.
+pub struct Triangle {
+    pub vertices: [Vec3; 3],
+    pub normals: Option<[Vec3; 3]>,
+    pub uvs: [(f64, f64); 3],
+    pub material: Arc<dyn Material>,
+}
+
+impl Triangle {
+    /// A flat-shaded triangle whose (u, v) run along its first two edges.
+    pub fn new(a: Vec3, b: Vec3, c: Vec3, material: Arc<dyn Material>) -> Triangle {
+        Triangle {
+            vertices: [a, b, c],
+            normals: None,
+            uvs: [(0., 0.), (1., 0.), (0., 1.)],
+            material,
+        }
+    }
+}
+
+/// Where a ray crosses the triangle `a b c`: the ray parameter and the
+/// barycentric weights of `b` and `c`.
+pub fn intersect(r: &Ray, a: Vec3, b: Vec3, c: Vec3) -> Option<(f64, f64, f64)> {
+    let e1 = b - a;
+    let e2 = c - a;
+    let pvec = r.direction().cross(e2);
+    let det = e1.dot(pvec);
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = 1. / det;
+    let tvec = *r.origin() - a;
+    let beta = tvec.dot(pvec) * inv_det;
+    if !(0. ..=1.).contains(&beta) {
+        return None;
+    }
+    let qvec = tvec.cross(e1);
+    let gamma = r.direction().dot(qvec) * inv_det;
+    if gamma < 0. || beta + gamma > 1. {
+        return None;
+    }
+    Some((e2.dot(qvec) * inv_det, beta, gamma))
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let [a, b, c] = self.vertices;
+        let (t, beta, gamma) = intersect(r, a, b, c)?;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+        let hit_record = hit_record(
+            r,
+            t,
+            (beta, gamma),
+            self.vertices,
+            self.normals,
+            self.uvs,
+            &*self.material,
+        );
+        Some(hit_record).filter(HitRecord::opaque)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::from_points(&self.vertices))
+    }
+
+    fn clipped_box(&self, clip: &Aabb) -> Option<Aabb> {
+        clipped_box(self.vertices, clip)
+    }
+}
+
+fn axis(v: &Vec3, i: usize) -> f64 {
+    match i {
+        0 => v.x(),
+        1 => v.y(),
+        _ => v.z(),
+    }
+}
+
+/// The box around the part of a triangle inside `clip`, found by cutting
+/// the triangle down by each of the box's faces in turn.
+pub fn clipped_box(vertices: [Vec3; 3], clip: &Aabb) -> Option<Aabb> {
+    let mut polygon = vertices.to_vec();
+    for i in 0..3 {
+        // signed distances inside the lower face, then the upper one
+        for &(bound, sign) in &[(axis(&clip.min, i), 1.), (axis(&clip.max, i), -1.)] {
+            let inside = |p: &Vec3| sign * (axis(p, i) - bound);
+            let mut cut = Vec::with_capacity(polygon.len() + 1);
+            for (k, p) in polygon.iter().enumerate() {
+                let q = &polygon[(k + 1) % polygon.len()];
+                let (dp, dq) = (inside(p), inside(q));
+                if dp >= 0. {
+                    cut.push(*p);
+                }
+                if (dp >= 0.) != (dq >= 0.) {
+                    cut.push(*p + dp / (dp - dq) * (*q - *p));
+                }
+            }
+            if cut.is_empty() {
+                return None;
+            }
+            polygon = cut;
+        }
+    }
+    Some(Aabb::from_points(&polygon))
+}
+
+/// The full record of a hit at `t` on a triangle, at barycentric weights
+/// `(beta, gamma)` of its second and third vertices.
+pub fn hit_record<'a>(
+    r: &Ray,
+    t: f64,
+    (beta, gamma): (f64, f64),
+    [a, b, c]: [Vec3; 3],
+    normals: Option<[Vec3; 3]>,
+    [(u0, v0), (u1, v1), (u2, v2)]: [(f64, f64); 3],
+    material: &'a dyn Material,
+) -> HitRecord<'a> {
+    let alpha = 1. - beta - gamma;
+    let e1 = b - a;
+    let e2 = c - a;
+    let (geometric_normal, front_face) = face_normal(r, e1.cross(e2).unit());
+    let normal = match normals {
+        Some([na, nb, nc]) => {
+            let n = (alpha * na + beta * nb + gamma * nc).unit();
+            if front_face {
+                n
+            } else {
+                -n
+            }
+        }
+        None => geometric_normal,
+    };
+
+    let (du1, dv1) = (u1 - u0, v1 - v0);
+    let (du2, dv2) = (u2 - u0, v2 - v0);
+    let uv_det = du1 * dv2 - du2 * dv1;
+    // dp/du, or any edge if the coordinates are degenerate
+    let tangent = if uv_det.abs() > 1e-12 {
+        (dv2 * e1 - dv1 * e2) / uv_det
+    } else {
+        e1
+    };
+    // world length per unit of u, taking the mapping as roughly uniform
+    let scale = if uv_det.abs() > 1e-12 {
+        (e1.cross(e2).length() / uv_det.abs()).sqrt()
+    } else {
+        1.
+    };
+
+    HitRecord {
+        t,
+        p: r.point_at_parameter(t),
+        normal,
+        geometric_normal,
+        front_face,
+        tangent,
+        u: alpha * u0 + beta * u1 + gamma * u2,
+        v: alpha * v0 + beta * v1 + gamma * v2,
+        uv_width: t * r.direction().length() * PIXEL_SPREAD / scale,
+        exterior_ior: 1.,
+        min_roughness: 0.,
+        wavelengths: r.wavelengths,
+        material,
+    }
+}
@@ -23,8 +23,18 @@ impl Vec3 {
         self.2
     }
 
+    /// Perceived brightness of an RGB color (Rec. 709 weights).
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.0 + 0.7152 * self.1 + 0.0722 * self.2
+    }
+
+    /// The mean of the three components.
+    pub fn average(&self) -> f64 {
+        (self.0 + self.1 + self.2) / 3.
+    }
+
     pub fn squared_length(&self) -> f64 {
-        (self.0 * self.0 + self.1 * self.1 + self.2 * self.2)
+        self.0 * self.0 + self.1 * self.1 + self.2 * self.2
     }
 
     pub fn length(&self) -> f64 {
@@ -36,10 +46,49 @@ impl Vec3 {
     pub fn dot(&self, other: Self) -> f64 {
         self.0 * other.0 + self.1 * other.1 + self.2 * other.2
     }
+    pub fn cross(&self, other: Self) -> Self {
+        Vec3(
+            self.1 * other.2 - self.2 * other.1,
+            self.2 * other.0 - self.0 * other.2,
+            self.0 * other.1 - self.1 * other.0,
+        )
+    }
+
+    /// The smaller of each pair of components.
+    pub fn min(&self, other: Self) -> Self {
+        Vec3(
+            self.0.min(other.0),
+            self.1.min(other.1),
+            self.2.min(other.2),
+        )
+    }
+
+    /// The larger of each pair of components.
+    pub fn max(&self, other: Self) -> Self {
+        Vec3(
+            self.0.max(other.0),
+            self.1.max(other.1),
+            self.2.max(other.2),
+        )
+    }
 
     pub fn reflect(&self, normal: &Vec3) -> Vec3 {
         *self - 2. * self.dot(*normal) * *normal
     }
+
+    /// Refract through a surface with the given normal per Snell's law, where
+    /// `ni_over_nt` is the ratio of the refractive indices on either side.
+    /// Returns None on total internal reflection.
+    pub fn refract(&self, normal: &Vec3, ni_over_nt: f64) -> Option<Vec3> {
+        let uv = self.unit();
+        let dt = uv.dot(*normal);
+        let discriminant = 1.0 - ni_over_nt * ni_over_nt * (1.0 - dt * dt);
+        if discriminant > 0. {
+            Some(ni_over_nt * (uv - *normal * dt) - *normal * discriminant.sqrt())
+        } else {
+            None
+        }
+    }
 }
 
 impl ops::Add for Vec3 {
@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::pdf::{Pdf, SpherePdf};
+use crate::sampler::{random_at, Sampler};
+use crate::texture::Texture;
+use crate::vec3::Vec3;
+use crate::{HitRecord, Hittable, Material, Ray, Scatter};
+
+/// Smoke, mist or fog filling the inside of `boundary`, evenly dense, like
+/// the smoke boxes of "Ray Tracing: The Next Week". A ray crossing it
+/// travels a distance drawn by how likely light is to get that far through
+/// it before scattering, and hits it there if that is still inside, so thin
+/// smoke lets most rays through and thick smoke turns them early. Where it
+/// is hit, `phase_function` says how the light scatters, which is usually
+/// `Isotropic`.
+///
+/// The boundary must be convex, like a sphere or a box: rays are taken to
+/// be inside it from where they first cross it to where they next do.
+pub struct ConstantMedium {
+    pub boundary: Box<dyn Hittable>,
+    /// How likely light is to scatter per unit distance.
+    pub density: f64,
+    pub phase_function: Arc<dyn Material>,
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        // where the ray crosses the boundary, from behind its start too, so
+        // rays starting inside are inside from their start
+        let enter = self.boundary.hit(r, f64::MIN, f64::MAX)?.t;
+        let exit = self.boundary.hit(r, enter + 0.0001, f64::MAX)?.t;
+        let (enter, exit) = (enter.max(t_min), exit.min(t_max));
+        if enter >= exit {
+            return None;
+        }
+        let speed = r.direction().length();
+        // a number of its own for each ray, there being no sampler to hand
+        let u = random_at(&r.point_at_parameter(enter));
+        let distance = -(1. - u).ln() / self.density;
+        if distance >= (exit - enter) * speed {
+            return None;
+        }
+        let t = enter + distance / speed;
+        // facing back along the ray, so light scattering on the way it was
+        // going counts as going through
+        let normal = -r.direction().unit();
+        Some(HitRecord {
+            t,
+            p: r.point_at_parameter(t),
+            normal,
+            geometric_normal: normal,
+            front_face: true,
+            tangent: Vec3(1., 0., 0.),
+            u: 0.,
+            v: 0.,
+            uv_width: 0.,
+            exterior_ior: 1.,
+            min_roughness: 0.,
+            wavelengths: r.wavelengths,
+            material: &*self.phase_function,
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.boundary.bounding_box()
+    }
+}
+
+/// Scatters light evenly in every direction, whichever way it arrived, as
+/// smoke and fog roughly do; `albedo` is the share of it not absorbed.
+pub struct Isotropic {
+    pub albedo: Box<dyn Texture>,
+}
+
+impl Material for Isotropic {
+    fn scatter(
+        &self,
+        r: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<Scatter> {
+        Scatter::sample(self, r, hit_record, sampler)
+    }
+
+    fn pdf(&self, _r: &Ray, _hit_record: &HitRecord) -> Option<Box<dyn Pdf>> {
+        Some(Box::new(SpherePdf))
+    }
+
+    fn eval(&self, _r: &Ray, hit_record: &HitRecord, _direction: &Vec3) -> Vec3 {
+        hit_record.texture(&*self.albedo) / (4. * std::f64::consts::PI)
+    }
+}